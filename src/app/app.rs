@@ -2,24 +2,342 @@
 //!
 //! This consists of the main implementation logic, excluding event handling
 
-use core::iter;
-use std::rc::Rc;
+use core::{cell::OnceCell, error::Error, fmt, iter, mem, time::Duration};
+use std::{collections::HashMap, sync::Arc};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    fs,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
 
-use wgpu::{LoadOp, StoreOp, SurfaceError};
-use winit::{dpi, window::Window};
+#[cfg(not(target_arch = "wasm32"))]
+use super::capture::{self, CaptureError};
+#[cfg(target_arch = "wasm32")]
+use super::pointer_lock::PointerLockEvents;
+#[cfg(any(test, not(target_arch = "wasm32")))]
+use super::readback;
+
+use super::{
+    bounds::BoundsOverlay,
+    camera::{Camera, CameraBinding, CameraController, OrthographicCamera},
+    cloth::ClothSim,
+    collider::{Collider, ColliderOverlay, OutlineStyle},
+    color, coloring,
+    config::{RedrawPolicy, SimulationConfig},
+    device::{
+        particles_supported, request_adapter, request_device, select_msaa_samples,
+        select_surface_format, supported_msaa_samples, DEPTH_FORMAT,
+    },
+    diagnostics::{self, DiagnosticsWindow},
+    emitter::Emitter,
+    frame_timer::{self, FrameTimer},
+    gamma::GammaHandling,
+    gpu_timer::GpuTimer,
+    gradient::Gradient,
+    grid::{self, SpatialGrid},
+    gui::{Controls, Gui},
+    hud::{self, Hud},
+    keys::{self, Action},
+    metrics::Metrics,
+    particles::{BoundaryMode, Environment, ForceField, Gravitation, Particle, SizeDistribution},
+    pointer_lock::{self, PointerLock},
+    recording::{InputEvent, InputRecorder, Recording, Replay},
+    simulation::{Integrator, ParticleSim, ParticleStyle, Simulation, SimulationContext},
+    spawn::SpawnPattern,
+    sph::{FluidParameters, SphSim},
+    stage::{self, RenderStage, Scene},
+    state::SimulationState,
+    theme::Theme,
+    trails::{self, Trails},
+};
+#[cfg(not(target_arch = "wasm32"))]
+use log::error;
+use log::{info, log_enabled, warn, Level};
+use rand::{rngs::StdRng, SeedableRng as _};
+use wgpu::SurfaceError;
+use winit::{
+    dpi,
+    event::{ElementState, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent},
+    keyboard::{KeyCode, ModifiersState},
+    window::Window,
+};
+
+/// The fixed amount of simulated time advanced by each physics step (1/120th of a second)
+const TIMESTEP: Duration = Duration::from_nanos(8_333_333);
+
+/// The most times faster than real time that the simulation may run
+const MAX_TIME_SCALE: f32 = 16.0;
+
+/// The default height of the floor that particles bounce off
+const DEFAULT_FLOOR: f32 = -1.0;
+
+/// The default maximum number of particles that may exist at once
+const MAX_PARTICLES: usize = 4096;
+
+/// The numbers of frames that may be queued for presentation ahead of the display
+const FRAME_LATENCY_RANGE: (u32, u32) = (1, 3);
+
+/// The acceleration the cursor's force field applies at unit distance
+const FORCE_FIELD_STRENGTH: f32 = 0.05;
+
+/// The number of particles added or removed at a time from the keyboard
+const PARTICLE_STEP: usize = 100;
+
+/// The gravitational constant of the attraction between particles, when turned on
+const GRAVITATIONAL_CONSTANT: f32 = 2.5e-4;
+
+/// The most air resistance that may slow particles, as a rate per second
+const MAX_DAMPING: f32 = 10.0;
+
+/// The default Barnes–Hut opening angle for approximating gravitation between particles
+const DEFAULT_THETA: f32 = 0.5;
+
+/// The opacity the background is drawn over the previous frame with when trails are turned on
+const DEFAULT_TRAIL_FADE: f32 = 0.1;
+
+/// The number of masses along each side of the cloth simulation
+const CLOTH_SIZE: usize = 20;
+
+/// The stiffness of the springs in the cloth simulation
+const CLOTH_STIFFNESS: f32 = 500.0;
+
+/// The side length of the cells that particles are bucketed into to find those near a point
+const QUERY_CELL_SIZE: f32 = 0.05;
+
+/// How close a click must be to a particle's center to select it, in pixels
+const SELECTION_RADIUS_PIXELS: f64 = 8.0;
+
+/// The size of the highlight around the selected particle, relative to the particle
+const SELECTION_OUTLINE_SCALE: f32 = 1.5;
+
+/// The factor the flat view is magnified by per line scrolled
+const ZOOM_PER_LINE: f32 = 1.1;
+
+/// The number of pixels scrolled by touchpads that corresponds to scrolling one line
+const PIXELS_PER_LINE: f64 = 40.0;
+
+/// The smallest and largest zooms reachable by scrolling
+const ZOOM_RANGE: (f32, f32) = (0.1, 100.0);
+
+/// How far the camera flies per second
+const CAMERA_SPEED: f32 = 1.0;
+
+/// How far the camera turns per unit of mouse motion, in radians
+const MOUSE_SENSITIVITY: f32 = 0.003;
+
+/// The format of the texture rendered to when rendering offscreen
+#[cfg(test)]
+const OFFSCREEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// Where the application's frames are rendered to
+enum RenderTarget<'app> {
+    /// A surface presenting to a window
+    Window {
+        /// The surface to render to
+        surface: wgpu::Surface<'app>,
+        /// The window the surface presents to
+        window: Arc<Window>,
+        /// The instance that the surface was created from, for creating surfaces for other windows
+        #[cfg(not(target_arch = "wasm32"))]
+        instance: wgpu::Instance,
+        /// The adapter that the device was requested from, for configuring other surfaces
+        adapter: wgpu::Adapter,
+    },
+    /// A texture that is read back after rendering, for testing rendering without a window
+    #[cfg(test)]
+    Offscreen(wgpu::Texture),
+}
+
+/// Which of the application's cameras the simulation is viewed through
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CameraMode {
+    /// Looking straight down onto the simulation, which appears flat
+    Orthographic,
+    /// Flying freely around the simulation in perspective
+    Perspective,
+}
 
 /// The main application struct, managing the rendering process and all application state
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "Each flag is toggled independently of the others"
+)]
 pub(super) struct Application<'app> {
-    /// The surface to render to
-    surface: wgpu::Surface<'app>,
+    /// Where frames are rendered to. Declared first so that the surface is dropped before the
+    /// device
+    target: RenderTarget<'app>,
     /// The device doing the rendering and computation (e.g. GPU)
-    device: wgpu::Device,
+    device: Arc<wgpu::Device>,
     /// The queue of commands for the device
-    queue: wgpu::Queue,
-    /// Configuration for the surface
+    queue: Arc<wgpu::Queue>,
+    /// The optional features enabled on `device`
+    features: wgpu::Features,
+    /// Configuration for the surface. When rendering offscreen, describes the target texture
     config: wgpu::SurfaceConfiguration,
-    /// The window to render to
-    window: Rc<Window>,
+    /// The number of samples per pixel rendered. Anti-aliasing is enabled if this is more than 1
+    msaa_samples: u32,
+    /// How colors are encoded for display on the target
+    gamma: GammaHandling,
+    /// The multisampled color buffer and a view into it, matching the size of the surface.
+    /// Resolved into the surface texture after rendering. `None` if anti-aliasing is disabled
+    msaa_texture: Option<(wgpu::Texture, wgpu::TextureView)>,
+    /// The depth buffer, matching the size of the surface
+    depth_texture: wgpu::Texture,
+    /// View into `depth_texture` used as the render pass's depth attachment
+    depth_view: wgpu::TextureView,
+    /// Which camera the simulation is currently viewed through
+    camera_mode: CameraMode,
+    /// The camera viewing the simulation flat on
+    ortho_camera: OrthographicCamera,
+    /// The camera viewing the simulation in perspective
+    camera: Camera,
+    /// Exposes the current camera to the shaders
+    camera_binding: CameraBinding,
+    /// Flies `camera` around in response to input
+    camera_controller: CameraController,
+    /// Whether the cursor is captured for looking around with the mouse
+    pointer_lock: PointerLock,
+    /// Reports the browser's answers to requests to capture the cursor
+    #[cfg(target_arch = "wasm32")]
+    pointer_lock_events: Option<PointerLockEvents>,
+    /// The physics being simulated
+    simulation: Box<dyn Simulation>,
+    /// The color the surface is cleared to before drawing
+    background: wgpu::Color,
+    /// Elapsed wall time that has not yet been simulated
+    accumulator: Duration,
+    /// The most steps taken within a single frame at normal speed, beyond which elapsed time is
+    /// dropped. Always at least one
+    max_steps_per_frame: u32,
+    /// The time of the last call to `update`
+    last_update: Instant,
+    /// When the window is redrawn
+    redraw_policy: RedrawPolicy,
+    /// Whether something changed since the last frame that should be shown, even if nothing is
+    /// moving
+    changed: bool,
+    /// Whether redrawing stopped after the last frame, so that the time until the next is not
+    /// simulated
+    idle: bool,
+    /// Whether the simulation has been paused deliberately. While paused, the application still
+    /// renders
+    paused: bool,
+    /// Whether the window has lost focus
+    unfocused: bool,
+    /// How many times faster than real time the simulation runs
+    time_scale: f32,
+    /// Whether the simulation is also paused while the window is unfocused
+    pause_on_focus_loss: bool,
+    /// The configuration the simulation started with, with the seed it was actually started
+    /// with, which resetting restores
+    initial_config: SimulationConfig,
+    /// The global forces and boundaries acting on the particles
+    environment: Environment,
+    /// The width and height of the world that particles are kept within, in meters, or `None` if
+    /// the world is whatever the flat view shows
+    world_size: Option<[f32; 2]>,
+    /// The source of all randomness in the simulation
+    rng: StdRng,
+    /// The number of particles spawned when restarting the particle simulation
+    particle_count: usize,
+    /// The distribution that the radii of spawned particles are drawn from
+    particle_sizes: SizeDistribution,
+    /// How the particles are arranged when restarting the particle simulation
+    spawn_pattern: SpawnPattern,
+    /// The sources continuously adding particles to the simulation, each step
+    emitters: Vec<Emitter>,
+    /// Buckets the current particles by position to find those near a point, built by the first
+    /// query after the particles change
+    particle_grid: OnceCell<SpatialGrid>,
+    /// The most particles that may exist at once. Spawning beyond this replaces the oldest
+    /// particle
+    max_particles: usize,
+    /// The scheme the simulation is advanced through time with, where supported
+    integrator: Integrator,
+    /// The last known position of the cursor within the window, if any
+    cursor: Option<dpi::PhysicalPosition<f64>>,
+    /// The point on the plane grabbed when dragging the flat view, which stays under the cursor
+    /// or touches. `None` if not dragging
+    drag_anchor: Option<[f32; 2]>,
+    /// The last known position of each finger touching the screen, by touch id
+    touches: HashMap<u64, dpi::PhysicalPosition<f64>>,
+    /// Times recent frames to measure the frame rate
+    frame_timer: FrameTimer,
+    /// The CPU time spent stepping the simulation during the most recent update
+    cpu_step: Duration,
+    /// The CPU time spent encoding and submitting the most recent frame
+    cpu_render_encode: Duration,
+    /// Measures the render pass on the GPU. `None` if timestamp queries are unsupported
+    gpu_timer: Option<GpuTimer>,
+    /// Draws statistics over the simulation
+    hud: Hud,
+    /// Whether the HUD is drawn
+    hud_visible: bool,
+    /// Whether particles are colored by how fast they move, where supported
+    velocity_color: bool,
+    /// How particles are drawn, where supported
+    particle_style: ParticleStyle,
+    /// The diameter in pixels that every particle is drawn at, or `None` to draw particles at
+    /// their own radius
+    point_size: Option<f32>,
+    /// Keeps previous frames around to fade into motion trails
+    trails: Trails,
+    /// The opacity the background is drawn over the previous frame with. At `1`, there are no
+    /// trails
+    trail_fade: f32,
+    /// The vertical gradient drawn as the background, when enabled
+    gradient: Gradient,
+    /// Whether the background is drawn as `gradient` instead of cleared to the flat `background`
+    gradient_enabled: bool,
+    /// The outline of the floor, ceiling, and walls, drawn when enabled
+    bounds: BoundsOverlay,
+    /// Whether `bounds` is drawn over the simulation
+    bounds_visible: bool,
+    /// The static obstacles that particles bounce off
+    colliders: Vec<Collider>,
+    /// The outlines of `colliders`, drawn over the simulation
+    collider_overlay: ColliderOverlay,
+    /// The index of the particle selected by clicking on it, if any
+    selected: Option<usize>,
+    /// The outline highlighting the selected particle
+    selection_overlay: ColliderOverlay,
+    /// The colors of the background and particles
+    theme: Theme,
+    /// The Barnes–Hut opening angle used whenever gravitation between particles is turned on
+    theta: f32,
+    /// The number of steps simulated since the simulation was last restarted for recording or
+    /// replaying
+    step_index: u64,
+    /// Records input for replaying later, while recording
+    recorder: Option<InputRecorder>,
+    /// Plays back recorded input, while replaying
+    replay: Option<Replay>,
+    /// The most recently finished recording, if any
+    last_recording: Option<Recording>,
+    /// The most recently saved snapshot of the simulation, if any
+    snapshot: Option<SimulationState>,
+    /// Whether the left button is held, pushing particles away from the cursor
+    pushing: bool,
+    /// The modifier keys currently held
+    modifiers: ModifiersState,
+    /// The preferred number of invocations in each workgroup of compute shaders
+    workgroup_size: u32,
+    /// The control panel drawn over the simulation
+    gui: Gui,
+    /// The most recently requested size that has not yet been applied, if any
+    pending_resize: Option<dpi::PhysicalSize<u32>>,
+    /// Whether the window was last resized to have no area, such as when minimized, leaving
+    /// nothing to draw into until it is resized again
+    zero_sized: bool,
+    /// Whether the window is entirely hidden, such as behind other windows or on another desktop,
+    /// so that nothing drawn would be seen
+    occluded: bool,
+    /// The ratio of physical pixels to logical pixels on the display being rendered to
+    scale_factor: f64,
+    /// The present modes supported by the target
+    present_modes: Vec<wgpu::PresentMode>,
 }
 
 #[derive(Debug)]
@@ -30,10 +348,11 @@ pub(super) enum ResizeError {
 }
 
 #[derive(Debug)]
+/// Errors that may arise from starting the application or opening windows onto it
 pub(super) enum AppError {
     /// Error creating the surface to render to
     Surface(wgpu::CreateSurfaceError),
-    /// Could not get an adapter
+    /// Could not get an adapter, not even the fallback adapter
     Adapter,
     /// Could not get a handle to the device
     Device(wgpu::RequestDeviceError),
@@ -43,12 +362,91 @@ pub(super) enum AppError {
     NoPresentMode,
     /// No alpha mode found
     NoAlphaMode,
+    /// The application renders offscreen, so has no surfaces that other windows can share
+    #[cfg(test)]
+    Offscreen,
+}
+
+impl fmt::Display for ResizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::ZeroDimension => f.write_str("the new size has no area"),
+        }
+    }
+}
+
+impl Error for ResizeError {}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Surface(ref err) => write!(f, "failed to create a surface: {err}"),
+            Self::Adapter => f.write_str("no compatible GPU adapter found"),
+            Self::Device(ref err) => write!(f, "failed to request a GPU device: {err}"),
+            Self::NoSurfaceFormats => f.write_str("the surface supports no texture formats"),
+            Self::NoPresentMode => f.write_str("the surface supports no present modes"),
+            Self::NoAlphaMode => f.write_str("the surface supports no alpha modes"),
+            #[cfg(test)]
+            Self::Offscreen => f.write_str("an offscreen application has no surface to share"),
+        }
+    }
+}
+
+impl Error for AppError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            Self::Surface(ref err) => Some(err),
+            Self::Device(ref err) => Some(err),
+            Self::Adapter | Self::NoSurfaceFormats | Self::NoPresentMode | Self::NoAlphaMode => {
+                None
+            }
+            #[cfg(test)]
+            Self::Offscreen => None,
+        }
+    }
+}
+
+#[cfg(test)]
+#[derive(Debug)]
+/// Errors that may arise from rendering offscreen
+pub(super) enum OffscreenError {
+    /// The application renders to a window rather than offscreen
+    NotOffscreen,
+    /// The rendered texture could not be read back
+    Readback(readback::ReadbackError),
+}
+
+#[cfg(test)]
+impl fmt::Display for OffscreenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::NotOffscreen => f.write_str("the application renders to a window"),
+            Self::Readback(ref err) => write!(f, "failed to read back the frame: {err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Error for OffscreenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            Self::NotOffscreen => None,
+            Self::Readback(ref err) => Some(err),
+        }
+    }
 }
 
-impl Application<'_> {
-    /// Creates a new application that renders using the given window.
+impl<'app> Application<'app> {
+    /// Creates a new application that renders using the given window, starting the simulation as
+    /// configured. Frames are presented according to the configured preference where possible.
+    /// An adapter matching the given power preference is used if available, then any hardware
+    /// adapter compatible with the window, and finally the fallback adapter, if there is one.
     /// Note that the surface is not necessarily configured yet, and should be done separately.
-    pub async fn new(window: Rc<Window>) -> Result<Self, AppError> {
+    pub async fn new(
+        window: Arc<Window>,
+        simulation: &SimulationConfig,
+        power_preference: wgpu::PowerPreference,
+    ) -> Result<Self, AppError> {
         let size = window.inner_size();
 
         // The instance is a handle to our GPU
@@ -62,56 +460,27 @@ impl Application<'_> {
         });
 
         let surface = instance
-            .create_surface(Rc::clone(&window))
+            .create_surface(Arc::clone(&window))
             .map_err(AppError::Surface)?;
 
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
+        let adapter = request_adapter(&instance, &surface, power_preference)
             .await
             .ok_or(AppError::Adapter)?;
 
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    required_features: wgpu::Features::empty(),
-                    // WebGL doesn't support all of wgpu's features, so if
-                    // we're building for the web, we'll have to disable some.
-                    required_limits: if cfg!(target_arch = "wasm32") {
-                        wgpu::Limits::downlevel_webgl2_defaults()
-                    } else {
-                        wgpu::Limits::default()
-                    },
-                    label: None,
-                },
-                None, // Trace path
-            )
+        let (device, queue) = request_device(&adapter, simulation.max_buffer_size)
             .await
             .map_err(AppError::Device)?;
 
         let surface_caps = surface.get_capabilities(&adapter);
-        // Shader code in this tutorial assumes an sRGB surface texture. Using a different
-        // one will result in all the colors coming out darker. If you want to support non
-        // sRGB surfaces, you'll need to account for that when drawing to the frame.
-
-        let config = wgpu::SurfaceConfiguration {
+        let mut config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_caps
-                .formats
-                .iter()
-                .find(|format| format.is_srgb())
-                .or(surface_caps.formats.first())
-                .copied()
+            format: select_surface_format(simulation.surface_formats, &surface_caps.formats)
                 .ok_or(AppError::NoSurfaceFormats)?,
             width: size.width,
             height: size.height,
-            present_mode: surface_caps
-                .present_modes
-                .first()
-                .copied()
+            present_mode: simulation
+                .present_preference
+                .select(&surface_caps.present_modes)
                 .ok_or(AppError::NoPresentMode)?,
             alpha_mode: surface_caps
                 .alpha_modes
@@ -119,77 +488,3045 @@ impl Application<'_> {
                 .copied()
                 .ok_or(AppError::NoAlphaMode)?,
             view_formats: vec![],
-            desired_maximum_frame_latency: 2,
+            desired_maximum_frame_latency: clamp_frame_latency(simulation.frame_latency),
         };
+        GammaHandling::for_format(config.format).configure(&mut config);
 
-        Ok(Self {
-            surface,
+        let msaa_samples = select_msaa_samples(
+            Some(&adapter),
+            GammaHandling::for_format(config.format).render_format(config.format),
+            simulation.msaa_samples,
+        );
+        let scale_factor = window.scale_factor();
+        let mut app = Self::from_parts(
+            RenderTarget::Window {
+                surface,
+                window,
+                #[cfg(not(target_arch = "wasm32"))]
+                instance,
+                adapter,
+            },
+            Arc::new(device),
+            Arc::new(queue),
+            config,
+            msaa_samples,
+            simulation,
+        );
+        app.scale_factor = scale_factor;
+        app.present_modes = surface_caps.present_modes;
+        if log_enabled!(Level::Info) {
+            info!("GPU capabilities:\n{}", app.capabilities_report());
+        }
+        Ok(app)
+    }
+
+    /// Creates a new application that renders offscreen into a texture of the given size, using
+    /// an existing device, and starts the simulation as configured
+    #[cfg(test)]
+    pub fn new_offscreen(
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        width: u32,
+        height: u32,
+        simulation: &SimulationConfig,
+    ) -> Self {
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format: OFFSCREEN_FORMAT,
+            width,
+            height,
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: clamp_frame_latency(simulation.frame_latency),
+        };
+        let texture = create_offscreen_texture(&device, &config);
+        // Without an adapter, support for anti-aliasing cannot be checked, so it is disabled
+        Self::from_parts(
+            RenderTarget::Offscreen(texture),
+            Arc::new(device),
+            Arc::new(queue),
+            config,
+            1,
+            simulation,
+        )
+    }
+
+    /// Creates the application state shared by all render targets
+    #[expect(
+        clippy::too_many_lines,
+        reason = "Initialization consists of many sequential, independent steps"
+    )]
+    #[expect(
+        clippy::float_arithmetic,
+        reason = "The walls of a sized world lie at its edges, in continuous world coordinates"
+    )]
+    fn from_parts(
+        target: RenderTarget<'app>,
+        device: Arc<wgpu::Device>,
+        queue: Arc<wgpu::Queue>,
+        config: wgpu::SurfaceConfiguration,
+        msaa_samples: u32,
+        simulation: &SimulationConfig,
+    ) -> Self {
+        let features = device.features();
+        let gamma = GammaHandling::for_format(config.format);
+        let render_format = gamma.render_format(config.format);
+        let msaa_texture = create_msaa_texture(&device, &config, render_format, msaa_samples);
+        let (depth_texture, depth_view) = create_attachment(
+            &device,
+            &config,
+            DEPTH_FORMAT,
+            msaa_samples,
+            "Depth Texture",
+        );
+
+        let aspect = aspect_ratio(config.width, config.height);
+        let world_size = simulation.world_size.filter(|&[width, height]| {
+            let valid = width > 0.0 && height > 0.0;
+            if !valid {
+                warn!("Ignoring the world size {width}x{height}, which should be positive");
+            }
+            valid
+        });
+        let half_extents = world_half_extents(world_size, aspect);
+        let floor = simulation.floor.filter(|floor| {
+            if !floor.is_finite() {
+                warn!("Ignoring the floor at {floor}, which should be finite");
+            }
+            floor.is_finite()
+        });
+        let mut ortho_camera = OrthographicCamera::new(aspect);
+        ortho_camera.fit(half_extents);
+        let camera = Camera::new(aspect);
+        let camera_binding = CameraBinding::new(&device, ortho_camera.view_proj());
+
+        let seed = simulation.seed.unwrap_or_else(rand::random);
+        info!("Seeding the simulation with {seed}");
+        let mut rng = StdRng::seed_from_u64(seed);
+        let supported = particles_supported(&device.limits(), simulation.workgroup_size);
+        let max_particles = MAX_PARTICLES.max(simulation.particle_count).min(supported);
+        let particle_count = simulation.particle_count.min(max_particles);
+        if particle_count < simulation.particle_count {
+            warn!(
+                "The device supports at most {supported} particles, fewer than the {} requested; \
+                 spawning {particle_count}",
+                simulation.particle_count
+            );
+        }
+        let mut particle_sim = ParticleSim::new(
+            &SimulationContext {
+                device: &device,
+                queue: &queue,
+                format: render_format,
+                sample_count: msaa_samples,
+                camera_layout: camera_binding.layout(),
+                #[cfg(not(target_arch = "wasm32"))]
+                workgroup_size: simulation.workgroup_size,
+            },
+            simulation.spawn_pattern.spawn(
+                particle_count,
+                half_extents,
+                &mut rng,
+                SizeDistribution::default(),
+            ),
+            max_particles,
+        );
+        particle_sim.set_colliders(simulation.colliders);
+
+        let gpu_timer = features
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+            .then(|| GpuTimer::new(&device, &queue));
+        let hud = Hud::new(&device, render_format);
+        let gui = Gui::new(&device, render_format);
+        let trails = Trails::new(&device, &config, render_format, msaa_samples, DEPTH_FORMAT);
+        trails.set_fade(&queue, simulation.background, 1.0);
+        let gradient = Gradient::new(
+            &device,
+            render_format,
+            msaa_samples,
+            DEPTH_FORMAT,
+            simulation.background,
+            wgpu::Color::BLACK,
+        );
+        let environment = Environment {
+            gravity: simulation.gravity,
+            restitution: simulation.restitution,
+            floor: floor.unwrap_or_else(|| world_size.map_or(DEFAULT_FLOOR, |_| -half_extents[1])),
+            left: -half_extents[0],
+            right: half_extents[0],
+            ceiling: half_extents[1],
+            force_field: None,
+            boundary: BoundaryMode::default(),
+            gravitation: None,
+            damping: 0.0,
+        };
+        let bounds = BoundsOverlay::new(
+            &device,
+            render_format,
+            camera_binding.layout(),
+            msaa_samples,
+            &environment,
+        );
+        let collider_overlay = ColliderOverlay::new(
+            &device,
+            render_format,
+            camera_binding.layout(),
+            msaa_samples,
+            OutlineStyle::Obstacle,
+            simulation.colliders,
+        );
+        let selection_overlay = ColliderOverlay::new(
+            &device,
+            render_format,
+            camera_binding.layout(),
+            msaa_samples,
+            OutlineStyle::Selection,
+            &[],
+        );
+        // Offscreen textures are not presented, so only the configured mode is meaningful
+        let present_modes = vec![config.present_mode];
+
+        Self {
+            target,
             device,
             queue,
+            features,
             config,
-            window,
-        })
+            msaa_samples,
+            gamma,
+            msaa_texture,
+            depth_texture,
+            depth_view,
+            camera_mode: CameraMode::Orthographic,
+            ortho_camera,
+            camera,
+            camera_binding,
+            camera_controller: CameraController::new(CAMERA_SPEED, MOUSE_SENSITIVITY),
+            pointer_lock: PointerLock::default(),
+            #[cfg(target_arch = "wasm32")]
+            pointer_lock_events: PointerLockEvents::new(),
+            simulation: Box::new(particle_sim),
+            background: simulation.background,
+            accumulator: Duration::ZERO,
+            max_steps_per_frame: simulation.max_steps_per_frame.max(1),
+            last_update: Instant::now(),
+            redraw_policy: simulation.redraw_policy,
+            changed: true,
+            idle: false,
+            paused: false,
+            unfocused: false,
+            time_scale: 1.0,
+            pause_on_focus_loss: simulation.pause_on_focus_loss,
+            initial_config: SimulationConfig {
+                seed: Some(seed),
+                ..*simulation
+            },
+            environment,
+            world_size,
+            rng,
+            particle_count,
+            particle_sizes: SizeDistribution::default(),
+            spawn_pattern: simulation.spawn_pattern,
+            emitters: simulation.emitters.to_vec(),
+            particle_grid: OnceCell::new(),
+            max_particles,
+            integrator: Integrator::default(),
+            cursor: None,
+            drag_anchor: None,
+            touches: HashMap::new(),
+            frame_timer: FrameTimer::new(),
+            cpu_step: Duration::ZERO,
+            cpu_render_encode: Duration::ZERO,
+            gpu_timer,
+            hud,
+            hud_visible: true,
+            velocity_color: false,
+            particle_style: ParticleStyle::default(),
+            point_size: None,
+            trails,
+            trail_fade: 1.0,
+            gradient,
+            gradient_enabled: false,
+            bounds,
+            bounds_visible: false,
+            colliders: simulation.colliders.to_vec(),
+            collider_overlay,
+            selected: None,
+            selection_overlay,
+            theme: Theme::default(),
+            theta: DEFAULT_THETA,
+            step_index: 0,
+            recorder: None,
+            replay: None,
+            last_recording: None,
+            snapshot: None,
+            pushing: false,
+            modifiers: ModifiersState::empty(),
+            workgroup_size: simulation.workgroup_size,
+            gui,
+            pending_resize: None,
+            zero_sized: false,
+            occluded: false,
+            scale_factor: 1.0,
+            present_modes,
+        }
     }
 
     /// Attempts to resize the window. Returns any errors that may occur
     pub fn resize(&mut self, new_size: dpi::PhysicalSize<u32>) -> Result<(), ResizeError> {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.config.width = new_size.width;
-            self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
-            Ok(())
-        } else {
-            Err(ResizeError::ZeroDimension)
+        self.zero_sized = new_size.width == 0 || new_size.height == 0;
+        if self.zero_sized {
+            return Err(ResizeError::ZeroDimension);
+        }
+        self.config.width = new_size.width;
+        self.config.height = new_size.height;
+        match self.target {
+            RenderTarget::Window { ref surface, .. } => {
+                surface.configure(&self.device, &self.config);
+            }
+            #[cfg(test)]
+            RenderTarget::Offscreen(ref mut texture) => {
+                *texture = create_offscreen_texture(&self.device, &self.config);
+            }
         }
+        self.msaa_texture = create_msaa_texture(
+            &self.device,
+            &self.config,
+            self.render_format(),
+            self.msaa_samples,
+        );
+        self.trails.resize(&self.device, &self.config);
+        (self.depth_texture, self.depth_view) = create_attachment(
+            &self.device,
+            &self.config,
+            DEPTH_FORMAT,
+            self.msaa_samples,
+            "Depth Texture",
+        );
+        let aspect = self.aspect_ratio();
+        self.ortho_camera.aspect = aspect;
+        self.ortho_camera.fit(self.world_half_extents());
+        self.camera.aspect = aspect;
+        self.update_camera_binding();
+        // Point sizes are fixed in pixels, so shrink or grow relative to the surface
+        if self.point_size.is_some() {
+            self.simulation.set_point_size(self.point_size_ndc());
+        }
+        Ok(())
     }
 
-    /// Updates the application state
-    pub fn update(&mut self) {}
+    /// Returns whether there is anything to draw into, or anyone to see it, which there is not
+    /// while the window has no area, such as when minimized, or is hidden entirely
+    pub const fn is_renderable(&self) -> bool {
+        self.config.width > 0 && self.config.height > 0 && !self.zero_sized && !self.occluded
+    }
 
-    /// Returns the window to which this application is attached
-    pub fn window(&self) -> &Window {
-        &self.window
+    /// Requests that the application be resized on the next update.
+    /// Only the most recently requested size is applied, so that a burst of requests, such as from
+    /// dragging the edge of the window, only reconfigures the surface once
+    pub fn request_resize(&mut self, new_size: dpi::PhysicalSize<u32>) {
+        self.pending_resize = Some(new_size);
     }
 
-    /// Renders the current state of the application to the surface
-    pub fn render(&mut self) -> Result<(), SurfaceError> {
-        /// Background color of the simulation
-        const BACKGROUND_COLOR: wgpu::Color = wgpu::Color {
-            r: 0.0,
-            g: 0.372_549_03,
-            b: 0.372_549_03,
-            a: 1.0,
+    /// Returns the format that the surface was configured with, which frames are presented and
+    /// captured in. The format drawn into may instead be its sRGB equivalent
+    pub const fn surface_format(&self) -> wgpu::TextureFormat {
+        self.config.format
+    }
+
+    /// Returns the ratio of the surface's width to its height, or `1` if the surface is empty.
+    /// This is the aspect ratio that both cameras project with, and so also the width of the flat
+    /// view at unit zoom
+    pub fn aspect_ratio(&self) -> f32 {
+        aspect_ratio(self.config.width, self.config.height)
+    }
+
+    /// Returns the ratio of physical pixels to logical pixels on the display being rendered to
+    pub const fn scale_factor(&self) -> f64 {
+        self.scale_factor
+    }
+
+    /// Records a change in the display's scale factor, and resizes to the given physical size to
+    /// match
+    pub fn scale_factor_changed(&mut self, scale_factor: f64, new_size: dpi::PhysicalSize<u32>) {
+        self.scale_factor = scale_factor;
+        self.request_resize(new_size);
+    }
+
+    /// Returns whether frames are synchronized with the display's refresh rate
+    pub fn vsync(&self) -> bool {
+        self.config.present_mode == wgpu::PresentMode::Fifo
+    }
+
+    /// Switches between presenting frames in sync with the display's refresh rate (`Fifo`) and
+    /// as soon as they are rendered (`Immediate`), reconfiguring the surface to match.
+    /// Keeps the current mode, logging a warning, if the target does not support the new one
+    pub fn set_vsync(&mut self, on: bool) {
+        let mode = if on {
+            wgpu::PresentMode::Fifo
+        } else {
+            wgpu::PresentMode::Immediate
         };
-        let output = self.surface.get_current_texture()?;
-        let view = output
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            });
+        if !self.present_modes.contains(&mode) {
+            warn!(
+                "Present mode {mode:?} is not supported; keeping {:?}",
+                self.config.present_mode
+            );
+            return;
+        }
+        self.config.present_mode = mode;
+        if let Some(surface) = self.surface() {
+            surface.configure(&self.device, &self.config);
+        }
+        info!("Switched to present mode {mode:?}");
+    }
+
+    /// Sets the number of frames that may be queued for presentation ahead of the display,
+    /// clamped to between 1 and 3, and reconfigures the surface to match.
+    /// Lower latencies make the display respond sooner to input, at the cost of the GPU idling
+    /// while it waits on each frame; higher latencies keep the GPU busier for smoother
+    /// throughput, but show each frame later
+    pub fn set_frame_latency(&mut self, frames: u32) {
+        self.config.desired_maximum_frame_latency = clamp_frame_latency(frames);
+        if let Some(surface) = self.surface() {
+            surface.configure(&self.device, &self.config);
+        }
+    }
+
+    /// Applies the most recently requested size, if any
+    fn apply_pending_resize(&mut self) {
+        if let Some(new_size) = self.pending_resize.take() {
+            if let Err(err) = self.resize(new_size) {
+                // Minimized windows have no size, and there is nothing to draw until restored
+                info!("Not resizing to {new_size:?}: {err:?}");
+            }
+        }
+    }
+
+    /// Updates the application state, applying any pending resize and simulating all wall time
+    /// elapsed since the last update
+    pub fn update(&mut self) {
+        self.apply_pending_resize();
+        self.reload_shaders();
+        #[cfg(target_arch = "wasm32")]
+        if let Some(locked) = self
+            .pointer_lock_events
+            .as_ref()
+            .and_then(PointerLockEvents::take)
         {
-            let _render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: LoadOp::Clear(BACKGROUND_COLOR),
-                        store: StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
+            self.pointer_lock_changed(locked);
+        }
+        let now = Instant::now();
+        // Nothing was moving while idle, so the time spent idle need not be caught up with
+        if mem::take(&mut self.idle) {
+            self.last_update = now;
+        }
+        let steps = self.simulate(now.duration_since(self.last_update));
+        self.last_update = now;
+        if steps > 0 {
+            self.upload_simulation();
+        }
+        self.cpu_step = now.elapsed();
+        self.selection_overlay
+            .update(&self.device, &self.queue, &self.selection_outline());
+        if steps > 0 && self.camera_mode == CameraMode::Perspective {
+            self.update_camera_binding();
+        }
+        self.update_gui();
+    }
+
+    /// Runs the control panel, applying any settings adjusted through it
+    fn update_gui(&mut self) {
+        let before = self.controls();
+        let mut after = before;
+        self.gui.run(
+            self.config.width,
+            self.config.height,
+            self.scale_factor(),
+            &mut after,
+        );
+        self.apply_controls(&before, &after);
+    }
+
+    /// Returns the current settings, as shown on the control panel
+    fn controls(&self) -> Controls {
+        let (top, bottom) = self.gradient();
+        Controls {
+            gravity: self.environment.gravity,
+            restitution: self.environment.restitution.base(),
+            particle_count: self.particle_count,
+            max_particles: self.max_particles,
+            damping: self.environment.damping,
+            max_damping: MAX_DAMPING,
+            background: color::rgb(self.background()),
+            theta: self.theta,
+            gradient: [color::rgb(top), color::rgb(bottom)],
+            gradient_enabled: self.gradient_enabled,
+            point_size: self.point_size,
+            radii: [
+                self.particle_sizes.min_radius,
+                self.particle_sizes.max_radius,
+            ],
+            pause_on_focus_loss: self.pause_on_focus_loss,
+            frame_latency: self.config.desired_maximum_frame_latency,
+            frame_latency_range: FRAME_LATENCY_RANGE,
+            redraw_on_demand: self.redraw_policy == RedrawPolicy::OnDemand,
+            spawn_pattern: self.spawn_pattern,
+        }
+    }
+
+    /// Applies the settings that changed between two states of the control panel. Only the color
+    /// channels of colors are adjustable, so their existing opacity is kept
+    #[expect(
+        clippy::float_cmp,
+        reason = "The panel leaves settings exactly as they were unless it adjusted them"
+    )]
+    fn apply_controls(&mut self, before: &Controls, after: &Controls) {
+        if before.gravity != after.gravity {
+            self.set_gravity(after.gravity);
+        }
+        if before.restitution != after.restitution {
+            self.set_restitution(after.restitution);
+        }
+        if before.particle_count != after.particle_count {
+            self.set_particle_count(after.particle_count);
+        }
+        if before.damping != after.damping {
+            self.set_damping(after.damping);
+        }
+        if before.background != after.background {
+            self.set_background(color::with_rgb(self.background, after.background));
+        }
+        if before.theta != after.theta {
+            self.set_theta(after.theta);
+        }
+        if before.gradient != after.gradient {
+            let (top, bottom) = self.gradient();
+            let [new_top, new_bottom] = after.gradient;
+            self.set_gradient(
+                color::with_rgb(top, new_top),
+                color::with_rgb(bottom, new_bottom),
+            );
+        }
+        if before.gradient_enabled != after.gradient_enabled {
+            self.set_gradient_enabled(after.gradient_enabled);
+        }
+        if before.point_size != after.point_size {
+            self.set_point_size(after.point_size);
+        }
+        if before.radii != after.radii {
+            let [first, second] = after.radii;
+            self.set_particle_sizes(SizeDistribution {
+                min_radius: first.min(second),
+                max_radius: first.max(second),
+            });
+        }
+        if before.pause_on_focus_loss != after.pause_on_focus_loss {
+            self.set_pause_on_focus_loss(after.pause_on_focus_loss);
+        }
+        if before.frame_latency != after.frame_latency {
+            self.set_frame_latency(after.frame_latency);
+        }
+        if before.redraw_on_demand != after.redraw_on_demand {
+            self.set_redraw_policy(if after.redraw_on_demand {
+                RedrawPolicy::OnDemand
+            } else {
+                RedrawPolicy::Continuous
             });
         }
+        if before.spawn_pattern != after.spawn_pattern {
+            self.set_spawn_pattern(after.spawn_pattern);
+        }
+    }
 
-        // submit will accept anything that implements IntoIter
-        self.queue.submit(iter::once(encoder.finish()));
-        output.present();
+    /// Feeds a window event into the control panel.
+    /// Returns `true` if the panel claims the event, in which case it should not be handled
+    /// further
+    pub fn gui_event(&mut self, event: &WindowEvent) -> bool {
+        self.gui.on_window_event(event, self.scale_factor())
+    }
 
-        Ok(())
+    /// Returns the combined view and projection matrix of the current camera
+    fn view_proj(&self) -> [[f32; 4]; 4] {
+        match self.camera_mode {
+            CameraMode::Orthographic => self.ortho_camera.view_proj(),
+            CameraMode::Perspective => self.camera.view_proj(),
+        }
+    }
+
+    /// Returns the point on the `z = 0` plane that appears at the given normalized device
+    /// coordinates through the current camera, or `None` if the plane is not visible there
+    fn ndc_to_plane(&self, ndc: [f32; 2]) -> Option<[f32; 2]> {
+        match self.camera_mode {
+            CameraMode::Orthographic => Some(self.ortho_camera.ndc_to_plane(ndc)),
+            CameraMode::Perspective => self.camera.ndc_to_plane(ndc),
+        }
+    }
+
+    /// Exposes the current camera's view to the shaders, and fits the walls and ceiling that
+    /// particles bounce off to the flat view, unless the world has a fixed size
+    fn update_camera_binding(&mut self) {
+        self.camera_binding.update(&self.queue, self.view_proj());
+        if self.world_size.is_some() {
+            return;
+        }
+        // Particles are kept within the flat view, whichever camera is drawing
+        let (min, max) = self.ortho_camera.bounds();
+        self.environment.left = min[0];
+        self.environment.right = max[0];
+        self.environment.ceiling = max[1];
+        self.bounds.update(&self.queue, &self.environment);
+    }
+
+    /// Returns half the width and height of the world that particles are spawned within, in
+    /// meters
+    fn world_half_extents(&self) -> [f32; 2] {
+        world_half_extents(self.world_size, self.aspect_ratio())
+    }
+
+    /// Sets how magnified the flat view is. At a zoom of `1`, the view spans the world vertically,
+    /// or `[-1, 1]` if the world has no fixed size.
+    /// Non-positive zooms are ignored
+    pub fn set_zoom(&mut self, zoom: f32) {
+        if zoom > 0.0 {
+            self.ortho_camera.zoom = zoom;
+            self.update_camera_binding();
+        }
+    }
+
+    /// Sets the point at the center of the flat view
+    pub fn set_center(&mut self, center: [f32; 2]) {
+        self.ortho_camera.center = center.into();
+        self.update_camera_binding();
+    }
+
+    /// Switches between viewing the simulation flat on and in perspective
+    fn toggle_camera_mode(&mut self) {
+        self.camera_mode = match self.camera_mode {
+            CameraMode::Orthographic => CameraMode::Perspective,
+            CameraMode::Perspective => CameraMode::Orthographic,
+        };
+        self.drag_anchor = None;
+        self.update_camera_binding();
+    }
+
+    /// Returns the most steps that a single call to `advance` may take, which grows with the time
+    /// scale so that fast-forwarding is not mistaken for falling behind
+    #[expect(
+        clippy::as_conversions,
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss,
+        reason = "Step limits are small, and the time scale is bounded by `MAX_TIME_SCALE`"
+    )]
+    #[expect(
+        clippy::float_arithmetic,
+        reason = "The limit grows with the time scale, which is a continuous multiplier"
+    )]
+    fn step_limit(&self) -> u32 {
+        (self.max_steps_per_frame as f32 * self.time_scale.max(1.0)).ceil() as u32
+    }
+
+    /// Simulates the given amount of elapsed wall time, scaled by the time scale, in steps of
+    /// `TIMESTEP`. Returns the number of steps taken
+    fn simulate(&mut self, elapsed: Duration) -> u32 {
+        // Scaling the elapsed time changes how many steps are taken, while each step stays the same
+        // length for stability
+        self.advance(elapsed.mul_f32(self.time_scale), TIMESTEP)
+    }
+
+    /// Simulates the given amount of elapsed time, in as many whole steps of `dt` as fit, up to
+    /// the step limit. Any leftover time is carried forward to the next call, unless the limit was
+    /// reached, in which case it is dropped so that the simulation does not fall ever further
+    /// behind. The camera keeps moving while paused, but the particles do not.
+    /// Returns the number of steps taken
+    fn advance(&mut self, elapsed: Duration, dt: Duration) -> u32 {
+        self.accumulator = self.accumulator.saturating_add(elapsed);
+        let limit = self.step_limit();
+        let mut steps = 0_u32;
+        while let Some(remaining) = self.accumulator.checked_sub(dt) {
+            if steps >= limit {
+                warn!(
+                    "Simulation fell behind by {:?}; dropping the time beyond {limit} steps",
+                    self.accumulator
+                );
+                self.accumulator = Duration::ZERO;
+                break;
+            }
+            self.accumulator = remaining;
+            self.camera_controller
+                .update_camera(&mut self.camera, dt.as_secs_f32());
+            self.replay_due_events();
+            if !self.is_paused() {
+                self.step(dt);
+            }
+            steps = steps.saturating_add(1);
+        }
+        steps
+    }
+
+    /// Advances the simulation by exactly one fixed timestep, even while paused, and redraws to
+    /// show the result. Time waiting to be simulated is left untouched
+    pub fn step_once(&mut self) {
+        self.replay_due_events();
+        self.step(TIMESTEP);
+        self.upload_simulation();
+        if let Some(window) = self.window() {
+            window.request_redraw();
+        }
+    }
+
+    /// Advances the simulation by a single step of `dt`
+    fn step(&mut self, dt: Duration) {
+        self.emit(dt);
+        let environment = Environment {
+            force_field: self.force_field(),
+            ..self.environment
+        };
+        self.track_removals(|app| app.simulation.step(dt.as_secs_f32(), &environment));
+        self.step_index = self.step_index.saturating_add(1);
+    }
+
+    /// Brings the simulation's GPU buffers up to date with the steps taken this frame
+    fn upload_simulation(&mut self) {
+        self.track_removals(|app| app.simulation.upload(&app.queue));
+    }
+
+    /// Runs the given change to the simulation, which may remove particles for having non-finite
+    /// state, forgetting anything that refers to particles by index if it did
+    fn track_removals<F>(&mut self, change: F)
+    where
+        F: FnOnce(&mut Self),
+    {
+        let non_finite_count = self.non_finite_count();
+        change(self);
+        self.particle_grid.take();
+        // Removing particles shifts the indices of those after them
+        if self.non_finite_count() != non_finite_count {
+            self.deselect();
+        }
+    }
+
+    /// Adds the particles that every emitter spawns over a further `dt`, as many as fit within the
+    /// limit on the number of particles. The rest are dropped
+    fn emit(&mut self, dt: Duration) {
+        let sizes = self.particle_sizes;
+        let particles: Vec<_> = self
+            .emitters
+            .iter_mut()
+            .flat_map(|emitter| emitter.emit(dt.as_secs_f32(), &mut self.rng, sizes))
+            .collect();
+        if !particles.is_empty() {
+            self.simulation.add(particles);
+        }
+    }
+
+    /// Returns the force field under the cursor while the left button is held, which pushes
+    /// particles away, or pulls them in while Alt is held
+    #[expect(
+        clippy::float_arithmetic,
+        reason = "The strength of the force field is a floating-point acceleration"
+    )]
+    fn force_field(&self) -> Option<ForceField> {
+        if !self.pushing {
+            return None;
+        }
+        let center = self.cursor_ndc().and_then(|ndc| self.ndc_to_plane(ndc))?;
+        let strength = if self.modifiers.alt_key() {
+            -FORCE_FIELD_STRENGTH
+        } else {
+            FORCE_FIELD_STRENGTH
+        };
+        Some(ForceField { center, strength })
+    }
+
+    /// Records which modifier keys are held
+    pub fn modifiers_changed(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
+
+    /// Returns whether the simulation is paused, either deliberately or because the window is
+    /// unfocused
+    pub const fn is_paused(&self) -> bool {
+        self.paused || (self.pause_on_focus_loss && self.unfocused)
+    }
+
+    /// Sets when the window is redrawn, redrawing it once more in case it had stopped
+    pub fn set_redraw_policy(&mut self, redraw_policy: RedrawPolicy) {
+        self.redraw_policy = redraw_policy;
+        self.request_redraw();
+    }
+
+    /// Notes that something shown has changed, and requests that the window be redrawn to show it
+    pub fn request_redraw(&mut self) {
+        self.changed = true;
+        if let Some(window) = self.window() {
+            window.request_redraw();
+        }
+    }
+
+    /// Returns whether the next frame should be drawn: always when redrawing continuously, and
+    /// otherwise only while the simulation runs, the camera moves, input is replayed, or something
+    /// has changed since the last frame
+    pub fn wants_redraw(&self) -> bool {
+        match self.redraw_policy {
+            RedrawPolicy::Continuous => true,
+            RedrawPolicy::OnDemand => {
+                self.changed
+                    || !self.is_paused()
+                    || self.camera_controller.is_moving()
+                    || self.replay.is_some()
+            }
+        }
+    }
+
+    /// Requests the next frame after one has been drawn, if it is wanted and there is anything to
+    /// draw into. Otherwise, the application idles until `request_redraw` is called, as it is
+    /// when the window is resized
+    pub fn schedule_redraw(&mut self) {
+        let wants_redraw = self.wants_redraw() && self.is_renderable();
+        self.changed = false;
+        self.idle = !wants_redraw;
+        if wants_redraw {
+            if let Some(window) = self.window() {
+                window.request_redraw();
+            }
+        }
+    }
+
+    /// Returns how many times faster than real time the simulation runs
+    pub const fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Sets how many times faster than real time the simulation runs, so that `0.5` is half
+    /// speed and `2` is double speed. Clamped to between `0` and a maximum that keeps the number
+    /// of steps per frame manageable
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale.clamp(0.0, MAX_TIME_SCALE);
+        info!("Simulating at {}x speed", self.time_scale);
+    }
+
+    /// Pauses or resumes the simulation deliberately. A deliberate pause outlasts the window
+    /// regaining focus
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Records whether the window has focus. The simulation is paused while it does not, if
+    /// pausing on focus loss is enabled
+    pub fn focus_changed(&mut self, focused: bool) {
+        self.unfocused = !focused;
+        if !focused {
+            self.release_pointer_lock();
+        }
+    }
+
+    /// Records whether the window is entirely hidden. Nothing is drawn while it is, and a frame is
+    /// drawn straight away once it is shown again, so that it does not show a stale frame
+    pub fn occlusion_changed(&mut self, occluded: bool) {
+        self.occluded = occluded;
+        if !occluded {
+            self.request_redraw();
+        }
+    }
+
+    /// Sets whether the simulation is paused while the window is unfocused
+    pub fn set_pause_on_focus_loss(&mut self, pause_on_focus_loss: bool) {
+        self.pause_on_focus_loss = pause_on_focus_loss;
+    }
+
+    /// Sets the vertical acceleration applied to every particle. Negative values pull downwards
+    pub fn set_gravity(&mut self, gravity: f32) {
+        self.environment.gravity = gravity;
+    }
+
+    /// Sets the fraction of its speed that a particle retains when bouncing at no speed, clamped
+    /// to `[0, 1]`, keeping how it falls off with speed
+    pub fn set_restitution(&mut self, restitution: f32) {
+        self.environment.restitution = self
+            .environment
+            .restitution
+            .with_base(restitution.clamp(0.0, 1.0));
+    }
+
+    /// Sets the rate per second at which air resistance slows every particle, clamped to
+    /// `[0, MAX_DAMPING]`. Zero, the default, leaves velocities unchanged, and NaN is treated as
+    /// zero
+    pub fn set_damping(&mut self, damping: f32) {
+        self.environment.damping = if damping.is_nan() {
+            0.0
+        } else {
+            damping.clamp(0.0, MAX_DAMPING)
+        };
+    }
+
+    /// Sets what happens to particles that reach the floor, ceiling, or walls
+    pub fn set_boundary_mode(&mut self, boundary: BoundaryMode) {
+        self.environment.boundary = boundary;
+        info!("Switched to {boundary:?} boundaries");
+    }
+
+    /// Turns mutual gravitational attraction between particles on or off
+    pub fn set_gravitation_enabled(&mut self, enabled: bool) {
+        self.environment.gravitation = enabled.then_some(Gravitation {
+            constant: GRAVITATIONAL_CONSTANT,
+            theta: self.theta,
+        });
+    }
+
+    /// Sets the Barnes–Hut opening angle used to approximate gravitation between particles.
+    /// Larger angles are faster but less accurate, and zero computes every pair exactly.
+    /// Negative and NaN angles are treated as zero
+    pub fn set_theta(&mut self, theta: f32) {
+        self.theta = theta.max(0.0);
+        if let Some(gravitation) = self.environment.gravitation.as_mut() {
+            gravitation.theta = self.theta;
+        }
+    }
+
+    /// Shows or hides the outline of the floor, ceiling, and walls that particles are kept within
+    pub fn set_bounds_visible(&mut self, visible: bool) {
+        self.bounds_visible = visible;
+    }
+
+    /// Restarts the random number generator that all randomness in the simulation is drawn from
+    /// with the given seed
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /// Restores the simulation to how it started: reseeds the random number generator with the
+    /// starting seed and respawns the starting particles, in fresh GPU buffers, and turns off
+    /// every force other than the starting gravity
+    pub fn reset(&mut self) {
+        let initial = self.initial_config;
+        if let Some(seed) = initial.seed {
+            self.reseed(seed);
+        }
+        self.particle_count = initial.particle_count.min(self.max_particles);
+        self.particle_sizes = SizeDistribution::default();
+        self.spawn_pattern = initial.spawn_pattern;
+        self.environment.gravity = initial.gravity;
+        self.environment.gravitation = None;
+        self.environment.damping = 0.0;
+        self.emitters = initial.emitters.to_vec();
+        self.spawn_particle_sim();
+        self.accumulator = Duration::ZERO;
+        info!("Reset the simulation");
+    }
+
+    /// Restarts the simulation as a freshly seeded particle simulation and starts recording input
+    /// against the steps it arrives at. Any ongoing replay is stopped
+    pub fn start_recording(&mut self) {
+        let seed = rand::random();
+        self.replay = None;
+        self.restart(seed);
+        self.recorder = Some(InputRecorder::new(seed));
+        info!("Recording input from seed {seed}");
+    }
+
+    /// Stops recording input, returning the recording, or `None` if not recording.
+    /// The recording is also kept to be replayed later
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        let recording = self.recorder.take()?.finish();
+        info!(
+            "Recorded {} input events over {} steps",
+            recording.events.len(),
+            self.step_index
+        );
+        self.last_recording = Some(recording.clone());
+        Some(recording)
+    }
+
+    /// Restarts the simulation from the recording's seed and replays its input at the steps it
+    /// was recorded at. Starting from the same settings, this reproduces the recorded run exactly
+    pub fn replay(&mut self, recording: Recording) {
+        self.recorder = None;
+        self.restart(recording.seed);
+        info!(
+            "Replaying {} input events from seed {}",
+            recording.events.len(),
+            recording.seed
+        );
+        self.replay = Some(Replay::new(recording));
+    }
+
+    /// Replaces the simulation with a particle simulation generated from the given seed, counting
+    /// steps from zero again
+    fn restart(&mut self, seed: u64) {
+        self.reseed(seed);
+        self.spawn_particle_sim();
+        self.accumulator = Duration::ZERO;
+        self.step_index = 0;
+    }
+
+    /// Applies every replayed event due before the next step
+    fn replay_due_events(&mut self) {
+        while let Some(event) = self
+            .replay
+            .as_mut()
+            .and_then(|replay| replay.next_due(self.step_index))
+        {
+            self.apply_input(event);
+        }
+        if self.replay.as_ref().is_some_and(Replay::is_finished) {
+            info!("Finished replaying input");
+            self.replay = None;
+        }
+    }
+
+    /// Applies an input event, recording it if recording
+    fn apply_input(&mut self, event: InputEvent) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(self.step_index, event);
+        }
+        match event {
+            InputEvent::Key(key) => {
+                if let Some(action) = keys::action(key).filter(|action| action.is_recorded()) {
+                    self.perform(action);
+                }
+            }
+            InputEvent::Spawn(position) => {
+                // The radius is drawn as the event is applied, so that replays draw the same one
+                let radius = self.particle_sizes.sample(&mut self.rng);
+                let count = self.simulation.particles().len();
+                if self.simulation.spawn(position, radius) {
+                    self.particle_grid.take();
+                    // A full simulation makes room by replacing its oldest particle, which may
+                    // have been the one selected
+                    if self.simulation.particles().len() == count {
+                        self.deselect();
+                    }
+                } else {
+                    warn!("The simulation does not support spawning; not spawning a particle");
+                }
+            }
+        }
+    }
+
+    /// Rebuilds the simulation's pipelines from any shader source files changed while developing
+    fn reload_shaders(&mut self) {
+        // The context is built from the fields directly, as the simulation is borrowed mutably
+        let format = self.render_format();
+        self.simulation.reload_shaders(&SimulationContext {
+            device: &self.device,
+            queue: &self.queue,
+            format,
+            sample_count: self.msaa_samples,
+            camera_layout: self.camera_binding.layout(),
+            #[cfg(not(target_arch = "wasm32"))]
+            workgroup_size: self.workgroup_size,
+        });
+    }
+
+    /// Returns the most particles that the device's buffer and dispatch limits allow to be
+    /// simulated at once
+    pub fn max_particles_supported(&self) -> usize {
+        particles_supported(&self.device.limits(), self.workgroup_size)
+    }
+
+    /// Returns the number of samples per pixel rendered. Anti-aliasing is enabled if this is more
+    /// than 1
+    pub const fn msaa_samples(&self) -> u32 {
+        self.msaa_samples
+    }
+
+    /// Sets the number of samples per pixel used for anti-aliasing, from 1, which disables it, to
+    /// 8. Counts that the color or depth format does not support fall back to the nearest count
+    /// that both do. Every attachment and pipeline drawing into them is recreated for the new
+    /// count, and rendering resolves into the output only while anti-aliasing. Returns the count
+    /// now in use
+    pub fn set_msaa_samples(&mut self, preferred: u32) -> u32 {
+        let format = self.render_format();
+        let samples = select_msaa_samples(self.adapter(), format, preferred);
+        if samples == self.msaa_samples {
+            return samples;
+        }
+        self.msaa_samples = samples;
+        self.msaa_texture = create_msaa_texture(&self.device, &self.config, format, samples);
+        (self.depth_texture, self.depth_view) = create_attachment(
+            &self.device,
+            &self.config,
+            DEPTH_FORMAT,
+            samples,
+            "Depth Texture",
+        );
+        self.trails = Trails::new(&self.device, &self.config, format, samples, DEPTH_FORMAT);
+        self.trails
+            .set_fade(&self.queue, self.background, self.trail_fade);
+        let (top, bottom) = self.gradient.colors();
+        self.gradient = Gradient::new(&self.device, format, samples, DEPTH_FORMAT, top, bottom);
+        self.bounds = BoundsOverlay::new(
+            &self.device,
+            format,
+            self.camera_binding.layout(),
+            samples,
+            &self.environment,
+        );
+        self.collider_overlay = ColliderOverlay::new(
+            &self.device,
+            format,
+            self.camera_binding.layout(),
+            samples,
+            OutlineStyle::Obstacle,
+            &self.colliders,
+        );
+        self.selection_overlay = ColliderOverlay::new(
+            &self.device,
+            format,
+            self.camera_binding.layout(),
+            samples,
+            OutlineStyle::Selection,
+            &self.selection_outline(),
+        );
+        // The context is built from the fields directly, as the simulation is borrowed mutably
+        self.simulation.rebuild_pipelines(&SimulationContext {
+            device: &self.device,
+            queue: &self.queue,
+            format,
+            sample_count: samples,
+            camera_layout: self.camera_binding.layout(),
+            #[cfg(not(target_arch = "wasm32"))]
+            workgroup_size: self.workgroup_size,
+        });
+        info!("Rendering with {samples}x anti-aliasing");
+        samples
+    }
+
+    /// Switches to the next supported anti-aliasing level, wrapping around to disabling it
+    fn cycle_msaa_samples(&mut self) {
+        let supported = supported_msaa_samples(self.adapter(), self.render_format());
+        let next = supported
+            .iter()
+            .copied()
+            .find(|&samples| samples > self.msaa_samples)
+            .unwrap_or(1);
+        self.set_msaa_samples(next);
+    }
+
+    /// Returns the surface rendered to, which only exists when rendering to a window
+    #[cfg_attr(
+        not(test),
+        expect(
+            clippy::unnecessary_wraps,
+            reason = "Only tests render offscreen, where there is no window"
+        )
+    )]
+    const fn surface(&self) -> Option<&wgpu::Surface<'app>> {
+        match self.target {
+            RenderTarget::Window { ref surface, .. } => Some(surface),
+            #[cfg(test)]
+            RenderTarget::Offscreen(_) => None,
+        }
+    }
+
+    /// Returns the adapter of the device, which is only known when rendering to a window
+    #[cfg_attr(
+        not(test),
+        expect(
+            clippy::unnecessary_wraps,
+            reason = "Only tests render offscreen, where there is no window"
+        )
+    )]
+    const fn adapter(&self) -> Option<&wgpu::Adapter> {
+        match self.target {
+            RenderTarget::Window { ref adapter, .. } => Some(adapter),
+            #[cfg(test)]
+            RenderTarget::Offscreen(_) => None,
+        }
+    }
+
+    /// Returns the resources needed to create a simulation drawn by this application
+    fn simulation_context(&self) -> SimulationContext<'_> {
+        SimulationContext {
+            device: &self.device,
+            queue: &self.queue,
+            format: self.render_format(),
+            sample_count: self.msaa_samples,
+            camera_layout: self.camera_binding.layout(),
+            #[cfg(not(target_arch = "wasm32"))]
+            workgroup_size: self.workgroup_size,
+        }
+    }
+
+    /// Sets the number of particles spawned by the particle simulation, up to the limit on the
+    /// number of particles, and restarts it with that many particles
+    pub fn set_particle_count(&mut self, particle_count: usize) {
+        self.particle_count = particle_count.min(self.max_particles);
+        self.spawn_particle_sim();
+    }
+
+    /// Adds `count` random particles to the simulation, or as many as fit within the limit on the
+    /// number of particles. Logs a warning if the simulation does not support adding particles
+    pub fn add_particles(&mut self, count: usize) {
+        let sizes = self.particle_sizes;
+        let half_extents = self.world_half_extents();
+        let particles = iter::repeat_with(|| Particle::random(&mut self.rng, half_extents, sizes))
+            .take(count)
+            .collect();
+        let added = self.simulation.add(particles);
+        self.particle_grid.take();
+        if added == 0 && count > 0 {
+            warn!("Could not add any particles to the simulation");
+        } else {
+            info!(
+                "Added {added} particles, for {} in total",
+                self.simulation.particles().len()
+            );
+        }
+    }
+
+    /// Returns the indices of every particle within `radius` of `center`, inclusive, in
+    /// ascending order. Positions are in world coordinates, as the particles are
+    pub fn particles_in_radius(&self, center: [f32; 2], radius: f32) -> Vec<usize> {
+        let grid = self.particle_grid.get_or_init(|| {
+            let mut grid = SpatialGrid::new(QUERY_CELL_SIZE);
+            grid.rebuild(
+                self.simulation
+                    .particles()
+                    .iter()
+                    .map(|particle| particle.position),
+            );
+            grid
+        });
+        let mut indices: Vec<_> = grid.neighbors(center, radius).collect();
+        indices.sort_unstable();
+        indices
+    }
+
+    /// Returns the particle nearest the given pixel position, within a few pixels of it
+    #[expect(
+        clippy::float_arithmetic,
+        reason = "Particles are picked within a radius around the cursor's continuous position"
+    )]
+    fn particle_at(&self, pixels: dpi::PhysicalPosition<f64>) -> Option<usize> {
+        let to_plane = |position| self.ndc_to_plane(self.to_ndc(position)?);
+        let center = to_plane(pixels)?;
+        let edge = to_plane(dpi::PhysicalPosition::new(
+            pixels.x + SELECTION_RADIUS_PIXELS,
+            pixels.y,
+        ))?;
+        let radius = grid::distance_squared(center, edge).sqrt();
+        let particles = self.simulation.particles();
+        self.particles_in_radius(center, radius)
+            .into_iter()
+            .filter_map(|index| Some((index, particles.get(index)?)))
+            .min_by(|&(_, first), &(_, second)| {
+                grid::distance_squared(center, first.position)
+                    .total_cmp(&grid::distance_squared(center, second.position))
+            })
+            .map(|(index, _)| index)
+    }
+
+    /// Selects the particle at the given index, logging its state
+    fn select(&mut self, index: usize) {
+        if let Some(particle) = self.simulation.particles().get(index) {
+            info!(
+                "Selected particle {index} at {:?}, moving at {:?}",
+                particle.position, particle.velocity
+            );
+            self.selected = Some(index);
+        }
+    }
+
+    /// Clears the selection, if any particle is selected
+    fn deselect(&mut self) {
+        if self.selected.take().is_some() {
+            info!("Deselected the particle");
+        }
+    }
+
+    /// Returns the outline around the selected particle, or none if no particle is selected
+    #[expect(
+        clippy::float_arithmetic,
+        reason = "The outline is scaled up from the particle's floating-point radius"
+    )]
+    fn selection_outline(&self) -> Vec<Collider> {
+        self.selected
+            .and_then(|index| self.simulation.particles().get(index))
+            .map(|particle| Collider::Circle {
+                center: particle.position,
+                radius: particle.radius * SELECTION_OUTLINE_SCALE,
+            })
+            .into_iter()
+            .collect()
+    }
+
+    /// Removes up to `count` of the most recently added particles from the simulation, stopping
+    /// once none are left
+    pub fn remove_particles(&mut self, count: usize) {
+        let removed = self.simulation.remove(count);
+        self.particle_grid.take();
+        if self
+            .selected
+            .is_some_and(|index| index >= self.simulation.particles().len())
+        {
+            self.deselect();
+        }
+        info!(
+            "Removed {removed} particles, for {} in total",
+            self.simulation.particles().len()
+        );
+    }
+
+    /// Removes every obstacle from the world. Obstacles otherwise outlast resets
+    pub fn clear_colliders(&mut self) {
+        self.colliders.clear();
+        self.simulation.set_colliders(&self.colliders);
+        self.collider_overlay.replace(&self.device, &self.colliders);
+    }
+
+    /// Removes every emitter, so that particles are no longer spawned continuously
+    pub fn clear_emitters(&mut self) {
+        self.emitters.clear();
+    }
+
+    /// Sets the distribution that the radii of particles are drawn from, both when particles are
+    /// spawned individually and when the particle simulation is restarted. Every particle has the
+    /// same density, so larger particles are also heavier
+    pub fn set_particle_sizes(&mut self, sizes: SizeDistribution) {
+        self.particle_sizes = sizes;
+    }
+
+    /// Sets how the particles are arranged when the particle simulation is restarted, and
+    /// restarts it in that arrangement
+    pub fn set_spawn_pattern(&mut self, spawn_pattern: SpawnPattern) {
+        self.spawn_pattern = spawn_pattern;
+        self.spawn_particle_sim();
+    }
+
+    /// Replaces the current simulation with freshly spawned particles in the spawn pattern
+    fn spawn_particle_sim(&mut self) {
+        let particles = self.spawn_pattern.spawn(
+            self.particle_count,
+            self.world_half_extents(),
+            &mut self.rng,
+            self.particle_sizes,
+        );
+        self.set_simulation(Box::new(ParticleSim::new(
+            &self.simulation_context(),
+            particles,
+            self.max_particles,
+        )));
+    }
+
+    /// Replaces the current simulation with a freshly hung cloth
+    fn spawn_cloth_sim(&mut self) {
+        self.set_simulation(Box::new(ClothSim::new(
+            &self.simulation_context(),
+            CLOTH_SIZE,
+            CLOTH_SIZE,
+            CLOTH_STIFFNESS,
+        )));
+    }
+
+    /// Replaces the current simulation with a resting block of fluid, of as many of the particles
+    /// spawned by the particle simulation as the fluid allows
+    fn spawn_fluid_sim(&mut self) {
+        self.set_simulation(Box::new(SphSim::new(
+            &self.simulation_context(),
+            self.particle_count,
+            self.world_half_extents(),
+            FluidParameters::default(),
+        )));
+    }
+
+    /// Replaces the physics being simulated, advancing it with the current integrator, and coloring
+    /// and drawing it as currently chosen, if it supports them
+    pub fn set_simulation(&mut self, mut simulation: Box<dyn Simulation>) {
+        if !simulation.set_integrator(self.integrator) {
+            warn!(
+                "Simulation does not support {:?} integration, falling back to its default",
+                self.integrator
+            );
+        }
+        if !simulation.set_velocity_color_enabled(self.velocity_color) {
+            warn!("Simulation does not support coloring by speed");
+        }
+        if !simulation.set_palette(self.theme.palette()) {
+            warn!(
+                "Simulation does not support the colors of the {:?} theme",
+                self.theme
+            );
+        }
+        if !self.colliders.is_empty() && !simulation.set_colliders(&self.colliders) {
+            warn!("Simulation does not support obstacles");
+        }
+        if self.point_size.is_some() && !simulation.set_point_size(self.point_size_ndc()) {
+            warn!("Simulation does not support fixed point sizes");
+        }
+        if !simulation.set_particle_style(self.particle_style) {
+            warn!(
+                "Simulation does not support the {:?} particle style",
+                self.particle_style
+            );
+        }
+        self.simulation = simulation;
+        self.particle_grid.take();
+        self.deselect();
+    }
+
+    /// Switches between coloring particles by how fast they move, from blue when slow to red when
+    /// fast, and coloring them flatly. Keeps the current coloring, logging a warning, if the
+    /// current simulation does not support coloring by speed
+    pub fn set_velocity_color_enabled(&mut self, enabled: bool) {
+        if self.simulation.set_velocity_color_enabled(enabled) {
+            self.velocity_color = enabled;
+        } else {
+            warn!("Simulation does not support coloring by speed");
+        }
+    }
+
+    /// Switches how particles are drawn: as solid circles, or as soft discs blended over what lies
+    /// behind them. Keeps the current style, logging a warning, if the current simulation does not
+    /// support it
+    pub fn set_particle_style(&mut self, style: ParticleStyle) {
+        if self.simulation.set_particle_style(style) {
+            self.particle_style = style;
+        } else {
+            warn!("Simulation does not support the {style:?} particle style");
+        }
+    }
+
+    /// Sets the diameter in pixels that every particle is drawn at, whatever its radius, the zoom,
+    /// or the size of the window, or `None` to draw particles at their own radius again. Keeps the
+    /// current size, logging a warning, if the current simulation does not support it
+    pub fn set_point_size(&mut self, pixels: Option<f32>) {
+        let previous = mem::replace(
+            &mut self.point_size,
+            pixels.filter(|&diameter| diameter > 0.0),
+        );
+        if !self.simulation.set_point_size(self.point_size_ndc()) {
+            self.point_size = previous;
+            warn!("Simulation does not support fixed point sizes");
+        }
+    }
+
+    /// Returns the size that every particle is drawn at, in normalized device coordinates for the
+    /// current surface, or `None` if particles are drawn at their own radius
+    fn point_size_ndc(&self) -> Option<[f32; 2]> {
+        self.point_size
+            .map(|pixels| coloring::point_size_ndc(pixels, self.config.width, self.config.height))
+    }
+
+    /// Switches the scheme the simulation is advanced through time with.
+    /// Returns `false`, leaving the integrator unchanged, if the current simulation does not
+    /// support it
+    pub fn set_integrator(&mut self, integrator: Integrator) -> bool {
+        let supported = self.simulation.set_integrator(integrator);
+        if supported {
+            self.integrator = integrator;
+            info!("Switched to {integrator:?} integration");
+        } else {
+            warn!("Simulation does not support {integrator:?} integration");
+        }
+        supported
+    }
+
+    /// Returns a snapshot of the current state of the simulation
+    pub fn save_state(&self) -> SimulationState {
+        SimulationState {
+            particles: self.simulation.particles().to_vec(),
+            gravity: self.environment.gravity,
+            paused: self.paused,
+        }
+    }
+
+    /// Replaces the current state of the simulation with a snapshot, rebuilding the GPU buffers
+    /// holding the particles. The snapshot is restored as a particle simulation, keeping only as
+    /// many of its particles as the limit on the number of particles allows
+    pub fn load_state(&mut self, mut state: SimulationState) {
+        if state.particles.len() > self.max_particles {
+            warn!(
+                "Keeping only {} of the {} particles in the snapshot",
+                self.max_particles,
+                state.particles.len()
+            );
+            state.particles.truncate(self.max_particles);
+        }
+        self.set_simulation(Box::new(ParticleSim::new(
+            &self.simulation_context(),
+            state.particles,
+            self.max_particles,
+        )));
+        self.environment.gravity = state.gravity;
+        self.paused = state.paused;
+    }
+
+    /// Handles a key being pressed or released.
+    /// `repeat` is whether this is a repeated press from the key being held down
+    pub fn keyboard_input(&mut self, key: KeyCode, state: ElementState, repeat: bool) {
+        if self.camera_controller.process_key(key, state)
+            || state != ElementState::Pressed
+            || repeat
+        {
+            return;
+        }
+        match keys::action(key) {
+            Some(Action::ToggleRecording) => {
+                if self.stop_recording().is_none() {
+                    self.start_recording();
+                }
+            }
+            Some(Action::Replay) => {
+                if let Some(recording) = self.last_recording.clone() {
+                    self.replay(recording);
+                } else {
+                    warn!("Nothing has been recorded to replay");
+                }
+            }
+            Some(Action::SaveState) => {
+                let snapshot = self.save_state();
+                info!("Saved a snapshot of {} particles", snapshot.particles.len());
+                self.snapshot = Some(snapshot);
+            }
+            Some(Action::LoadState) => {
+                if let Some(snapshot) = self.snapshot.clone() {
+                    self.load_state(snapshot);
+                    info!("Restored the saved snapshot");
+                } else {
+                    warn!("No snapshot has been saved to restore");
+                }
+            }
+            Some(_) => self.apply_input(InputEvent::Key(key)),
+            None => {}
+        }
+    }
+
+    /// Performs the action bound to a key. Controlling recording and replaying, and saving and
+    /// restoring snapshots, are handled as the key is pressed instead, as they are not recorded
+    #[expect(
+        clippy::float_arithmetic,
+        reason = "The time scale is a continuous multiplier, halved and doubled by keys"
+    )]
+    fn perform(&mut self, action: Action) {
+        match action {
+            Action::TogglePause => self.set_paused(!self.paused),
+            Action::StepOnce => self.step_once(),
+            Action::CycleBoundary => self.set_boundary_mode(self.environment.boundary.next()),
+            Action::ToggleGravitation => {
+                self.set_gravitation_enabled(self.environment.gravitation.is_none());
+            }
+            Action::SlowDown => self.set_time_scale(self.time_scale() / 2.0),
+            Action::SpeedUp => self.set_time_scale(self.time_scale() * 2.0),
+            Action::ToggleCameraMode => self.toggle_camera_mode(),
+            Action::ResetView => {
+                self.set_zoom(1.0);
+                self.set_center([0.0, 0.0]);
+            }
+            Action::ToggleHud => self.set_hud_visible(!self.hud_visible),
+            Action::ToggleVsync => self.set_vsync(!self.vsync()),
+            Action::ToggleVelocityColor => self.set_velocity_color_enabled(!self.velocity_color),
+            Action::ToggleGradient => self.set_gradient_enabled(!self.gradient_enabled),
+            Action::ToggleBounds => self.set_bounds_visible(!self.bounds_visible),
+            Action::CycleMsaa => self.cycle_msaa_samples(),
+            Action::CycleTheme => self.set_theme(self.theme.next()),
+            Action::ToggleParticleStyle => self.set_particle_style(self.particle_style.toggled()),
+            Action::ToggleTrails => self.set_trail_fade(if self.trail_fade < 1.0 {
+                1.0
+            } else {
+                DEFAULT_TRAIL_FADE
+            }),
+            Action::AddParticles => self.add_particles(PARTICLE_STEP),
+            Action::RemoveParticles => self.remove_particles(PARTICLE_STEP),
+            Action::Reset => self.reset(),
+            Action::ParticleSim => self.spawn_particle_sim(),
+            Action::ClothSim => self.spawn_cloth_sim(),
+            Action::FluidSim => self.spawn_fluid_sim(),
+            Action::ClearEmitters => self.clear_emitters(),
+            Action::ClearColliders => self.clear_colliders(),
+            Action::ToggleIntegrator => {
+                self.set_integrator(self.integrator.toggled());
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            Action::Screenshot => self.save_screenshot(),
+            Action::ToggleRecording | Action::Replay | Action::SaveState | Action::LoadState => {}
+        }
+    }
+
+    /// Captures the current frame and saves it into a PNG file in the working directory, named
+    /// after the time of capture
+    #[cfg(not(target_arch = "wasm32"))]
+    fn save_screenshot(&self) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let path = format!("screenshot-{timestamp}.png");
+        match self.capture_frame() {
+            Ok(png) => match fs::write(&path, png) {
+                Ok(()) => info!("Saved screenshot to {path}"),
+                Err(err) => error!("Failed to save screenshot to {path}: {err}"),
+            },
+            Err(err) => error!("Failed to capture screenshot: {err}"),
+        }
+    }
+
+    /// Records the latest position of the cursor within the window
+    /// Pans the flat view if it is being dragged
+    pub fn cursor_moved(&mut self, position: dpi::PhysicalPosition<f64>) {
+        self.cursor = Some(position);
+        self.drag_to(position);
+    }
+
+    /// Returns the position of the cursor in normalized device coordinates, if it is within the
+    /// surface
+    fn cursor_ndc(&self) -> Option<[f32; 2]> {
+        self.to_ndc(self.cursor?)
+    }
+
+    /// Converts a pixel position within the window into normalized device coordinates, if it is
+    /// within the surface
+    fn to_ndc(&self, position: dpi::PhysicalPosition<f64>) -> Option<[f32; 2]> {
+        pixel_to_ndc(position, self.config.width, self.config.height)
+    }
+
+    /// Starts dragging the flat view from the given pixel position, or stops dragging if `None`
+    fn start_drag(&mut self, position: Option<dpi::PhysicalPosition<f64>>) {
+        self.drag_anchor = position
+            .filter(|_| self.camera_mode == CameraMode::Orthographic)
+            .and_then(|pixels| self.to_ndc(pixels))
+            .map(|ndc| self.ortho_camera.ndc_to_plane(ndc));
+    }
+
+    /// Pans the flat view so that the point grabbed when dragging started is at the given pixel
+    /// position. Does nothing if not dragging
+    fn drag_to(&mut self, position: dpi::PhysicalPosition<f64>) {
+        if let (Some(anchor), Some(ndc)) = (self.drag_anchor, self.to_ndc(position)) {
+            self.ortho_camera.pan_to(anchor, ndc);
+            self.update_camera_binding();
+        }
+    }
+
+    /// Handles a finger touching, moving across, or leaving the screen.
+    /// A single touch spawns a particle, like a left click, while two or more touches pan the
+    /// flat view around their centroid, like dragging with the middle button
+    pub fn touch(&mut self, id: u64, phase: TouchPhase, location: dpi::PhysicalPosition<f64>) {
+        match phase {
+            TouchPhase::Started => {
+                self.touches.insert(id, location);
+                match self.touches.len() {
+                    1 => self.spawn_at(location),
+                    _ => self.start_drag(self.touch_centroid()),
+                }
+            }
+            TouchPhase::Moved => {
+                self.touches.insert(id, location);
+                if let Some(centroid) = self.touch_centroid() {
+                    self.drag_to(centroid);
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touches.remove(&id);
+                // Re-anchor around the remaining touches, so the view does not jump
+                self.start_drag(self.touch_centroid());
+            }
+        }
+    }
+
+    /// Returns the centroid of the active touches, if there are enough to pan with
+    #[expect(
+        clippy::as_conversions,
+        clippy::cast_precision_loss,
+        reason = "There are only ever a handful of touches"
+    )]
+    #[expect(
+        clippy::float_arithmetic,
+        reason = "The centroid is the average of the touches' floating-point positions"
+    )]
+    fn touch_centroid(&self) -> Option<dpi::PhysicalPosition<f64>> {
+        if self.touches.len() < 2 {
+            return None;
+        }
+        let count = self.touches.len() as f64;
+        let (x, y) = self
+            .touches
+            .values()
+            .fold((0.0_f64, 0.0_f64), |(x, y), touch| {
+                (x + touch.x, y + touch.y)
+            });
+        Some(dpi::PhysicalPosition::new(x / count, y / count))
+    }
+
+    /// Handles the mouse wheel being scrolled, which zooms the flat view around the cursor
+    #[expect(
+        clippy::as_conversions,
+        clippy::cast_possible_truncation,
+        reason = "Scroll amounts are far smaller than the range of an `f32`"
+    )]
+    #[expect(
+        clippy::float_arithmetic,
+        reason = "Zooming scales the view exponentially with the distance scrolled"
+    )]
+    pub fn mouse_wheel(&mut self, delta: MouseScrollDelta) {
+        if self.camera_mode != CameraMode::Orthographic {
+            return;
+        }
+        let lines = match delta {
+            MouseScrollDelta::LineDelta(_, lines) => lines,
+            MouseScrollDelta::PixelDelta(pixels) => (pixels.y / PIXELS_PER_LINE) as f32,
+        };
+        let zoom =
+            (self.ortho_camera.zoom * ZOOM_PER_LINE.powf(lines)).clamp(ZOOM_RANGE.0, ZOOM_RANGE.1);
+        let ndc = self.cursor_ndc().unwrap_or([0.0, 0.0]);
+        self.ortho_camera.zoom_at(ndc, zoom);
+        self.update_camera_binding();
+    }
+
+    /// Handles a mouse button being pressed or released
+    pub fn mouse_input(&mut self, state: ElementState, button: MouseButton) {
+        match button {
+            // Clicking a particle selects it, while clicking empty space deselects and spawns one
+            MouseButton::Left => {
+                self.pushing = state == ElementState::Pressed;
+                if let Some(cursor) = self.cursor.filter(|_| self.pushing) {
+                    if let Some(index) = self.particle_at(cursor) {
+                        self.select(index);
+                    } else {
+                        self.deselect();
+                        self.spawn_at(cursor);
+                    }
+                }
+            }
+            // Clicking captures the cursor to look around until it is released. Until the capture
+            // is granted, looking around lasts only while the button is held
+            MouseButton::Right => {
+                if state == ElementState::Pressed {
+                    self.camera_controller.set_looking(true);
+                    self.request_pointer_lock();
+                } else if !self.pointer_lock.is_locked() {
+                    self.camera_controller.set_looking(false);
+                } else {
+                    // Releasing the button keeps looking around while the cursor is captured
+                }
+            }
+            MouseButton::Middle => {
+                self.start_drag(self.cursor.filter(|_| state == ElementState::Pressed));
+            }
+            MouseButton::Back | MouseButton::Forward | MouseButton::Other(_) => {}
+        }
+    }
+
+    /// Asks for the cursor to be captured, so that looking around with the mouse continues
+    /// without the cursor leaving the window. Platforms that answer later do so through
+    /// `pointer_lock_changed`
+    fn request_pointer_lock(&mut self) {
+        if self.pointer_lock != PointerLock::Unlocked {
+            return;
+        }
+        self.pointer_lock = self.pointer_lock.requested();
+        let granted = self.window().map_or(Some(false), pointer_lock::request);
+        if let Some(locked) = granted {
+            self.pointer_lock_changed(locked);
+        }
+    }
+
+    /// Handles the cursor being captured or released, whether in answer to a request or because
+    /// the platform released it. Looking around lasts exactly as long as the capture
+    fn pointer_lock_changed(&mut self, locked: bool) {
+        let previous = self.pointer_lock;
+        self.pointer_lock = PointerLock::reported(locked);
+        if locked {
+            info!("Captured the cursor; press Escape to release it");
+        } else if previous == PointerLock::Pending {
+            warn!("The request to capture the cursor was denied");
+        } else {
+            info!("The platform released the cursor");
+        }
+        self.camera_controller.set_looking(locked);
+    }
+
+    /// Returns whether the cursor is captured for looking around
+    pub const fn is_pointer_locked(&self) -> bool {
+        self.pointer_lock.is_locked()
+    }
+
+    /// Releases the cursor if it is captured or has been requested to be, and stops looking
+    /// around
+    pub fn release_pointer_lock(&mut self) {
+        if self.pointer_lock == PointerLock::Unlocked {
+            return;
+        }
+        if let Some(window) = self.window() {
+            pointer_lock::release(window);
+        }
+        self.pointer_lock = PointerLock::Unlocked;
+        self.camera_controller.set_looking(false);
+    }
+
+    /// Handles relative motion of the mouse, which turns the camera while looking around
+    pub fn mouse_motion(&mut self, delta: (f64, f64)) {
+        self.camera_controller.process_mouse_motion(delta);
+    }
+
+    /// Spawns a stationary particle under the given pixel position, if it is within the surface,
+    /// with a radius drawn from the size distribution
+    fn spawn_at(&mut self, pixels: dpi::PhysicalPosition<f64>) {
+        let Some(position) = self.to_ndc(pixels).and_then(|ndc| self.ndc_to_plane(ndc)) else {
+            return;
+        };
+        self.apply_input(InputEvent::Spawn(position));
+    }
+
+    /// Returns the optional features enabled on the device
+    pub const fn enabled_features(&self) -> wgpu::Features {
+        self.features
+    }
+
+    /// Returns how long the GPU spent on the most recent render pass, or `None` if this is
+    /// unsupported or no frame has been rendered yet
+    pub fn last_gpu_frame_time(&self) -> Option<Duration> {
+        self.gpu_timer.as_ref().and_then(GpuTimer::last)
+    }
+
+    /// Returns the number of particles removed so far for having non-finite state, which
+    /// indicates that the physics has become unstable
+    pub fn non_finite_count(&self) -> usize {
+        self.simulation.non_finite_count()
+    }
+
+    /// Returns the average frame rate over recent frames
+    pub fn fps(&self) -> f32 {
+        self.frame_timer.fps()
+    }
+
+    /// Returns the performance measurements of the most recent frame
+    pub fn metrics(&self) -> Metrics {
+        Metrics {
+            cpu_step: self.cpu_step,
+            cpu_render_encode: self.cpu_render_encode,
+            gpu_frame: self.last_gpu_frame_time(),
+            fps: self.fps(),
+            particles: self.simulation.particles().len(),
+        }
+    }
+
+    /// Returns the color the surface is cleared to before drawing
+    pub const fn background(&self) -> wgpu::Color {
+        self.background
+    }
+
+    /// Sets the color the surface is cleared to before drawing, in linear space
+    pub fn set_background(&mut self, color: wgpu::Color) {
+        self.background = color;
+        self.trails.set_fade(&self.queue, color, self.trail_fade);
+    }
+
+    /// Sets the color the surface is cleared to before drawing, from 8-bit sRGB components.
+    /// The color components are converted to linear space, while alpha is kept linear
+    #[cfg_attr(
+        not(test),
+        expect(
+            dead_code,
+            reason = "Offered for colors picked as 8-bit sRGB; the crate's own colors are set in \
+                      linear space"
+        )
+    )]
+    #[expect(
+        clippy::min_ident_chars,
+        reason = "Single-letter names are conventional for color components"
+    )]
+    pub fn set_background_rgba8(&mut self, r: u8, g: u8, b: u8, a: u8) {
+        self.set_background(color::srgb8a(r, g, b, a));
+    }
+
+    /// Draws the background as a vertical gradient between the given colors, in linear space,
+    /// instead of a flat color
+    pub fn set_gradient(&mut self, top: wgpu::Color, bottom: wgpu::Color) {
+        self.gradient.set_colors(&self.queue, top, bottom);
+        self.gradient_enabled = true;
+    }
+
+    /// Switches to the given visual theme, setting the flat and gradient backgrounds and the colors
+    /// of particles together. Whether the gradient is drawn is kept as it was
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+        self.set_background(theme.background());
+        let (top, bottom) = theme.gradient();
+        self.gradient.set_colors(&self.queue, top, bottom);
+        if !self.simulation.set_palette(theme.palette()) {
+            warn!("Simulation does not support the colors of the {theme:?} theme");
+        }
+        info!("Switched to the {theme:?} theme");
+    }
+
+    /// Returns the colors at the top and bottom of the gradient background
+    pub const fn gradient(&self) -> (wgpu::Color, wgpu::Color) {
+        self.gradient.colors()
+    }
+
+    /// Switches between drawing the background as the gradient and clearing it to the flat
+    /// background color. Trails always fade towards the flat color
+    pub fn set_gradient_enabled(&mut self, enabled: bool) {
+        self.gradient_enabled = enabled;
+    }
+
+    /// Sets how quickly motion trails fade: each frame, the background is drawn over the previous
+    /// one with this opacity, clamped into `[0, 1]`. At `1`, every frame is fully cleared and
+    /// there are no trails; lower opacities leave longer trails
+    pub fn set_trail_fade(&mut self, alpha: f32) {
+        self.trail_fade = trails::clamp_fade(alpha);
+        self.trails
+            .set_fade(&self.queue, self.background, self.trail_fade);
+    }
+
+    /// Returns a human-readable summary of what the GPU supports and what was selected from it:
+    /// the adapter, the formats, present modes, and alpha modes that the surface supports, the
+    /// surface configuration in use, the key limits of the device, and how many particles they fit.
+    /// When rendering offscreen, only the configuration and limits are known
+    pub fn capabilities_report(&self) -> String {
+        let mut lines = Vec::new();
+        if let (Some(surface), Some(adapter)) = (self.surface(), self.adapter()) {
+            let info = adapter.get_info();
+            lines.push(format!(
+                "Adapter: {} ({:?} backend, {:?}, driver {} {})",
+                info.name, info.backend, info.device_type, info.driver, info.driver_info
+            ));
+            let caps = surface.get_capabilities(adapter);
+            lines.push(format!("Supported formats: {:?}", caps.formats));
+            lines.push(format!("Supported present modes: {:?}", caps.present_modes));
+            lines.push(format!("Supported alpha modes: {:?}", caps.alpha_modes));
+        }
+        lines.push(format!(
+            "Selected: format {:?} ({:?}), present mode {:?}, alpha mode {:?}, {} MSAA samples",
+            self.surface_format(),
+            self.gamma_handling(),
+            self.config.present_mode,
+            self.config.alpha_mode,
+            self.msaa_samples()
+        ));
+        let limits = self.device.limits();
+        lines.push(format!(
+            "Limits: 2D textures up to {}, buffers up to {} bytes, storage bindings up to {} bytes, \
+             {} invocations per workgroup",
+            limits.max_texture_dimension_2d,
+            limits.max_buffer_size,
+            limits.max_storage_buffer_binding_size,
+            limits.max_compute_invocations_per_workgroup
+        ));
+        lines.push(format!(
+            "Particles: up to {} can be simulated at once",
+            self.max_particles_supported()
+        ));
+        lines.push(format!("Enabled features: {:?}", self.enabled_features()));
+        lines.join("\n")
+    }
+
+    /// Returns how colors are encoded for display on the target
+    pub const fn gamma_handling(&self) -> GammaHandling {
+        self.gamma
+    }
+
+    /// Returns the format that frames are drawn in, which views of the target are created with
+    fn render_format(&self) -> wgpu::TextureFormat {
+        self.gamma.render_format(self.config.format)
+    }
+
+    /// Returns the device doing the rendering and computation
+    pub fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    /// Creates a diagnostics window rendering to the given window, sharing this application's
+    /// device and queue
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn open_diagnostics(&self, window: Arc<Window>) -> Result<DiagnosticsWindow, AppError> {
+        let (instance, adapter) = match self.target {
+            RenderTarget::Window {
+                ref instance,
+                ref adapter,
+                ..
+            } => (instance, adapter),
+            #[cfg(test)]
+            RenderTarget::Offscreen(_) => return Err(AppError::Offscreen),
+        };
+        let size = window.inner_size();
+        let surface = instance
+            .create_surface(Arc::clone(&window))
+            .map_err(AppError::Surface)?;
+        let surface_caps = surface.get_capabilities(adapter);
+        let mut config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: select_surface_format(
+                self.initial_config.surface_formats,
+                &surface_caps.formats,
+            )
+            .ok_or(AppError::NoSurfaceFormats)?,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            // FIFO is supported everywhere, and statistics need not be shown with low latency
+            present_mode: wgpu::PresentMode::Fifo,
+            alpha_mode: surface_caps
+                .alpha_modes
+                .first()
+                .copied()
+                .ok_or(AppError::NoAlphaMode)?,
+            view_formats: vec![],
+            desired_maximum_frame_latency: self.config.desired_maximum_frame_latency,
+        };
+        GammaHandling::for_format(config.format).configure(&mut config);
+        Ok(DiagnosticsWindow::new(
+            &self.device,
+            window,
+            surface,
+            config,
+        ))
+    }
+
+    /// Renders the current statistics of the application into the given diagnostics window
+    pub fn render_diagnostics(
+        &self,
+        diagnostics: &mut DiagnosticsWindow,
+    ) -> Result<(), SurfaceError> {
+        let text = diagnostics::diagnostics_text(
+            self.fps(),
+            self.simulation.particles().len(),
+            self.last_gpu_frame_time(),
+        );
+        diagnostics.render(&self.device, &self.queue, &text)
+    }
+
+    /// Returns the window to which this application is attached, or `None` if rendering
+    /// offscreen
+    #[cfg_attr(
+        not(test),
+        expect(
+            clippy::unnecessary_wraps,
+            reason = "Only tests render offscreen, where there is no window"
+        )
+    )]
+    pub fn window(&self) -> Option<&Window> {
+        match self.target {
+            RenderTarget::Window { ref window, .. } => Some(window),
+            #[cfg(test)]
+            RenderTarget::Offscreen(_) => None,
+        }
+    }
+
+    /// Renders the current state of the application to the surface, or to the offscreen texture
+    pub fn render(&mut self) -> Result<(), SurfaceError> {
+        // Acquiring a frame from a surface with no area fails, and frames for a hidden window are
+        // never seen, so nothing is drawn until the window is resized or shown
+        if !self.is_renderable() {
+            return Ok(());
+        }
+        self.update_hud();
+        self.gui.prepare(&self.device, &self.queue);
+        self.cpu_render_encode = match self.target {
+            RenderTarget::Window { ref surface, .. } => {
+                let output = surface.get_current_texture()?;
+                let encode = self.draw(&output.texture);
+                output.present();
+                encode
+            }
+            #[cfg(test)]
+            RenderTarget::Offscreen(ref texture) => self.draw(texture),
+        };
+        self.finish_frame();
+        Ok(())
+    }
+
+    /// Renders the current state of the application offscreen, returning the rendered RGBA
+    /// pixels row by row, starting from the top left
+    #[cfg(test)]
+    pub fn render_offscreen(&mut self) -> Result<Vec<u8>, OffscreenError> {
+        self.update_hud();
+        self.gui.prepare(&self.device, &self.queue);
+        let RenderTarget::Offscreen(ref texture) = self.target else {
+            return Err(OffscreenError::NotOffscreen);
+        };
+        let encode = self.draw(texture);
+        let pixels = readback::read_texture(&self.device, &self.queue, texture)
+            .map_err(OffscreenError::Readback)?;
+        self.cpu_render_encode = encode;
+        self.finish_frame();
+        Ok(pixels)
+    }
+
+    /// Renders the current state of the application and encodes it into a PNG image
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn capture_frame(&self) -> Result<Vec<u8>, CaptureError> {
+        let texture = create_offscreen_texture(
+            &self.device,
+            &wgpu::SurfaceConfiguration {
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+                ..self.config.clone()
+            },
+        );
+        self.draw(&texture);
+        let mut pixels = readback::read_texture(&self.device, &self.queue, &texture)
+            .map_err(CaptureError::Readback)?;
+        if !capture::to_rgba8(self.config.format, &mut pixels) {
+            return Err(CaptureError::UnsupportedFormat(self.config.format));
+        }
+        capture::encode_png(texture.width(), texture.height(), &pixels)
+    }
+
+    /// Shows or hides the HUD of statistics
+    pub fn set_hud_visible(&mut self, visible: bool) {
+        self.hud_visible = visible;
+    }
+
+    /// Lays out the HUD with the latest statistics, for the current size of the surface
+    fn update_hud(&mut self) {
+        if self.hud_visible {
+            let metrics = self.metrics();
+            let text = hud::hud_text(metrics.fps, metrics.particles);
+            self.hud
+                .set_text(&self.queue, &text, self.config.width, self.config.height);
+        }
+    }
+
+    /// Draws the current state of the application into the given texture.
+    /// Returns the CPU time spent encoding and submitting the commands
+    fn draw(&self, texture: &wgpu::Texture) -> Duration {
+        let start = Instant::now();
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(self.render_format()),
+            ..wgpu::TextureViewDescriptor::default()
+        });
+        let scene = Scene {
+            msaa_view: self.msaa_texture.as_ref().map(|msaa| &msaa.1),
+            depth_view: &self.depth_view,
+            background: self.background,
+            trails: self.active_trails(),
+            gradient: self.gradient_enabled.then_some(&self.gradient),
+            camera_binding: &self.camera_binding,
+            simulation: self.simulation.as_ref(),
+            overlays: [&self.collider_overlay, &self.selection_overlay],
+            bounds: self.bounds_visible.then_some(&self.bounds),
+            gpu_timer: self.gpu_timer.as_ref(),
+        };
+        let mut stages: Vec<&dyn RenderStage> = vec![&scene];
+        // With trails, frames accumulate in a persistent texture that is copied onto the output
+        if let Some(trails) = self.active_trails() {
+            stages.push(trails);
+        }
+        if self.hud_visible {
+            stages.push(&self.hud);
+        }
+        stages.push(&self.gui);
+        if let Some(ref gpu_timer) = self.gpu_timer {
+            stages.push(gpu_timer);
+        }
+        stage::submit(&self.device, &self.queue, &view, &stages);
+        start.elapsed()
+    }
+
+    /// Returns the trails that frames accumulate in, if trails are on
+    fn active_trails(&self) -> Option<&Trails> {
+        (self.trail_fade < 1.0).then_some(&self.trails)
+    }
+
+    /// Records that a frame has been drawn, for measuring the frame rate
+    fn finish_frame(&mut self) {
+        if let Some(ref mut gpu_timer) = self.gpu_timer {
+            gpu_timer.read(&self.device);
+        }
+
+        let now = Instant::now();
+        self.frame_timer.frame(now);
+        if self.frame_timer.should_report(now) {
+            info!(
+                "{}",
+                frame_timer::frame_stats(
+                    self.fps(),
+                    self.simulation.particles().len(),
+                    self.last_gpu_frame_time()
+                )
+            );
+        }
+    }
+}
+
+impl Drop for Application<'_> {
+    /// Waits for all submitted GPU work to finish, so that no resource is released while still
+    /// in use
+    fn drop(&mut self) {
+        self.device.poll(wgpu::Maintain::Wait);
+    }
+}
+
+/// Clamps a requested maximum frame latency to the supported range
+fn clamp_frame_latency(frames: u32) -> u32 {
+    frames.clamp(FRAME_LATENCY_RANGE.0, FRAME_LATENCY_RANGE.1)
+}
+
+/// Returns the ratio of a surface's width to its height, or `1` if the surface is empty
+#[expect(
+    clippy::as_conversions,
+    clippy::cast_precision_loss,
+    reason = "Surface dimensions are small enough to be represented exactly"
+)]
+#[expect(clippy::float_arithmetic, reason = "An aspect ratio is a fraction")]
+fn aspect_ratio(width: u32, height: u32) -> f32 {
+    if height == 0 {
+        1.0
+    } else {
+        width as f32 / height as f32
+    }
+}
+
+/// Returns half the width and height of a world of the given size, in meters, or if it has no
+/// fixed size, of the flat view at unit zoom with the given aspect ratio
+#[expect(
+    clippy::float_arithmetic,
+    reason = "World sizes are in continuous world coordinates"
+)]
+fn world_half_extents(world_size: Option<[f32; 2]>, aspect: f32) -> [f32; 2] {
+    world_size.map_or([aspect, 1.0], |size| size.map(|extent| extent / 2.0))
+}
+
+/// Converts a pixel position on a surface of the given size into normalized device coordinates.
+/// Returns `None` if the surface is empty or the position lies outside of it
+#[expect(
+    clippy::as_conversions,
+    clippy::cast_possible_truncation,
+    reason = "Normalized device coordinates lie in [-1, 1], well within `f32` precision"
+)]
+#[expect(
+    clippy::float_arithmetic,
+    reason = "Normalized device coordinates are continuous"
+)]
+fn pixel_to_ndc(position: dpi::PhysicalPosition<f64>, width: u32, height: u32) -> Option<[f32; 2]> {
+    let (surface_width, surface_height) = (f64::from(width), f64::from(height));
+    if !(0.0_f64..surface_width).contains(&position.x)
+        || !(0.0_f64..surface_height).contains(&position.y)
+    {
+        return None;
+    }
+    Some([
+        (2.0 * position.x / surface_width - 1.0) as f32,
+        (1.0 - 2.0 * position.y / surface_height) as f32,
+    ])
+}
+
+/// Creates a multisampled color buffer of the given format matching the size of the surface,
+/// along with a view into it.
+/// Returns `None` if anti-aliasing is disabled, i.e. there is only one sample per pixel
+fn create_msaa_texture(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> Option<(wgpu::Texture, wgpu::TextureView)> {
+    (sample_count > 1).then(|| {
+        create_attachment(
+            device,
+            config,
+            format,
+            sample_count,
+            "Multisampled Color Texture",
+        )
+    })
+}
+
+/// Creates a render attachment texture matching the size of the surface, along with a view into it.
+/// Empty dimensions are rounded up to a single texel, since textures cannot be empty
+fn create_attachment(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+    label: &str,
+) -> (wgpu::Texture, wgpu::TextureView) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width: config.width.max(1),
+            height: config.height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
+
+/// Creates a texture matching the configuration to render into offscreen
+#[cfg(any(test, not(target_arch = "wasm32")))]
+fn create_offscreen_texture(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Offscreen Texture"),
+        size: wgpu::Extent3d {
+            width: config.width.max(1),
+            height: config.height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: config.usage,
+        view_formats: &config.view_formats,
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// How the fragments drawn by a pipeline combine with what is already drawn
+pub(super) enum Blending {
+    /// Fragments replace what lies behind them, and write their depth
+    Opaque,
+    /// Fragments are blended over what lies behind them by their alpha. Depth is not written, so
+    /// that translucent fragments do not hide what is drawn after them
+    Alpha,
+}
+
+impl Blending {
+    /// Returns the blend state of the color target
+    pub const fn state(self) -> wgpu::BlendState {
+        match self {
+            Self::Opaque => wgpu::BlendState::REPLACE,
+            Self::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+        }
+    }
+
+    /// Returns whether fragments write their depth
+    const fn writes_depth(self) -> bool {
+        match self {
+            Self::Opaque => true,
+            Self::Alpha => false,
+        }
+    }
+}
+
+/// Creates an opaque pipeline drawing the given primitives onto a surface of the given format,
+/// using the given bind groups. The shader must have `vs_main` and `fs_main` entry points
+pub(super) fn create_render_pipeline(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    shader: &wgpu::ShaderModule,
+    buffers: &[wgpu::VertexBufferLayout<'_>],
+    topology: wgpu::PrimitiveTopology,
+    sample_count: u32,
+) -> wgpu::RenderPipeline {
+    create_blended_render_pipeline(
+        device,
+        format,
+        bind_group_layouts,
+        shader,
+        buffers,
+        topology,
+        sample_count,
+        ("fs_main", Blending::Opaque),
+    )
+}
+
+/// Creates a pipeline like [`create_render_pipeline`], with the given fragment entry point and
+/// blending
+#[expect(
+    clippy::too_many_arguments,
+    reason = "Each argument configures an independent part of the pipeline"
+)]
+pub(super) fn create_blended_render_pipeline(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    bind_group_layouts: &[&wgpu::BindGroupLayout],
+    shader: &wgpu::ShaderModule,
+    buffers: &[wgpu::VertexBufferLayout<'_>],
+    topology: wgpu::PrimitiveTopology,
+    sample_count: u32,
+    (fragment_entry, blending): (&str, Blending),
+) -> wgpu::RenderPipeline {
+    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Render Pipeline Layout"),
+        bind_group_layouts,
+        push_constant_ranges: &[],
+    });
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Render Pipeline"),
+        layout: Some(&render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_main",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            buffers,
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: fragment_entry,
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(blending.state()),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: blending.writes_depth(),
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
+        multiview: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::Cell, time::Duration};
+    use std::rc::Rc;
+
+    use winit::{
+        dpi,
+        event::{MouseScrollDelta, TouchPhase},
+    };
+
+    use super::{
+        aspect_ratio, color, pixel_to_ndc, Action, AppError, Application, Controls, ResizeError,
+        MAX_PARTICLES, TIMESTEP,
+    };
+    use crate::app::{
+        bounds::boundary_vertices,
+        config::{RedrawPolicy, SimulationConfig},
+        device,
+        particles::{Environment, Particle, RADIUS},
+        recording::InputEvent,
+        simulation::{Simulation, SimulationContext},
+        state::SimulationState,
+        theme::Theme,
+    };
+
+    /// Returns an application rendering a small offscreen texture, or `None`, skipping the test,
+    /// if there is no adapter to render with
+    fn headless(config: &SimulationConfig) -> Option<Application<'static>> {
+        let (device, queue) = device::test_device()?;
+        Some(Application::new_offscreen(device, queue, 64, 64, config))
+    }
+
+    #[test]
+    fn state_round_trips() {
+        let Some(mut app) = headless(&SimulationConfig {
+            particle_count: 32,
+            seed: Some(1),
+            ..SimulationConfig::default()
+        }) else {
+            return;
+        };
+        app.step_once();
+        let saved = app.save_state();
+        app.set_gravity(5.0);
+        app.step_once();
+        app.step_once();
+        assert_ne!(app.save_state(), saved);
+        app.load_state(saved.clone());
+        assert_eq!(app.save_state(), saved);
+    }
+
+    #[test]
+    fn offscreen_frames_are_cleared_to_the_background() {
+        let Some(mut app) = headless(&SimulationConfig {
+            particle_count: 1,
+            ..SimulationConfig::default()
+        }) else {
+            return;
+        };
+        let pixels = app
+            .render_offscreen()
+            .expect("rendering offscreen should succeed");
+        assert_eq!(pixels.len(), 64 * 64 * 4);
+        let [red, green, blue] = color::TEAL;
+        let (background, covered): (Vec<_>, Vec<_>) = pixels.chunks_exact(4).partition(|pixel| {
+            pixel
+                .iter()
+                .zip([red, green, blue, u8::MAX])
+                .all(|(&actual, expected)| actual.abs_diff(expected) <= 2)
+        });
+        // Only the particle, the HUD, and the outline of the world cover the background
+        assert!(
+            background.len() > covered.len(),
+            "only {} pixels show the background",
+            background.len()
+        );
+    }
+
+    #[test]
+    fn background_is_kept_as_set() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        let color = wgpu::Color {
+            r: 0.25,
+            g: 0.5,
+            b: 0.75,
+            a: 1.0,
+        };
+        app.set_background(color);
+        assert_eq!(app.background(), color);
+    }
+
+    #[test]
+    fn eight_bit_backgrounds_are_linearized() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        app.set_background_rgba8(188, 0, 255, 128);
+        assert_eq!(app.background(), color::srgb8a(188, 0, 255, 128));
+    }
+
+    #[test]
+    fn leftover_time_is_carried_forward() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        let steps = app.advance(Duration::from_millis(50), Duration::from_millis(16));
+        assert_eq!(steps, 3);
+        assert_eq!(app.accumulator, Duration::from_millis(2));
+    }
+
+    #[test]
+    fn pausing_twice_resumes() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        assert!(!app.is_paused());
+        app.perform(Action::TogglePause);
+        assert!(app.is_paused());
+        app.perform(Action::TogglePause);
+        assert!(!app.is_paused());
+    }
+
+    #[test]
+    fn pixels_map_onto_normalized_device_coordinates() {
+        let ndc = |x, y| pixel_to_ndc(dpi::PhysicalPosition::new(x, y), 800, 600);
+        assert_eq!(ndc(0.0_f64, 0.0_f64), Some([-1.0, 1.0]));
+        assert_eq!(ndc(400.0_f64, 300.0_f64), Some([0.0, 0.0]));
+        assert_eq!(ndc(600.0_f64, 450.0_f64), Some([0.5, -0.5]));
+        assert_eq!(ndc(800.0_f64, 300.0_f64), None);
+        assert_eq!(ndc(400.0_f64, -1.0_f64), None);
+    }
+
+    #[test]
+    #[expect(clippy::float_cmp, reason = "Halving a power of two is exact")]
+    fn resizing_updates_the_aspect_ratio() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        app.resize(dpi::PhysicalSize::new(128, 64))
+            .expect("resizing to a non-empty size should succeed");
+        assert_eq!(app.camera.aspect, 2.0);
+        assert_eq!(app.ortho_camera.aspect, 2.0);
+    }
+
+    #[test]
+    fn only_the_last_requested_size_is_applied() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        app.request_resize(dpi::PhysicalSize::new(100, 50));
+        app.request_resize(dpi::PhysicalSize::new(80, 40));
+        app.apply_pending_resize();
+        assert_eq!((app.config.width, app.config.height), (80, 40));
+        assert!(app.pending_resize.is_none());
+    }
+
+    #[test]
+    #[expect(clippy::float_cmp, reason = "The scale factor is stored as given")]
+    fn scale_factor_changes_are_stored() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        app.scale_factor_changed(2.0, dpi::PhysicalSize::new(128, 128));
+        assert_eq!(app.scale_factor(), 2.0_f64);
+        assert_eq!(app.pending_resize, Some(dpi::PhysicalSize::new(128, 128)));
+    }
+
+    #[test]
+    fn same_seed_spawns_the_same_particles() {
+        let config = SimulationConfig {
+            particle_count: 16,
+            seed: Some(7),
+            ..SimulationConfig::default()
+        };
+        let (Some(first), Some(second)) = (headless(&config), headless(&config)) else {
+            return;
+        };
+        assert_eq!(first.save_state(), second.save_state());
+    }
+
+    #[test]
+    fn scrolling_back_restores_the_zoom() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        let zoom = app.ortho_camera.zoom;
+        app.mouse_wheel(MouseScrollDelta::LineDelta(0.0, 3.0));
+        assert!(app.ortho_camera.zoom > zoom);
+        app.mouse_wheel(MouseScrollDelta::LineDelta(0.0, -3.0));
+        assert!((app.ortho_camera.zoom - zoom).abs() < 1e-5);
+    }
+
+    #[test]
+    fn touches_map_onto_the_view_like_the_cursor() {
+        let Some(mut app) = headless(&SimulationConfig {
+            particle_count: 0,
+            ..SimulationConfig::default()
+        }) else {
+            return;
+        };
+        app.touch(
+            0,
+            TouchPhase::Started,
+            dpi::PhysicalPosition::new(16.0_f64, 16.0_f64),
+        );
+        app.touch(
+            1,
+            TouchPhase::Started,
+            dpi::PhysicalPosition::new(48.0_f64, 48.0_f64),
+        );
+        let centroid = app
+            .touch_centroid()
+            .expect("two touches should have a centroid");
+        assert_eq!(centroid, dpi::PhysicalPosition::new(32.0_f64, 32.0_f64));
+        app.cursor = Some(centroid);
+        assert_eq!(app.to_ndc(centroid), Some([0.0, 0.0]));
+        assert_eq!(app.to_ndc(centroid), app.cursor_ndc());
+    }
+
+    /// A simulation that only counts the steps it takes
+    struct CountingSimulation(Rc<Cell<u32>>);
+
+    impl Simulation for CountingSimulation {
+        fn step(&mut self, _: f32, _: &Environment) {
+            self.0.set(self.0.get().saturating_add(1));
+        }
+
+        fn upload(&mut self, _: &wgpu::Queue) {}
+
+        fn draw<'pass>(&'pass self, _: &mut wgpu::RenderPass<'pass>) {}
+
+        fn rebuild_pipelines(&mut self, _: &SimulationContext<'_>) {}
+    }
+
+    #[test]
+    fn elapsed_time_is_simulated_in_whole_steps() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        let steps = Rc::new(Cell::new(0));
+        app.simulation = Box::new(CountingSimulation(Rc::clone(&steps)));
+        app.advance(Duration::from_millis(35), Duration::from_millis(10));
+        assert_eq!(steps.get(), 3);
+    }
+
+    #[test]
+    #[expect(clippy::float_cmp, reason = "The adjusted gravity is applied as given")]
+    fn adjusting_a_slider_applies_the_setting() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        let before = app.controls();
+        let after = Controls {
+            gravity: -3.0,
+            ..before
+        };
+        app.apply_controls(&before, &after);
+        assert_eq!(app.environment.gravity, -3.0);
+        assert_eq!(app.controls(), after);
+    }
+
+    #[test]
+    fn unsupported_present_modes_are_not_switched_to() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        app.present_modes = vec![wgpu::PresentMode::Fifo];
+        app.config.present_mode = wgpu::PresentMode::Fifo;
+        app.set_vsync(false);
+        assert!(app.vsync());
+        app.present_modes.push(wgpu::PresentMode::Immediate);
+        app.set_vsync(false);
+        assert!(!app.vsync());
+    }
+
+    #[test]
+    fn deliberate_pauses_outlast_regaining_focus() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        app.focus_changed(false);
+        assert!(app.is_paused());
+        app.focus_changed(true);
+        assert!(!app.is_paused());
+
+        app.focus_changed(false);
+        app.set_paused(true);
+        app.focus_changed(true);
+        assert!(app.is_paused());
+        app.set_paused(false);
+        assert!(!app.is_paused());
+    }
+
+    #[test]
+    fn frame_latency_is_applied_within_range() {
+        let Some(mut app) = headless(&SimulationConfig {
+            frame_latency: 1,
+            ..SimulationConfig::default()
+        }) else {
+            return;
+        };
+        assert_eq!(app.config.desired_maximum_frame_latency, 1);
+        app.set_frame_latency(3);
+        assert_eq!(app.config.desired_maximum_frame_latency, 3);
+        app.set_frame_latency(10);
+        assert_eq!(app.config.desired_maximum_frame_latency, 3);
+        app.set_frame_latency(0);
+        assert_eq!(app.config.desired_maximum_frame_latency, 1);
+    }
+
+    /// Returns an application simulating a single paused particle at the origin, moving at the
+    /// given velocity without any gravity or damping
+    fn single_particle(velocity: [f32; 2]) -> Option<Application<'static>> {
+        let mut app = headless(&SimulationConfig::default())?;
+        app.load_state(SimulationState {
+            particles: vec![Particle::new([0.0, 0.0], velocity, RADIUS)],
+            gravity: 0.0,
+            paused: true,
+        });
+        app.set_damping(0.0);
+        Some(app)
+    }
+
+    #[test]
+    fn a_single_step_advances_by_one_timestep() {
+        let Some(mut app) = single_particle([1.0, 0.0]) else {
+            return;
+        };
+        app.step_once();
+        let [particle] = app.save_state().particles[..] else {
+            panic!("the particle should remain");
+        };
+        assert!((particle.position[0] - TIMESTEP.as_secs_f32()).abs() < 1e-6);
+        assert!(particle.position[1].abs() < 1e-6);
+    }
+
+    #[test]
+    fn double_speed_takes_twice_the_steps() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        // Three whole timesteps, with a sliver left over
+        let elapsed = Duration::from_millis(25);
+        assert_eq!(app.simulate(elapsed), 3);
+        app.accumulator = Duration::ZERO;
+        app.set_time_scale(2.0);
+        assert_eq!(app.simulate(elapsed), 6);
+    }
+
+    #[test]
+    fn gradient_is_kept_and_can_be_turned_off() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        let background = app.background();
+        let (top, bottom) = (wgpu::Color::RED, wgpu::Color::BLUE);
+        app.set_gradient(top, bottom);
+        assert_eq!(app.gradient(), (top, bottom));
+        assert!(app.gradient_enabled);
+        app.perform(Action::ToggleGradient);
+        assert!(!app.gradient_enabled);
+        assert_eq!(app.background(), background);
+    }
+
+    #[test]
+    fn offscreen_applications_are_dropped_cleanly() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        app.update();
+        app.render().expect("rendering offscreen should succeed");
+        drop(app);
+    }
+
+    #[test]
+    fn capabilities_report_names_the_selected_format() {
+        let Some(app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        let report = app.capabilities_report();
+        assert!(
+            report.contains(&format!("{:?}", app.surface_format())),
+            "{report}"
+        );
+    }
+
+    #[test]
+    fn replayed_spawns_happen_at_the_recorded_step() {
+        let Some(mut app) = headless(&SimulationConfig {
+            particle_count: 8,
+            ..SimulationConfig::default()
+        }) else {
+            return;
+        };
+        let count = |simulated: &Application<'_>| simulated.save_state().particles.len();
+        app.start_recording();
+        for _ in 0..5_u32 {
+            app.step_once();
+        }
+        app.apply_input(InputEvent::Spawn([0.0, 0.0]));
+        let recording = app.stop_recording().expect("input should be recorded");
+        assert_eq!(recording.events, [(5, InputEvent::Spawn([0.0, 0.0]))]);
+
+        app.replay(recording);
+        assert_eq!(count(&app), 8);
+        for _ in 0..5_u32 {
+            app.step_once();
+        }
+        assert_eq!(count(&app), 8);
+        app.step_once();
+        assert_eq!(count(&app), 9);
+    }
+
+    #[test]
+    fn removing_more_particles_than_exist_leaves_none() {
+        let Some(mut app) = headless(&SimulationConfig {
+            particle_count: 10,
+            ..SimulationConfig::default()
+        }) else {
+            return;
+        };
+        app.remove_particles(100);
+        assert!(app.save_state().particles.is_empty());
+    }
+
+    #[test]
+    fn aspect_ratio_is_one_for_empty_surfaces() {
+        assert!((aspect_ratio(1600, 900) - 16.0 / 9.0).abs() <= f32::EPSILON);
+        assert!((aspect_ratio(1600, 0) - 1.0).abs() <= f32::EPSILON);
+    }
+
+    #[test]
+    fn reset_respawns_the_starting_particles() {
+        let Some(mut app) = headless(&SimulationConfig {
+            particle_count: 12,
+            seed: Some(3),
+            ..SimulationConfig::default()
+        }) else {
+            return;
+        };
+        let initial = app.save_state().particles;
+        app.add_particles(200);
+        for _ in 0..10_u32 {
+            app.step_once();
+        }
+        app.reset();
+        assert_eq!(app.save_state().particles, initial);
+    }
+
+    #[test]
+    fn hitches_are_caught_up_on_for_at_most_the_step_limit() {
+        let Some(mut app) = headless(&SimulationConfig {
+            max_steps_per_frame: 5,
+            ..SimulationConfig::default()
+        }) else {
+            return;
+        };
+        let steps = Rc::new(Cell::new(0));
+        app.simulation = Box::new(CountingSimulation(Rc::clone(&steps)));
+        app.advance(Duration::from_secs(10), Duration::from_millis(10));
+        assert_eq!(steps.get(), 5);
+        assert_eq!(app.accumulator, Duration::ZERO);
+    }
+
+    #[test]
+    fn paused_idle_applications_stop_redrawing_on_demand() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        app.set_paused(true);
+        app.set_redraw_policy(RedrawPolicy::OnDemand);
+        assert!(app.wants_redraw(), "the change of policy should be drawn");
+        app.schedule_redraw();
+        assert!(!app.wants_redraw());
+        app.set_redraw_policy(RedrawPolicy::Continuous);
+        app.schedule_redraw();
+        assert!(app.wants_redraw());
+    }
+
+    #[test]
+    #[expect(
+        clippy::float_cmp,
+        reason = "The extents of a view twice as wide as it is tall are exact"
+    )]
+    fn outline_follows_the_world_extents_when_resized() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        app.resize(dpi::PhysicalSize::new(128, 64))
+            .expect("resizing to a non-empty size should succeed");
+        let [half_width, half_height] = app.world_half_extents();
+        assert_eq!([half_width, half_height], [2.0, 1.0]);
+        assert_eq!(
+            boundary_vertices(&app.environment),
+            [
+                [-half_width, -half_height],
+                [half_width, -half_height],
+                [half_width, half_height],
+                [-half_width, half_height],
+                [-half_width, -half_height],
+            ]
+        );
+    }
+
+    #[test]
+    #[expect(clippy::float_cmp, reason = "The floor is kept as configured")]
+    fn floor_is_configurable_independently_of_the_world() {
+        let Some(app) = headless(&SimulationConfig {
+            world_size: Some([4.0, 2.0]),
+            floor: Some(-0.5),
+            ..SimulationConfig::default()
+        }) else {
+            return;
+        };
+        assert_eq!(app.environment.floor, -0.5);
+        assert_eq!(app.environment.ceiling, 1.0);
+    }
+
+    #[test]
+    fn metrics_count_the_current_particles() {
+        let Some(mut app) = headless(&SimulationConfig {
+            particle_count: 20,
+            ..SimulationConfig::default()
+        }) else {
+            return;
+        };
+        assert_eq!(app.metrics().particles, 20);
+        app.add_particles(5);
+        app.remove_particles(2);
+        assert_eq!(app.metrics().particles, app.save_state().particles.len());
+        assert_eq!(app.metrics().particles, 23);
+    }
+
+    #[test]
+    fn fire_theme_sets_its_background() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        app.set_theme(Theme::Fire);
+        assert_eq!(app.background(), color::srgb8(40, 8, 4));
+        assert_eq!(app.gradient(), Theme::Fire.gradient());
+    }
+
+    #[test]
+    fn particles_are_found_within_a_radius_across_cells() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        let at = |position| Particle::new(position, [0.0, 0.0], RADIUS);
+        app.load_state(SimulationState {
+            particles: vec![
+                at([0.0, 0.0]),
+                at([0.049, 0.0]),
+                at([-0.06, 0.05]),
+                at([0.0, -0.099]),
+                at([0.101, 0.0]),
+                at([0.5, 0.5]),
+            ],
+            gravity: 0.0,
+            paused: true,
+        });
+        assert_eq!(app.particles_in_radius([0.0, 0.0], 0.1), [0, 1, 2, 3]);
+        assert_eq!(app.particles_in_radius([0.5, 0.5], 0.1), [5]);
+    }
+
+    #[test]
+    fn evicting_the_selected_particle_clears_the_selection() {
+        let Some(mut app) = headless(&SimulationConfig {
+            particle_count: MAX_PARTICLES,
+            ..SimulationConfig::default()
+        }) else {
+            return;
+        };
+        app.select(0);
+        assert_eq!(app.selected, Some(0));
+        app.apply_input(InputEvent::Spawn([0.0, 0.0]));
+        assert_eq!(app.save_state().particles.len(), MAX_PARTICLES);
+        assert_eq!(app.selected, None);
+
+        app.select(MAX_PARTICLES - 1);
+        app.remove_particles(1);
+        assert_eq!(app.selected, None);
+    }
+
+    #[test]
+    fn zero_sized_applications_render_nothing() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        assert!(app.resize(dpi::PhysicalSize::new(0, 64)).is_err());
+        assert!(!app.is_renderable());
+        app.render()
+            .expect("rendering a zero-sized surface should do nothing");
+        assert_eq!(app.metrics().cpu_render_encode, Duration::ZERO);
+        app.resize(dpi::PhysicalSize::new(64, 64))
+            .expect("resizing to a non-empty size should succeed");
+        assert!(app.is_renderable());
+    }
+
+    #[test]
+    fn occluded_applications_do_not_draw_until_shown() {
+        let Some(mut app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        app.occlusion_changed(true);
+        assert!(!app.is_renderable());
+        app.render()
+            .expect("rendering an occluded window should do nothing");
+        assert_eq!(app.metrics().cpu_render_encode, Duration::ZERO);
+        app.schedule_redraw();
+        assert!(app.idle);
+        app.occlusion_changed(false);
+        assert!(app.is_renderable());
+        assert!(app.wants_redraw(), "a frame should be drawn once shown");
+    }
+
+    #[test]
+    fn errors_describe_what_failed() {
+        for (err, phrase) in [
+            (AppError::Adapter, "no compatible GPU adapter"),
+            (AppError::NoSurfaceFormats, "no texture formats"),
+            (AppError::NoPresentMode, "no present modes"),
+            (AppError::NoAlphaMode, "no alpha modes"),
+            (AppError::Offscreen, "offscreen"),
+        ] {
+            assert!(err.to_string().contains(phrase), "{err} lacks {phrase:?}");
+        }
+        assert!(ResizeError::ZeroDimension.to_string().contains("no area"));
+    }
+
+    #[test]
+    fn enabled_features_are_supported_by_the_adapter() {
+        let Some(adapter) = device::test_adapter() else {
+            return;
+        };
+        let Some(app) = headless(&SimulationConfig::default()) else {
+            return;
+        };
+        assert!(adapter.features().contains(app.enabled_features()));
     }
 }
@@ -0,0 +1,96 @@
+//! An outline of the floor, ceiling, and walls that particles are kept within, for checking that
+//! they line up with the edges of the view
+
+use core::mem;
+
+use wgpu::util::DeviceExt as _;
+
+use super::{app::create_render_pipeline, particles::Environment};
+
+/// The attributes of a corner of the outline, matching the locations in the bounds shader
+const CORNER_ATTRIBUTES: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![0 => Float32x2];
+
+/// The number of vertices in the outline: each corner, with the first repeated to close it
+const VERTEX_COUNT: usize = 5;
+
+/// Returns the corners of the box bounded by the floor, ceiling, and walls of the environment,
+/// counterclockwise from the bottom left, with the first repeated to close the loop
+pub(super) const fn boundary_vertices(environment: &Environment) -> [[f32; 2]; VERTEX_COUNT] {
+    let (left, right) = (environment.left, environment.right);
+    let (floor, ceiling) = (environment.floor, environment.ceiling);
+    [
+        [left, floor],
+        [right, floor],
+        [right, ceiling],
+        [left, ceiling],
+        [left, floor],
+    ]
+}
+
+/// The pipeline and vertices drawing the outline of the environment's boundaries
+pub(super) struct BoundsOverlay {
+    /// Draws the outline as a strip of lines
+    pipeline: wgpu::RenderPipeline,
+    /// The vertices of the outline, from `boundary_vertices`
+    vertex_buffer: wgpu::Buffer,
+}
+
+impl BoundsOverlay {
+    /// Creates an outline of the given environment's boundaries, drawn through the camera with
+    /// the given bind group layout into a pass with the given color format and number of samples
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        camera_layout: &wgpu::BindGroupLayout,
+        sample_count: u32,
+        environment: &Environment,
+    ) -> Self {
+        let pipeline = create_render_pipeline(
+            device,
+            format,
+            &[camera_layout],
+            &device.create_shader_module(wgpu::include_wgsl!("bounds.wgsl")),
+            &[wgpu::VertexBufferLayout {
+                #[expect(
+                    clippy::as_conversions,
+                    reason = "`usize` to `u64` is lossless on all supported platforms"
+                )]
+                array_stride: mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &CORNER_ATTRIBUTES,
+            }],
+            wgpu::PrimitiveTopology::LineStrip,
+            sample_count,
+        );
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Bounds Vertex Buffer"),
+            contents: bytemuck::cast_slice(&boundary_vertices(environment)),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+        Self {
+            pipeline,
+            vertex_buffer,
+        }
+    }
+
+    /// Moves the outline to the current boundaries of the given environment
+    pub fn update(&self, queue: &wgpu::Queue, environment: &Environment) {
+        queue.write_buffer(
+            &self.vertex_buffer,
+            0,
+            bytemuck::cast_slice(&boundary_vertices(environment)),
+        );
+    }
+
+    /// Draws the outline. The camera must already be bound to group 0
+    pub fn draw<'pass>(&'pass self, render_pass: &mut wgpu::RenderPass<'pass>) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        #[expect(
+            clippy::as_conversions,
+            clippy::cast_possible_truncation,
+            reason = "The outline has only a handful of vertices"
+        )]
+        render_pass.draw(0..VERTEX_COUNT as u32, 0..1);
+    }
+}
@@ -0,0 +1,286 @@
+//! Fluent configuration of the simulation, for embedding it in other applications
+
+use core::{error::Error, fmt};
+
+use super::{
+    collider::Collider,
+    config::{RedrawPolicy, SimulationConfig, WindowIcon},
+    emitter::Emitter,
+    particles::RestitutionModel,
+    present::PresentPreference,
+    spawn::SpawnPattern,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Errors that may arise from building a simulation configuration
+#[non_exhaustive]
+pub enum BuilderError {
+    /// The simulation would start without any particles
+    NoParticles,
+    /// The gravity is infinite or NaN
+    NonFiniteGravity(f32),
+}
+
+impl fmt::Display for BuilderError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::NoParticles => f.write_str("the simulation has no particles"),
+            Self::NonFiniteGravity(gravity) => {
+                write!(f, "the gravity {gravity} is not finite")
+            }
+        }
+    }
+}
+
+impl Error for BuilderError {}
+
+#[derive(Clone, Copy, Debug)]
+/// The entry point for configuring a simulation to embed
+#[non_exhaustive]
+pub struct PhysicalSimulation;
+
+impl PhysicalSimulation {
+    /// Returns a builder for a simulation configuration, starting from the defaults
+    #[inline]
+    #[must_use]
+    pub fn builder() -> PhysicalSimulationBuilder {
+        PhysicalSimulationBuilder::default()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+/// Builds a validated [`SimulationConfig`], one setting at a time
+pub struct PhysicalSimulationBuilder {
+    /// The configuration built so far
+    config: SimulationConfig,
+}
+
+impl PhysicalSimulationBuilder {
+    /// Sets the color the surface is cleared to before drawing, in linear space
+    #[inline]
+    #[must_use]
+    pub const fn background(mut self, background: wgpu::Color) -> Self {
+        self.config.background = background;
+        self
+    }
+
+    /// Sets the number of particles spawned when the application starts
+    #[inline]
+    #[must_use]
+    pub const fn particle_count(mut self, particle_count: usize) -> Self {
+        self.config.particle_count = particle_count;
+        self
+    }
+
+    /// Sets the vertical acceleration applied to every particle, in meters per second squared.
+    /// Negative values pull downwards
+    #[inline]
+    #[must_use]
+    pub const fn gravity(mut self, gravity: f32) -> Self {
+        self.config.gravity = gravity;
+        self
+    }
+
+    /// Sets how the fraction of its speed that a particle retains when bouncing depends on how
+    /// fast it hits
+    #[inline]
+    #[must_use]
+    pub const fn restitution(mut self, restitution: RestitutionModel) -> Self {
+        self.config.restitution = restitution;
+        self
+    }
+
+    /// Sets the width and height of the world that particles are kept within, in meters, which
+    /// is fitted into the window whatever its size
+    #[inline]
+    #[must_use]
+    pub const fn world_size(mut self, width: f32, height: f32) -> Self {
+        self.config.world_size = Some([width, height]);
+        self
+    }
+
+    /// Sets the height of the floor that particles bounce off, in meters, independently of the
+    /// size of the world
+    #[inline]
+    #[must_use]
+    pub const fn floor(mut self, floor: f32) -> Self {
+        self.config.floor = Some(floor);
+        self
+    }
+
+    /// Sets the preferred way of presenting rendered frames
+    #[inline]
+    #[must_use]
+    pub const fn present_preference(mut self, present_preference: PresentPreference) -> Self {
+        self.config.present_preference = present_preference;
+        self
+    }
+
+    /// Sets the number of samples per pixel used for anti-aliasing, or the nearest supported count
+    #[inline]
+    #[must_use]
+    pub const fn msaa_samples(mut self, msaa_samples: u32) -> Self {
+        self.config.msaa_samples = msaa_samples;
+        self
+    }
+
+    /// Sets the seed that all randomness in the simulation is drawn from
+    #[inline]
+    #[must_use]
+    pub const fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    /// Sets the preferred number of invocations in each workgroup when integrating particles on
+    /// the GPU
+    #[inline]
+    #[must_use]
+    pub const fn workgroup_size(mut self, workgroup_size: u32) -> Self {
+        self.config.workgroup_size = workgroup_size;
+        self
+    }
+
+    /// Sets whether the simulation pauses while the window is unfocused
+    #[inline]
+    #[must_use]
+    pub const fn pause_on_focus_loss(mut self, pause_on_focus_loss: bool) -> Self {
+        self.config.pause_on_focus_loss = pause_on_focus_loss;
+        self
+    }
+
+    /// Sets the number of frames that may be queued for presentation ahead of the display
+    #[inline]
+    #[must_use]
+    pub const fn frame_latency(mut self, frame_latency: u32) -> Self {
+        self.config.frame_latency = frame_latency;
+        self
+    }
+
+    /// Sets the most physics steps taken to catch up within a single frame at normal speed
+    #[inline]
+    #[must_use]
+    pub const fn max_steps_per_frame(mut self, max_steps_per_frame: u32) -> Self {
+        self.config.max_steps_per_frame = max_steps_per_frame;
+        self
+    }
+
+    /// Sets the largest GPU buffer, in bytes, requested from the device to hold many particles
+    #[inline]
+    #[must_use]
+    pub const fn max_buffer_size(mut self, max_buffer_size: u64) -> Self {
+        self.config.max_buffer_size = max_buffer_size;
+        self
+    }
+
+    /// Sets the title of the window created on desktop platforms
+    #[inline]
+    #[must_use]
+    pub const fn title(mut self, title: &'static str) -> Self {
+        self.config.title = title;
+        self
+    }
+
+    /// Sets the icon of the window created on desktop platforms
+    #[inline]
+    #[must_use]
+    pub const fn icon(mut self, icon: WindowIcon) -> Self {
+        self.config.icon = Some(icon);
+        self
+    }
+
+    /// Sets the formats to configure the surface with, in order of preference
+    #[inline]
+    #[must_use]
+    pub const fn surface_formats(
+        mut self,
+        surface_formats: &'static [wgpu::TextureFormat],
+    ) -> Self {
+        self.config.surface_formats = surface_formats;
+        self
+    }
+
+    /// Sets when the window is redrawn
+    #[inline]
+    #[must_use]
+    pub const fn redraw_policy(mut self, redraw_policy: RedrawPolicy) -> Self {
+        self.config.redraw_policy = redraw_policy;
+        self
+    }
+
+    /// Sets how the particles are arranged when the particle simulation starts
+    #[inline]
+    #[must_use]
+    pub const fn spawn_pattern(mut self, spawn_pattern: SpawnPattern) -> Self {
+        self.config.spawn_pattern = spawn_pattern;
+        self
+    }
+
+    /// Sets the sources continuously spawning particles when the application starts
+    #[inline]
+    #[must_use]
+    pub const fn emitters(mut self, emitters: &'static [Emitter]) -> Self {
+        self.config.emitters = emitters;
+        self
+    }
+
+    /// Sets the static obstacles placed in the world, which particles bounce off
+    #[inline]
+    #[must_use]
+    pub const fn colliders(mut self, colliders: &'static [Collider]) -> Self {
+        self.config.colliders = colliders;
+        self
+    }
+
+    /// Validates the settings and returns the configuration, for use with
+    /// [`AppWrapper::with_config`](super::AppWrapper::with_config)
+    ///
+    /// # Errors
+    /// Returns an error if there are no particles, or if the gravity is not finite
+    #[inline]
+    pub fn build(self) -> Result<SimulationConfig, BuilderError> {
+        validate(&self.config)?;
+        Ok(self.config)
+    }
+}
+
+/// Checks that the simulation can start with the given configuration
+pub(super) fn validate(config: &SimulationConfig) -> Result<(), BuilderError> {
+    if config.particle_count == 0 {
+        return Err(BuilderError::NoParticles);
+    }
+    if !config.gravity.is_finite() {
+        return Err(BuilderError::NonFiniteGravity(config.gravity));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BuilderError, PhysicalSimulation, SimulationConfig};
+
+    #[test]
+    fn defaults_are_the_default_configuration() {
+        assert_eq!(
+            PhysicalSimulation::builder().build(),
+            Ok(SimulationConfig::default())
+        );
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        assert_eq!(
+            PhysicalSimulation::builder().particle_count(0).build(),
+            Err(BuilderError::NoParticles)
+        );
+        assert!(matches!(
+            PhysicalSimulation::builder().gravity(f32::NAN).build(),
+            Err(BuilderError::NonFiniteGravity(gravity)) if gravity.is_nan()
+        ));
+        assert_eq!(
+            PhysicalSimulation::builder().gravity(f32::INFINITY).build(),
+            Err(BuilderError::NonFiniteGravity(f32::INFINITY))
+        );
+    }
+}
@@ -0,0 +1,415 @@
+//! The camera through which the simulation is viewed, and its GPU-side uniform
+
+#![expect(
+    clippy::float_arithmetic,
+    reason = "Camera movement and projection are floating-point linear algebra"
+)]
+
+use core::{f32::consts::FRAC_PI_2, mem};
+
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Quat, Vec2, Vec3};
+use wgpu::util::DeviceExt as _;
+use winit::{event::ElementState, keyboard::KeyCode};
+
+/// A perspective camera looking from a point towards a target
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct Camera {
+    /// The position of the camera
+    pub eye: Vec3,
+    /// The point the camera looks at
+    pub target: Vec3,
+    /// The upwards direction of the camera
+    pub up: Vec3,
+    /// The ratio of the width of the view to its height
+    pub aspect: f32,
+    /// The vertical field of view, in radians
+    pub fovy: f32,
+    /// The distance to the near clipping plane
+    pub znear: f32,
+    /// The distance to the far clipping plane
+    pub zfar: f32,
+}
+
+impl Camera {
+    /// Creates a camera looking at the origin down the negative z-axis, positioned so that the
+    /// square `[-1, 1]` on the `z = 0` plane exactly fills the view vertically
+    pub const fn new(aspect: f32) -> Self {
+        Self {
+            eye: Vec3::new(0.0, 0.0, 1.0),
+            target: Vec3::ZERO,
+            up: Vec3::Y,
+            aspect,
+            fovy: FRAC_PI_2,
+            znear: 0.1,
+            zfar: 100.0,
+        }
+    }
+
+    /// Returns the matrix transforming world coordinates into clip coordinates
+    pub fn matrix(&self) -> Mat4 {
+        let view = Mat4::look_at_rh(self.eye, self.target, self.up);
+        let projection = Mat4::perspective_rh(self.fovy, self.aspect, self.znear, self.zfar);
+        projection * view
+    }
+
+    /// Returns the combined view and projection matrix, in column-major order
+    pub fn view_proj(&self) -> [[f32; 4]; 4] {
+        self.matrix().to_cols_array_2d()
+    }
+
+    /// Returns the point on the `z = 0` plane that appears at the given normalized device
+    /// coordinates, or `None` if the plane is not visible there
+    pub fn ndc_to_plane(&self, ndc: [f32; 2]) -> Option<[f32; 2]> {
+        let inverse = self.matrix().inverse();
+        let near = inverse.project_point3(Vec3::new(ndc[0], ndc[1], 0.0));
+        let far = inverse.project_point3(Vec3::new(ndc[0], ndc[1], 1.0));
+        let direction = far - near;
+        if direction.z == 0.0 {
+            return None;
+        }
+        let distance = -near.z / direction.z;
+        (0.0..=1.0).contains(&distance).then(|| {
+            let point = near + direction * distance;
+            [point.x, point.y]
+        })
+    }
+}
+
+/// A camera looking straight down onto the `z = 0` plane without perspective, so that the
+/// simulation appears flat and squares stay square regardless of the aspect ratio
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct OrthographicCamera {
+    /// The point on the plane at the center of the view
+    pub center: Vec2,
+    /// How magnified the view is. At a zoom of `1`, the view spans `[-extent, extent]` vertically
+    pub zoom: f32,
+    /// The ratio of the width of the view to its height
+    pub aspect: f32,
+    /// Half the height of the plane visible at a zoom of `1`, in meters
+    pub extent: f32,
+}
+
+impl OrthographicCamera {
+    /// Creates a camera centered on the origin, spanning `[-1, 1]` vertically
+    pub const fn new(aspect: f32) -> Self {
+        Self {
+            center: Vec2::ZERO,
+            zoom: 1.0,
+            aspect,
+            extent: 1.0,
+        }
+    }
+
+    /// Sets the extent so that, at a zoom of `1`, the view just contains a world centered on the
+    /// origin with the given half width and height, whatever the aspect ratio
+    pub fn fit(&mut self, half_extents: [f32; 2]) {
+        self.extent = half_extents[1].max(half_extents[0] / self.aspect);
+    }
+
+    /// Returns half of the width and height of the plane visible through the camera
+    fn half_extent(&self) -> Vec2 {
+        let half_height = self.extent / self.zoom;
+        Vec2::new(half_height * self.aspect, half_height)
+    }
+
+    /// Returns the lower left and upper right corners of the plane visible through the camera
+    pub fn bounds(&self) -> ([f32; 2], [f32; 2]) {
+        (
+            (self.center - self.half_extent()).into(),
+            (self.center + self.half_extent()).into(),
+        )
+    }
+
+    /// Returns the matrix transforming world coordinates into clip coordinates
+    pub fn matrix(&self) -> Mat4 {
+        let min = self.center - self.half_extent();
+        let max = self.center + self.half_extent();
+        Mat4::orthographic_rh(min.x, max.x, min.y, max.y, -1.0, 1.0)
+    }
+
+    /// Returns the combined view and projection matrix, in column-major order
+    pub fn view_proj(&self) -> [[f32; 4]; 4] {
+        self.matrix().to_cols_array_2d()
+    }
+
+    /// Returns the point on the `z = 0` plane that appears at the given normalized device
+    /// coordinates
+    pub fn ndc_to_plane(&self, ndc: [f32; 2]) -> [f32; 2] {
+        (self.center + Vec2::from(ndc) * self.half_extent()).to_array()
+    }
+
+    /// Changes the zoom while keeping the point at the given normalized device coordinates fixed
+    /// in place
+    pub fn zoom_at(&mut self, ndc: [f32; 2], zoom: f32) {
+        let before = Vec2::from(self.ndc_to_plane(ndc));
+        self.zoom = zoom;
+        let after = Vec2::from(self.ndc_to_plane(ndc));
+        self.center += before - after;
+    }
+
+    /// Moves the view so that the given point on the plane appears at the given normalized device
+    /// coordinates
+    pub fn pan_to(&mut self, point: [f32; 2], ndc: [f32; 2]) {
+        self.center += Vec2::from(point) - Vec2::from(self.ndc_to_plane(ndc));
+    }
+}
+
+/// Flies a camera around in response to keyboard and mouse input.
+/// W/A/S/D move within the view, E and Shift move up and down, and mouse motion turns the camera
+/// while looking is enabled. Space is left unbound, as it pauses the simulation
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "Each key is held independently of the others"
+)]
+pub(super) struct CameraController {
+    /// How far the camera moves per second
+    speed: f32,
+    /// How far the camera turns per unit of mouse motion, in radians
+    sensitivity: f32,
+    /// Whether the key moving forwards is held
+    forward: bool,
+    /// Whether the key moving backwards is held
+    backward: bool,
+    /// Whether the key moving left is held
+    left: bool,
+    /// Whether the key moving right is held
+    right: bool,
+    /// Whether the key moving up is held
+    up: bool,
+    /// Whether the key moving down is held
+    down: bool,
+    /// Whether mouse motion should turn the camera
+    looking: bool,
+    /// The mouse motion accumulated since the camera was last updated
+    rotation: [f32; 2],
+}
+
+impl CameraController {
+    /// The smallest angle, in radians, allowed between the view direction and the up direction,
+    /// to stop the camera from flipping over when looking straight up or down
+    const MIN_PITCH_ANGLE: f32 = 0.01;
+
+    /// Creates a controller moving at `speed` units per second and turning `sensitivity`
+    /// radians per unit of mouse motion
+    pub const fn new(speed: f32, sensitivity: f32) -> Self {
+        Self {
+            speed,
+            sensitivity,
+            forward: false,
+            backward: false,
+            left: false,
+            right: false,
+            up: false,
+            down: false,
+            looking: false,
+            rotation: [0.0; 2],
+        }
+    }
+
+    /// Records a key being pressed or released.
+    /// Returns whether the key controls the camera
+    #[expect(
+        clippy::wildcard_enum_match_arm,
+        reason = "Every other key, including any added later, does not move the camera"
+    )]
+    pub fn process_key(&mut self, key: KeyCode, state: ElementState) -> bool {
+        let held = state == ElementState::Pressed;
+        let flag = match key {
+            KeyCode::KeyW => &mut self.forward,
+            KeyCode::KeyS => &mut self.backward,
+            KeyCode::KeyA => &mut self.left,
+            KeyCode::KeyD => &mut self.right,
+            KeyCode::KeyE => &mut self.up,
+            KeyCode::ShiftLeft | KeyCode::ShiftRight => &mut self.down,
+            _ => return false,
+        };
+        *flag = held;
+        true
+    }
+
+    /// Returns whether the camera may move on the next update, as a key moving it is held or the
+    /// mouse is turning it
+    pub const fn is_moving(&self) -> bool {
+        self.forward
+            || self.backward
+            || self.left
+            || self.right
+            || self.up
+            || self.down
+            || self.looking
+    }
+
+    /// Enables or disables turning the camera with the mouse
+    pub fn set_looking(&mut self, looking: bool) {
+        self.looking = looking;
+        if !looking {
+            self.rotation = [0.0; 2];
+        }
+    }
+
+    /// Records relative mouse motion, which turns the camera on the next update if looking is
+    /// enabled
+    #[expect(
+        clippy::as_conversions,
+        clippy::cast_possible_truncation,
+        reason = "Mouse motion is far smaller than the range of an `f32`"
+    )]
+    pub fn process_mouse_motion(&mut self, (dx, dy): (f64, f64)) {
+        if self.looking {
+            self.rotation[0] += dx as f32;
+            self.rotation[1] += dy as f32;
+        }
+    }
+
+    /// Turns the camera by the mouse motion seen since the last update, then moves it as if the
+    /// currently held movement keys have been held for `dt` seconds
+    pub fn update_camera(&mut self, camera: &mut Camera, dt: f32) {
+        self.rotate(camera);
+        let Some(forward) = (camera.target - camera.eye).try_normalize() else {
+            return;
+        };
+        let Some(right) = forward.cross(camera.up).try_normalize() else {
+            return;
+        };
+        let axis = |positive: bool, negative: bool| {
+            f32::from(u8::from(positive)) - f32::from(u8::from(negative))
+        };
+        let movement = forward * axis(self.forward, self.backward)
+            + right * axis(self.right, self.left)
+            + camera.up * axis(self.up, self.down);
+        let offset = movement * self.speed * dt;
+        camera.eye += offset;
+        camera.target += offset;
+    }
+
+    /// Turns the camera by the mouse motion accumulated since the last update
+    fn rotate(&mut self, camera: &mut Camera) {
+        let [dx, dy] = mem::take(&mut self.rotation);
+        let offset = camera.target - camera.eye;
+        let Some(right) = offset.cross(camera.up).try_normalize() else {
+            return;
+        };
+        let yaw = Quat::from_axis_angle(camera.up.normalize(), -dx * self.sensitivity);
+        let pitch = Quat::from_axis_angle(right, -dy * self.sensitivity);
+        let pitched = pitch * offset;
+        let turned = if pitched.angle_between(camera.up) < Self::MIN_PITCH_ANGLE
+            || pitched.angle_between(-camera.up) < Self::MIN_PITCH_ANGLE
+        {
+            offset
+        } else {
+            pitched
+        };
+        camera.target = camera.eye + yaw * turned;
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+/// The camera's data as laid out in the shaders' uniform buffer
+struct CameraUniform {
+    /// The combined view and projection matrix
+    view_proj: [[f32; 4]; 4],
+}
+
+/// The GPU-side resources exposing the camera to shaders, at group 0, binding 0
+pub(super) struct CameraBinding {
+    /// Uniform buffer holding the `CameraUniform`
+    buffer: wgpu::Buffer,
+    /// The layout of `bind_group`, for use in pipeline layouts
+    layout: wgpu::BindGroupLayout,
+    /// Binds `buffer` to the vertex shader
+    bind_group: wgpu::BindGroup,
+}
+
+impl CameraBinding {
+    /// Creates the resources exposing the given view and projection matrix
+    pub fn new(device: &wgpu::Device, view_proj: [[f32; 4]; 4]) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Camera Buffer"),
+            contents: bytemuck::bytes_of(&CameraUniform { view_proj }),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Camera Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Camera Bind Group"),
+            layout: &layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        Self {
+            buffer,
+            layout,
+            bind_group,
+        }
+    }
+
+    /// Returns the layout of the camera's bind group
+    pub const fn layout(&self) -> &wgpu::BindGroupLayout {
+        &self.layout
+    }
+
+    /// Returns the bind group exposing the camera
+    pub const fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    /// Replaces the view and projection matrix seen by the shaders
+    pub fn update(&self, queue: &wgpu::Queue, view_proj: [[f32; 4]; 4]) {
+        queue.write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::bytes_of(&CameraUniform { view_proj }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec2, Vec3};
+    use winit::{event::ElementState, keyboard::KeyCode};
+
+    use super::{Camera, CameraController, OrthographicCamera};
+
+    #[test]
+    fn unit_aspect_projection_is_finite() {
+        let view_proj = Camera::new(1.0).view_proj();
+        assert!(view_proj.iter().flatten().all(|entry| entry.is_finite()));
+    }
+
+    #[test]
+    fn holding_w_moves_forward_by_speed_times_dt() {
+        let (speed, dt) = (2.0, 0.5);
+        let mut camera = Camera::new(1.0);
+        let mut controller = CameraController::new(speed, 0.0);
+        assert!(controller.process_key(KeyCode::KeyW, ElementState::Pressed));
+        let forward = (camera.target - camera.eye).normalize();
+        let start = camera.eye;
+        controller.update_camera(&mut camera, dt);
+        assert!(camera.eye.abs_diff_eq(start + forward * speed * dt, 1e-6));
+    }
+
+    #[test]
+    fn center_of_the_flat_view_maps_to_the_origin() {
+        let mut camera = OrthographicCamera::new(1.5);
+        camera.center = Vec2::new(3.0, -2.0);
+        camera.zoom = 4.0;
+        let ndc = camera.matrix().project_point3(Vec3::new(3.0, -2.0, 0.0));
+        assert!(ndc.truncate().abs_diff_eq(Vec2::ZERO, 1e-6));
+    }
+}
@@ -0,0 +1,75 @@
+//! Encoding captured frames into PNG images
+
+use core::{error::Error, fmt};
+
+use super::readback::ReadbackError;
+
+#[derive(Debug)]
+/// Errors that may arise from capturing a frame
+pub(super) enum CaptureError {
+    /// The frame is rendered in a format that cannot be converted into RGBA
+    UnsupportedFormat(wgpu::TextureFormat),
+    /// The rendered frame could not be read back
+    Readback(ReadbackError),
+    /// The frame could not be encoded into a PNG image
+    Encode(png::EncodingError),
+}
+
+impl fmt::Display for CaptureError {
+    #[expect(
+        clippy::use_debug,
+        reason = "Texture formats are only named by their `Debug` implementation"
+    )]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::UnsupportedFormat(format) => {
+                write!(f, "frames in {format:?} cannot be converted into RGBA")
+            }
+            Self::Readback(ref err) => write!(f, "failed to read back the frame: {err}"),
+            Self::Encode(ref err) => write!(f, "failed to encode the frame: {err}"),
+        }
+    }
+}
+
+impl Error for CaptureError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            Self::UnsupportedFormat(_) => None,
+            Self::Readback(ref err) => Some(err),
+            Self::Encode(ref err) => Some(err),
+        }
+    }
+}
+
+/// Converts pixels read back from a texture of the given format into 8-bit RGBA, in place.
+/// Returns `false` if the format cannot be converted
+#[expect(
+    clippy::wildcard_enum_match_arm,
+    reason = "Only 8-bit RGBA and BGRA formats can be converted"
+)]
+pub(super) fn to_rgba8(format: wgpu::TextureFormat, pixels: &mut [u8]) -> bool {
+    match format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => true,
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Encodes tightly packed 8-bit sRGB RGBA pixels of an image of the given size into a PNG file
+pub(super) fn encode_png(width: u32, height: u32, pixels: &[u8]) -> Result<Vec<u8>, CaptureError> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(pixels))
+        .map_err(CaptureError::Encode)?;
+    Ok(bytes)
+}
@@ -0,0 +1,344 @@
+//! Cloth modelled as a grid of point masses connected by springs
+
+#![expect(
+    clippy::float_arithmetic,
+    reason = "Spring forces and the positions of point masses are floating-point quantities"
+)]
+
+use core::mem;
+
+use wgpu::util::DeviceExt as _;
+
+use super::{
+    app::create_render_pipeline,
+    grid,
+    particles::{Environment, Particle, RADIUS},
+    simulation::{Integrator, Simulation, SimulationContext},
+};
+
+/// The width of the cloth when it is first hung, in meters
+const CLOTH_WIDTH: f32 = 1.0;
+
+/// The height at which the top row of the cloth is pinned, in meters
+const PIN_HEIGHT: f32 = 0.9;
+
+/// The fraction of their velocity that masses lose per second, so that the cloth settles
+const DAMPING: f32 = 0.5;
+
+/// The attributes of a mass used for rendering, matching the locations in the cloth shader
+const MASS_ATTRIBUTES: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![0 => Float32x2];
+
+/// Returns the layout of a buffer of masses, when used as the vertices of line segments
+const fn mass_desc() -> wgpu::VertexBufferLayout<'static> {
+    wgpu::VertexBufferLayout {
+        #[expect(
+            clippy::as_conversions,
+            reason = "`usize` to `u64` is lossless on all supported platforms"
+        )]
+        array_stride: mem::size_of::<Particle>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &MASS_ATTRIBUTES,
+    }
+}
+
+/// Creates the pipeline drawing the structural springs of a cloth as lines between its masses
+fn create_cloth_pipeline(context: &SimulationContext<'_>) -> wgpu::RenderPipeline {
+    create_render_pipeline(
+        context.device,
+        context.format,
+        &[context.camera_layout],
+        &context
+            .device
+            .create_shader_module(wgpu::include_wgsl!("cloth.wgsl")),
+        &[mass_desc()],
+        wgpu::PrimitiveTopology::LineList,
+        context.sample_count,
+    )
+}
+
+/// A spring connecting two masses, pulling them towards its rest length
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Spring {
+    /// The index of one of the connected masses
+    first: usize,
+    /// The index of the other connected mass
+    second: usize,
+    /// The distance between the masses at which the spring exerts no force
+    rest_length: f32,
+}
+
+/// Returns the force exerted on a mass at `first` by a spring connecting it to a mass at `second`,
+/// following Hooke's law. The force on the other mass is equal and opposite
+fn spring_force(first: [f32; 2], second: [f32; 2], rest_length: f32, stiffness: f32) -> [f32; 2] {
+    let distance = grid::distance_squared(first, second).sqrt();
+    if distance == 0.0 {
+        return [0.0, 0.0];
+    }
+    let magnitude = stiffness * (distance - rest_length) / distance;
+    [
+        (second[0] - first[0]) * magnitude,
+        (second[1] - first[1]) * magnitude,
+    ]
+}
+
+/// Returns `position` moved along `direction` scaled by `scale`
+fn displaced(position: [f32; 2], direction: [f32; 2], scale: f32) -> [f32; 2] {
+    [
+        position[0] + direction[0] * scale,
+        position[1] + direction[1] * scale,
+    ]
+}
+
+/// A rectangular grid of unit point masses hanging from its pinned top row, connected by springs.
+/// Structural springs join neighboring masses, shear springs join diagonal neighbors, and bend
+/// springs join masses two apart
+pub(super) struct ClothSim {
+    /// The masses, row by row from the top
+    masses: Vec<Particle>,
+    /// The number of masses in each row, which are pinned in place in the top row
+    columns: usize,
+    /// The springs connecting the masses
+    springs: Vec<Spring>,
+    /// The stiffness shared by every spring
+    stiffness: f32,
+    /// The scheme advancing the masses through time
+    integrator: Integrator,
+    /// The position of every mass as of the previous step, used by Verlet integration.
+    /// Empty until the first Verlet step, which derives them from the masses' velocities
+    previous: Vec<[f32; 2]>,
+    /// The GPU-side copy of every mass, drawn as vertices
+    vertex_buffer: wgpu::Buffer,
+    /// Pairs of indices into `vertex_buffer` describing the structural springs to draw
+    index_buffer: wgpu::Buffer,
+    /// The number of indices in `index_buffer`
+    num_indices: u32,
+    /// The pipeline drawing the springs as line segments
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl ClothSim {
+    /// Creates a cloth of `columns` by `rows` masses, connected by springs of the given stiffness,
+    /// hanging from its top row
+    #[expect(
+        clippy::expect_used,
+        reason = "Cloth dimensions are small enough for every index to fit in a `u32`"
+    )]
+    pub fn new(
+        context: &SimulationContext<'_>,
+        columns: usize,
+        rows: usize,
+        stiffness: f32,
+    ) -> Self {
+        assert!(
+            columns > 0 && rows > 0,
+            "Cloth should have at least one mass"
+        );
+        #[expect(
+            clippy::as_conversions,
+            clippy::cast_precision_loss,
+            reason = "Cloth dimensions are small enough to be represented exactly"
+        )]
+        let spacing = CLOTH_WIDTH / columns.saturating_sub(1).max(1) as f32;
+        #[expect(
+            clippy::as_conversions,
+            clippy::cast_precision_loss,
+            reason = "Cloth dimensions are small enough to be represented exactly"
+        )]
+        let masses = (0..rows)
+            .flat_map(|row| {
+                (0..columns).map(move |column| {
+                    Particle::new(
+                        [
+                            -CLOTH_WIDTH / 2.0 + column as f32 * spacing,
+                            PIN_HEIGHT - row as f32 * spacing,
+                        ],
+                        [0.0, 0.0],
+                        RADIUS,
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let index = |row: usize, column: usize| row.saturating_mul(columns).saturating_add(column);
+        let mut structural = Vec::new();
+        let mut springs = Vec::new();
+        for row in 0..rows {
+            for column in 0..columns {
+                let neighbors = [
+                    // Structural
+                    (row, column.saturating_add(1), true),
+                    (row.saturating_add(1), column, true),
+                    // Shear
+                    (row.saturating_add(1), column.saturating_add(1), false),
+                    (row.saturating_add(1), column.wrapping_sub(1), false),
+                    // Bend
+                    (row, column.saturating_add(2), false),
+                    (row.saturating_add(2), column, false),
+                ];
+                for (other_row, other_column, is_structural) in neighbors {
+                    if other_row >= rows || other_column >= columns {
+                        continue;
+                    }
+                    let (first, second) = (index(row, column), index(other_row, other_column));
+                    let rest_length = masses.get(first).zip(masses.get(second)).map_or(
+                        0.0,
+                        |(first_mass, second_mass)| {
+                            grid::distance_squared(first_mass.position, second_mass.position).sqrt()
+                        },
+                    );
+                    springs.push(Spring {
+                        first,
+                        second,
+                        rest_length,
+                    });
+                    if is_structural {
+                        structural.extend([first, second].map(|mass| {
+                            u32::try_from(mass).expect("Mass index should fit in a `u32`")
+                        }));
+                    }
+                }
+            }
+        }
+
+        let device = context.device;
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Cloth Vertex Buffer"),
+            contents: bytemuck::cast_slice(&masses),
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Cloth Index Buffer"),
+            contents: bytemuck::cast_slice(&structural),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        let pipeline = create_cloth_pipeline(context);
+        Self {
+            masses,
+            columns,
+            springs,
+            stiffness,
+            integrator: Integrator::default(),
+            previous: Vec::new(),
+            vertex_buffer,
+            index_buffer,
+            num_indices: u32::try_from(structural.len())
+                .expect("Index count should fit in a `u32`"),
+            pipeline,
+        }
+    }
+}
+
+impl Simulation for ClothSim {
+    fn step(&mut self, dt: f32, environment: &Environment) {
+        let mut forces = self
+            .masses
+            .iter()
+            .map(|mass| environment.acceleration(mass))
+            .collect::<Vec<_>>();
+        for spring in &self.springs {
+            let (Some(first), Some(second)) = (
+                self.masses.get(spring.first),
+                self.masses.get(spring.second),
+            ) else {
+                continue;
+            };
+            let force = spring_force(
+                first.position,
+                second.position,
+                spring.rest_length,
+                self.stiffness,
+            );
+            for (index, sign) in [(spring.first, 1.0), (spring.second, -1.0)] {
+                if let Some(total) = forces.get_mut(index) {
+                    total[0] += sign * force[0];
+                    total[1] += sign * force[1];
+                }
+            }
+        }
+        // Every mass is a unit mass, so the forces are also the accelerations.
+        // Air resistance slows the masses on top of the damping of the springs
+        let damping = (1.0 - DAMPING * dt) * environment.damping_factor(dt);
+        match self.integrator {
+            Integrator::Euler => {
+                for (mass, acceleration) in self.masses.iter_mut().zip(forces).skip(self.columns) {
+                    mass.velocity = mass.velocity.map(|component| component * damping);
+                    mass.integrate(acceleration, dt);
+                    mass.apply_bounds(environment);
+                }
+            }
+            Integrator::Verlet => {
+                if self.previous.len() != self.masses.len() {
+                    self.previous = self
+                        .masses
+                        .iter()
+                        .map(|mass| displaced(mass.position, mass.velocity, -dt))
+                        .collect();
+                }
+                for ((mass, previous), acceleration) in self
+                    .masses
+                    .iter_mut()
+                    .zip(&mut self.previous)
+                    .zip(forces)
+                    .skip(self.columns)
+                {
+                    // Damping depends on velocity, which Verlet only has implicitly as the
+                    // distance moved since the previous step, so that distance is damped instead
+                    let moved = [
+                        mass.position[0] - previous[0],
+                        mass.position[1] - previous[1],
+                    ];
+                    *previous = displaced(mass.position, moved, -damping);
+                    mass.integrate_verlet(previous, acceleration, dt);
+                    mass.apply_bounds(environment);
+                    // Bouncing changes the velocity, so the previous position is moved to match
+                    *previous = displaced(mass.position, mass.velocity, -dt);
+                }
+            }
+        }
+    }
+
+    fn upload(&mut self, queue: &wgpu::Queue) {
+        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&self.masses));
+    }
+
+    fn draw<'pass>(&'pass self, render_pass: &mut wgpu::RenderPass<'pass>) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
+    }
+
+    fn rebuild_pipelines(&mut self, context: &SimulationContext<'_>) {
+        self.pipeline = create_cloth_pipeline(context);
+    }
+
+    fn set_integrator(&mut self, integrator: Integrator) -> bool {
+        self.integrator = integrator;
+        self.previous.clear();
+        true
+    }
+
+    fn particles(&self) -> &[Particle] {
+        &self.masses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::spring_force;
+
+    #[test]
+    #[expect(clippy::float_cmp, reason = "Forces at whole lengths are exact")]
+    fn springs_follow_hookes_law() {
+        let stiffness = 10.0;
+        assert_eq!(
+            spring_force([0.0, 0.0], [1.0, 0.0], 1.0, stiffness),
+            [0.0, 0.0]
+        );
+        // Stretched by one rest length, the spring pulls the mass towards the other
+        assert_eq!(
+            spring_force([0.0, 0.0], [2.0, 0.0], 1.0, stiffness),
+            [stiffness, 0.0]
+        );
+    }
+}
@@ -0,0 +1,300 @@
+//! Static obstacles placed in the world, which particles bounce off, and the outlines they and the
+//! selected particle are drawn with
+
+#![expect(
+    clippy::float_arithmetic,
+    reason = "Reflecting particles off obstacles and tessellating their outlines is floating-point geometry"
+)]
+
+use core::{f32::consts::TAU, mem};
+
+use wgpu::util::DeviceExt as _;
+
+use super::{
+    app::{create_blended_render_pipeline, Blending},
+    particles::{Particle, RestitutionModel},
+};
+
+/// The attributes of a vertex of an outline, matching the locations in the collider shader
+const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![0 => Float32x2];
+
+/// The number of straight lines that the outline of a circle is approximated with
+const CIRCLE_SEGMENTS: usize = 48;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// A static obstacle that particles collide with but that never moves
+#[non_exhaustive]
+pub enum Collider {
+    /// A solid disc
+    Circle {
+        /// The center of the disc, in meters
+        center: [f32; 2],
+        /// The radius of the disc, in meters
+        radius: f32,
+    },
+    /// An infinitely thin wall between two points
+    Segment {
+        /// One end of the wall, in meters
+        start: [f32; 2],
+        /// The other end of the wall, in meters
+        end: [f32; 2],
+    },
+}
+
+/// Returns the dot product of two vectors
+fn dot(lhs: [f32; 2], rhs: [f32; 2]) -> f32 {
+    lhs[0] * rhs[0] + lhs[1] * rhs[1]
+}
+
+impl Collider {
+    /// Returns the point on the obstacle's center or spine closest to the given position, and how
+    /// far the obstacle's surface extends beyond that point
+    fn closest_point(&self, position: [f32; 2]) -> ([f32; 2], f32) {
+        match *self {
+            Self::Circle { center, radius } => (center, radius),
+            Self::Segment { start, end } => {
+                let along = [end[0] - start[0], end[1] - start[1]];
+                let length_squared = dot(along, along);
+                let fraction = if length_squared > 0.0 {
+                    (dot([position[0] - start[0], position[1] - start[1]], along) / length_squared)
+                        .clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                (
+                    [
+                        start[0] + fraction * along[0],
+                        start[1] + fraction * along[1],
+                    ],
+                    0.0,
+                )
+            }
+        }
+    }
+
+    /// Resolves a collision between the obstacle and a particle, if they overlap.
+    /// The particle is pushed out along the obstacle's surface normal so that they just touch,
+    /// and if it is heading into the obstacle, the component of its velocity along the normal is
+    /// reflected, scaled by the restitution at the speed it hits
+    pub(super) fn collide(&self, particle: &mut Particle, restitution: RestitutionModel) {
+        let (closest, thickness) = self.closest_point(particle.position);
+        let offset = [
+            particle.position[0] - closest[0],
+            particle.position[1] - closest[1],
+        ];
+        let distance = dot(offset, offset).sqrt();
+        let overlap = thickness + particle.radius - distance;
+        if overlap <= 0.0 || distance == 0.0 {
+            return;
+        }
+        let normal = offset.map(|component| component / distance);
+        for (position, component) in particle.position.iter_mut().zip(normal) {
+            *position += component * overlap;
+        }
+        let normal_speed = dot(particle.velocity, normal);
+        if normal_speed < 0.0 {
+            for (velocity, component) in particle.velocity.iter_mut().zip(normal) {
+                *velocity -= (1.0 + restitution.at(normal_speed)) * normal_speed * component;
+            }
+        }
+    }
+
+    /// Returns the outline of the obstacle, as pairs of vertices for a list of lines
+    #[expect(
+        clippy::as_conversions,
+        clippy::cast_precision_loss,
+        reason = "The number of segments is small enough to be represented exactly"
+    )]
+    fn outline(&self) -> Vec<[f32; 2]> {
+        match *self {
+            Self::Circle { center, radius } => {
+                let point = |index: usize| {
+                    let angle = TAU * index as f32 / CIRCLE_SEGMENTS as f32;
+                    [
+                        center[0] + radius * angle.cos(),
+                        center[1] + radius * angle.sin(),
+                    ]
+                };
+                (0..CIRCLE_SEGMENTS)
+                    .flat_map(|index| [point(index), point(index.saturating_add(1))])
+                    .collect()
+            }
+            Self::Segment { start, end } => vec![start, end],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// What a set of outlines marks, which decides their color
+pub(super) enum OutlineStyle {
+    /// Obstacles, in light gray
+    Obstacle,
+    /// The selected particle, in yellow
+    Selection,
+}
+
+impl OutlineStyle {
+    /// Returns the entry point of the collider shader coloring outlines in this style
+    const fn fragment_entry(self) -> &'static str {
+        match self {
+            Self::Obstacle => "fs_main",
+            Self::Selection => "fs_selection",
+        }
+    }
+}
+
+/// The pipeline and vertices drawing the outlines of a set of obstacles
+pub(super) struct ColliderOverlay {
+    /// Draws the outlines as a list of lines
+    pipeline: wgpu::RenderPipeline,
+    /// The vertices of every outline, or `None` if there are no obstacles
+    vertex_buffer: Option<wgpu::Buffer>,
+    /// The number of vertices in `vertex_buffer`
+    vertex_count: u32,
+}
+
+impl ColliderOverlay {
+    /// Creates outlines of the given obstacles in the given style, drawn through the camera with
+    /// the given bind group layout into a pass with the given color format and number of samples
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        camera_layout: &wgpu::BindGroupLayout,
+        sample_count: u32,
+        style: OutlineStyle,
+        colliders: &[Collider],
+    ) -> Self {
+        let pipeline = create_blended_render_pipeline(
+            device,
+            format,
+            &[camera_layout],
+            &device.create_shader_module(wgpu::include_wgsl!("collider.wgsl")),
+            &[wgpu::VertexBufferLayout {
+                #[expect(
+                    clippy::as_conversions,
+                    reason = "`usize` to `u64` is lossless on all supported platforms"
+                )]
+                array_stride: mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                step_mode: wgpu::VertexStepMode::Vertex,
+                attributes: &VERTEX_ATTRIBUTES,
+            }],
+            wgpu::PrimitiveTopology::LineList,
+            sample_count,
+            (style.fragment_entry(), Blending::Opaque),
+        );
+        let mut overlay = Self {
+            pipeline,
+            vertex_buffer: None,
+            vertex_count: 0,
+        };
+        overlay.replace(device, colliders);
+        overlay
+    }
+
+    /// Replaces the outlines with those of the given obstacles, in a new buffer
+    pub fn replace(&mut self, device: &wgpu::Device, colliders: &[Collider]) {
+        let vertices: Vec<_> = colliders.iter().flat_map(Collider::outline).collect();
+        self.vertex_count = u32::try_from(vertices.len()).unwrap_or(u32::MAX);
+        self.vertex_buffer = (!vertices.is_empty()).then(|| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Collider Vertex Buffer"),
+                contents: bytemuck::cast_slice(&vertices),
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            })
+        });
+    }
+
+    /// Moves the outlines to those of the given obstacles, writing into the existing buffer if
+    /// their outlines have as many vertices as before, as when following a moving obstacle
+    pub fn update(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, colliders: &[Collider]) {
+        let vertices: Vec<_> = colliders.iter().flat_map(Collider::outline).collect();
+        match self.vertex_buffer {
+            Some(ref vertex_buffer)
+                if u32::try_from(vertices.len()).is_ok_and(|count| count == self.vertex_count) =>
+            {
+                queue.write_buffer(vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+            }
+            _ => self.replace(device, colliders),
+        }
+    }
+
+    /// Draws the outlines, if there are any obstacles. The camera must already be bound to group 0
+    pub fn draw<'pass>(&'pass self, render_pass: &mut wgpu::RenderPass<'pass>) {
+        if let Some(ref vertex_buffer) = self.vertex_buffer {
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
+            render_pass.draw(0..self.vertex_count, 0..1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Collider;
+    use crate::app::particles::{Particle, RestitutionModel};
+
+    /// Returns whether two vectors are equal within rounding
+    fn approx_eq(lhs: [f32; 2], rhs: [f32; 2]) -> bool {
+        lhs.iter()
+            .zip(rhs)
+            .all(|(&first, second)| (first - second).abs() < 1e-5)
+    }
+
+    #[test]
+    fn particle_reflects_off_a_circle() {
+        let circle = Collider::Circle {
+            center: [0.0, 0.0],
+            radius: 1.0,
+        };
+        let mut particle = Particle::new([0.0, 1.05], [0.5, -2.0], 0.1);
+        circle.collide(&mut particle, RestitutionModel::Constant(1.0));
+        assert!(approx_eq(particle.velocity, [0.5, 2.0]));
+        assert!(approx_eq(particle.position, [0.0, 1.1]));
+    }
+
+    #[test]
+    fn restitution_scales_the_reflected_speed() {
+        let circle = Collider::Circle {
+            center: [0.0, 0.0],
+            radius: 1.0,
+        };
+        let mut particle = Particle::new([1.05, 0.0], [-2.0, 0.0], 0.1);
+        circle.collide(&mut particle, RestitutionModel::Constant(0.5));
+        assert!(approx_eq(particle.velocity, [1.0, 0.0]));
+    }
+
+    #[test]
+    fn separating_particle_keeps_its_velocity() {
+        let circle = Collider::Circle {
+            center: [0.0, 0.0],
+            radius: 1.0,
+        };
+        let mut particle = Particle::new([0.0, 1.05], [0.0, 2.0], 0.1);
+        circle.collide(&mut particle, RestitutionModel::Constant(1.0));
+        assert!(approx_eq(particle.velocity, [0.0, 2.0]));
+    }
+
+    #[test]
+    fn particle_reflects_off_the_middle_of_a_segment() {
+        let wall = Collider::Segment {
+            start: [-1.0, 0.0],
+            end: [1.0, 0.0],
+        };
+        let mut particle = Particle::new([0.25, 0.05], [1.0, -1.0], 0.1);
+        wall.collide(&mut particle, RestitutionModel::Constant(1.0));
+        assert!(approx_eq(particle.velocity, [1.0, 1.0]));
+    }
+
+    #[test]
+    fn circle_outline_is_closed() {
+        let outline = Collider::Circle {
+            center: [2.0, 0.0],
+            radius: 1.0,
+        }
+        .outline();
+        assert!(outline.len().is_multiple_of(2));
+        let (first, last) = (outline[0], outline[outline.len().saturating_sub(1)]);
+        assert!(approx_eq(first, [3.0, 0.0]));
+        assert!(approx_eq(last, first));
+    }
+}
@@ -0,0 +1,32 @@
+// Shaders for outlining the obstacles that particles bounce off
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> camera: CameraUniform;
+
+struct OutlineInput {
+    @location(0) position: vec2<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+};
+
+@vertex
+fn vs_main(outline: OutlineInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * vec4<f32>(outline.position, 0.0, 1.0);
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(0.85, 0.85, 0.85, 1.0);
+}
+
+@fragment
+fn fs_selection(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0, 0.85, 0.2, 1.0);
+}
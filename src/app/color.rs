@@ -0,0 +1,115 @@
+//! Colors given as 8-bit sRGB components, as in most color pickers, converted into the linear
+//! colors that the GPU blends and clears with
+
+/// Teal, as 8-bit sRGB components
+pub(super) const TEAL: [u8; 3] = [0, 165, 165];
+
+/// Converts a color into the components passed to shaders
+#[expect(
+    clippy::as_conversions,
+    clippy::cast_possible_truncation,
+    reason = "Color channels lie in [0, 1], well within `f32` precision"
+)]
+pub(super) const fn color_components(color: wgpu::Color) -> [f32; 4] {
+    [
+        color.r as f32,
+        color.g as f32,
+        color.b as f32,
+        color.a as f32,
+    ]
+}
+
+/// Returns the red, green, and blue components of a color, without its alpha
+pub(super) const fn rgb(color: wgpu::Color) -> [f32; 3] {
+    let [red, green, blue, _] = color_components(color);
+    [red, green, blue]
+}
+
+/// Returns the color with its red, green, and blue components replaced, keeping its alpha
+pub(super) fn with_rgb(color: wgpu::Color, [red, green, blue]: [f32; 3]) -> wgpu::Color {
+    wgpu::Color {
+        r: f64::from(red),
+        g: f64::from(green),
+        b: f64::from(blue),
+        a: color.a,
+    }
+}
+
+/// Converts an 8-bit sRGB-encoded color component into a linear component in `[0, 1]`
+#[expect(
+    clippy::float_arithmetic,
+    reason = "The sRGB transfer function is defined over real numbers"
+)]
+fn srgb_to_linear(component: u8) -> f64 {
+    let encoded = f64::from(component) / f64::from(u8::MAX);
+    if encoded <= 0.040_45 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Returns the opaque linear color with the given 8-bit sRGB components
+#[expect(
+    clippy::min_ident_chars,
+    reason = "Single-letter names are conventional for color components"
+)]
+pub(super) fn srgb8(r: u8, g: u8, b: u8) -> wgpu::Color {
+    srgb8a(r, g, b, u8::MAX)
+}
+
+/// Returns the linear color with the given 8-bit sRGB components. Alpha is not gamma-encoded, so
+/// is only scaled into `[0, 1]`
+#[expect(
+    clippy::min_ident_chars,
+    reason = "Single-letter names are conventional for color components"
+)]
+#[expect(
+    clippy::float_arithmetic,
+    reason = "Color components are normalized into `[0, 1]`"
+)]
+pub(super) fn srgb8a(r: u8, g: u8, b: u8, a: u8) -> wgpu::Color {
+    wgpu::Color {
+        r: srgb_to_linear(r),
+        g: srgb_to_linear(g),
+        b: srgb_to_linear(b),
+        a: f64::from(a) / f64::from(u8::MAX),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{color_components, srgb8, srgb8a, srgb_to_linear};
+
+    /// Returns whether two color components are within rounding of each other
+    #[expect(clippy::float_arithmetic, reason = "Comparing within a tolerance")]
+    fn close(first: f64, second: f64) -> bool {
+        (first - second).abs() < 1e-6
+    }
+
+    /// Checks the linear values that sRGB components decode to, such as about 0.5029 for 188.
+    /// Figures such as 0.3725 (95 / 255) are sRGB-encoded values, not linear ones
+    #[test]
+    fn known_srgb_values_are_linearized() {
+        assert!(close(srgb_to_linear(0), 0.0));
+        assert!(close(srgb_to_linear(10), 0.003_035));
+        assert!(close(srgb_to_linear(128), 0.215_861));
+        assert!(close(srgb_to_linear(188), 0.502_886));
+        assert!(close(srgb_to_linear(u8::MAX), 1.0));
+    }
+
+    /// Checks that alpha is the linear 188 / 255, about 0.7373, rather than the gamma-decoded 0.5029
+    /// of the color components
+    #[test]
+    fn alpha_is_scaled_without_decoding() {
+        let color = srgb8a(188, 188, 188, 188);
+        assert!(close(color.r, 0.502_886));
+        assert!(close(color.a, 0.737_255));
+    }
+
+    #[test]
+    fn srgb_colors_are_opaque() {
+        let [.., alpha] = color_components(srgb8(1, 2, 3));
+        assert!(close(f64::from(alpha), 1.0));
+    }
+}
@@ -0,0 +1,243 @@
+//! Coloring particles by how fast they move, or flatly, with colors from a palette, and sizing
+//! them on screen
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt as _;
+
+use super::{color, grid, particles::Particle};
+
+/// The slowest speed that the fastest particle is treated as moving at, so that nearly still
+/// particles are not all colored as fast
+const MIN_TOP_SPEED: f32 = 0.1;
+
+/// The fastest speed that the fastest particle is treated as moving at, so that one runaway
+/// particle does not leave every other particle colored as slow
+const MAX_TOP_SPEED: f32 = 10.0;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+/// Parameters for coloring particles, matching `Coloring` in the particle shader
+struct ColoringUniform {
+    /// The factor normalizing speeds into `[0, 1]`, the reciprocal of the fastest speed
+    speed_scale: f32,
+    /// Nonzero if particles are colored by speed, rather than a flat color
+    by_velocity: u32,
+    /// Half the width and height that every particle is drawn at, in normalized device
+    /// coordinates, or zero to draw particles at their own radius
+    point_size: [f32; 2],
+    /// The color of every particle when not colored by speed
+    flat: [f32; 4],
+    /// The color of the slowest particles
+    slow: [f32; 4],
+    /// The color of particles moving at half the top speed
+    middle: [f32; 4],
+    /// The color of the fastest particles
+    fast: [f32; 4],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// The colors that particles are drawn with, in linear space
+pub(super) struct Palette {
+    /// The color of every particle when not colored by speed
+    pub flat: wgpu::Color,
+    /// The color of the slowest particles, when colored by speed
+    pub slow: wgpu::Color,
+    /// The color of particles moving at half the top speed, when colored by speed
+    pub middle: wgpu::Color,
+    /// The color of the fastest particles, when colored by speed
+    pub fast: wgpu::Color,
+}
+
+impl Default for Palette {
+    /// White particles, ramping from blue when slow, through green, to red when fast
+    fn default() -> Self {
+        Self {
+            flat: wgpu::Color::WHITE,
+            slow: wgpu::Color::BLUE,
+            middle: wgpu::Color::GREEN,
+            fast: wgpu::Color::RED,
+        }
+    }
+}
+
+impl ColoringUniform {
+    /// Returns the parameters for coloring particles from the given palette, with speeds
+    /// normalized by the given factor, and drawing them at the given size, if any
+    const fn new(
+        speed_scale: f32,
+        by_velocity: bool,
+        palette: &Palette,
+        point_size: Option<[f32; 2]>,
+    ) -> Self {
+        Self {
+            speed_scale,
+            by_velocity: if by_velocity { 1 } else { 0 },
+            point_size: match point_size {
+                Some(size) => size,
+                None => [0.0; 2],
+            },
+            flat: color::color_components(palette.flat),
+            slow: color::color_components(palette.slow),
+            middle: color::color_components(palette.middle),
+            fast: color::color_components(palette.fast),
+        }
+    }
+}
+
+/// Returns the factor that normalizes the speeds of the given particles into `[0, 1]`, such that
+/// the fastest particle has a speed of `1`. The fastest speed is clamped to a sensible range
+pub(super) fn speed_scale(particles: &[Particle]) -> f32 {
+    let top_speed_squared = particles
+        .iter()
+        .map(|particle| grid::distance_squared(particle.velocity, [0.0, 0.0]))
+        .fold(0.0, f32::max);
+    top_speed_squared
+        .sqrt()
+        .clamp(MIN_TOP_SPEED, MAX_TOP_SPEED)
+        .recip()
+}
+
+/// Returns half the width and height, in normalized device coordinates, of a particle drawn
+/// `pixels` across on a surface of the given size in pixels. Normalized device coordinates span
+/// two units across the surface either way, so the halves cancel
+#[expect(
+    clippy::as_conversions,
+    clippy::cast_precision_loss,
+    reason = "Surface dimensions are far smaller than the precision of an `f32`"
+)]
+#[expect(
+    clippy::float_arithmetic,
+    reason = "Sizes in normalized device coordinates are fractions of the surface"
+)]
+pub(super) fn point_size_ndc(pixels: f32, width: u32, height: u32) -> [f32; 2] {
+    [pixels / width.max(1) as f32, pixels / height.max(1) as f32]
+}
+
+/// The GPU-side resources exposing how particles are colored to shaders
+pub(super) struct ColoringBinding {
+    /// Uniform buffer holding the `ColoringUniform`
+    buffer: wgpu::Buffer,
+    /// The layout of `bind_group`, for use in pipeline layouts
+    layout: wgpu::BindGroupLayout,
+    /// Binds `buffer` to the vertex shader
+    bind_group: wgpu::BindGroup,
+    /// Whether particles are colored by speed
+    by_velocity: bool,
+    /// The colors that particles are drawn with
+    palette: Palette,
+    /// The size that every particle is drawn at, from `point_size_ndc`, or `None` to draw
+    /// particles at their own radius
+    point_size: Option<[f32; 2]>,
+}
+
+impl ColoringBinding {
+    /// Creates the resources for coloring particles with a flat color
+    pub fn new(device: &wgpu::Device) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Coloring Buffer"),
+            contents: bytemuck::bytes_of(&ColoringUniform::new(
+                MIN_TOP_SPEED.recip(),
+                false,
+                &Palette::default(),
+                None,
+            )),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Coloring Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Coloring Bind Group"),
+            layout: &layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        Self {
+            buffer,
+            layout,
+            bind_group,
+            by_velocity: false,
+            palette: Palette::default(),
+            point_size: None,
+        }
+    }
+
+    /// Returns the layout of the coloring bind group
+    pub const fn layout(&self) -> &wgpu::BindGroupLayout {
+        &self.layout
+    }
+
+    /// Returns the bind group exposing how particles are colored
+    pub const fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+
+    /// Sets whether particles are colored by speed, taking effect on the next `update`
+    pub fn set_by_velocity(&mut self, by_velocity: bool) {
+        self.by_velocity = by_velocity;
+    }
+
+    /// Sets the colors that particles are drawn with, taking effect on the next `update`
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    /// Sets the size that every particle is drawn at, from `point_size_ndc`, or `None` to draw
+    /// particles at their own radius, taking effect on the next `update`
+    pub fn set_point_size(&mut self, point_size: Option<[f32; 2]>) {
+        self.point_size = point_size;
+    }
+
+    /// Normalizes coloring to the speeds of the given particles, as seen by the shaders
+    pub fn update(&self, queue: &wgpu::Queue, particles: &[Particle]) {
+        queue.write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::bytes_of(&ColoringUniform::new(
+                speed_scale(particles),
+                self.by_velocity,
+                &self.palette,
+                self.point_size,
+            )),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{point_size_ndc, speed_scale, MAX_TOP_SPEED, MIN_TOP_SPEED};
+    use crate::app::particles::{Particle, RADIUS};
+
+    /// Returns the speed scale of a single particle moving at the given speed
+    fn scale_at(speed: f32) -> f32 {
+        speed_scale(&[Particle::new([0.0, 0.0], [0.0, speed], RADIUS)])
+    }
+
+    #[test]
+    #[expect(clippy::float_cmp, reason = "Clamped speeds are exactly the bounds")]
+    fn top_speed_is_normalized_within_range() {
+        assert!((scale_at(2.0) - 0.5).abs() < 1e-6);
+        assert_eq!(scale_at(1000.0), MAX_TOP_SPEED.recip());
+        assert_eq!(scale_at(0.0), MIN_TOP_SPEED.recip());
+        assert_eq!(speed_scale(&[]), MIN_TOP_SPEED.recip());
+    }
+
+    #[test]
+    #[expect(clippy::float_cmp, reason = "Dividing by powers of two is exact")]
+    fn point_sizes_stay_the_same_number_of_pixels_across_widths() {
+        assert_eq!(point_size_ndc(8.0, 1024, 512), [1.0 / 128.0, 1.0 / 64.0]);
+        assert_eq!(point_size_ndc(8.0, 2048, 512), [1.0 / 256.0, 1.0 / 64.0]);
+    }
+}
@@ -0,0 +1,247 @@
+//! GPU integration of particles via a compute shader
+//!
+//! WebGL does not support compute shaders, so this is unavailable on the web
+
+use core::{iter, mem};
+
+use bytemuck::{Pod, Zeroable};
+
+use super::particles::Environment;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+/// Uniform parameters for a single integration step, matching `Params` in the shader
+struct Params {
+    /// Length of the timestep, in seconds
+    dt: f32,
+    /// Vertical acceleration applied to every particle
+    gravity: f32,
+    /// Fraction of speed retained when bouncing off the floor at no speed
+    restitution: f32,
+    /// Height of the floor
+    floor: f32,
+    /// The number of particles to integrate
+    count: u32,
+    /// Horizontal position of the left wall
+    left: f32,
+    /// Horizontal position of the right wall
+    right: f32,
+    /// Height of the ceiling
+    ceiling: f32,
+    /// The point the force field pushes away from
+    field_center: [f32; 2],
+    /// The strength of the force field, or zero if there is none
+    field_strength: f32,
+    /// What happens to particles reaching the edges, as given by `BoundaryMode::shader_value`
+    boundary: u32,
+    /// The factor velocities are scaled by over the step due to air resistance
+    damping: f32,
+    /// How much the fraction of speed retained falls for every meter per second of impact speed
+    restitution_slope: f32,
+}
+
+/// The compute pipeline and associated resources that integrate particles on the GPU
+pub(super) struct ParticleIntegrator {
+    /// The compute pipeline running the integration shader
+    pipeline: wgpu::ComputePipeline,
+    /// Binds the parameters and particle buffers to the pipeline: the first reads from the first
+    /// particle buffer and writes into the second, and the second the other way around
+    bind_groups: [wgpu::BindGroup; 2],
+    /// Uniform buffer holding the `Params` of the current step
+    params_buffer: wgpu::Buffer,
+    /// The number of invocations in each workgroup that the shader was built with
+    workgroup_size: u32,
+}
+
+impl ParticleIntegrator {
+    /// Creates an integrator that updates particles from either of the given pair of storage
+    /// buffers into the other, using workgroups of up to the given size, as limited by the device
+    pub fn new(device: &wgpu::Device, particles: [&wgpu::Buffer; 2], preferred_size: u32) -> Self {
+        let workgroup_size = clamp_workgroup_size(preferred_size, &device.limits());
+        // The workgroup size must be known when the shader is compiled, so it is prepended as a
+        // constant to the shader source
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("integrate.wgsl"),
+            source: wgpu::ShaderSource::Wgsl(
+                format!(
+                    "const WORKGROUP_SIZE: u32 = {workgroup_size}u;\n{}",
+                    include_str!("integrate.wgsl")
+                )
+                .into(),
+            ),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Integration Pipeline"),
+            layout: None,
+            module: &shader,
+            entry_point: "cs_main",
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        });
+        #[expect(
+            clippy::as_conversions,
+            reason = "`usize` to `u64` is lossless on all supported platforms"
+        )]
+        let params_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Integration Params Buffer"),
+            size: mem::size_of::<Params>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_groups = create_bind_groups(device, &pipeline, &params_buffer, particles);
+        Self {
+            pipeline,
+            bind_groups,
+            params_buffer,
+            workgroup_size,
+        }
+    }
+
+    /// Switches the integrator to update a different pair of particle storage buffers, such as
+    /// ones that have replaced the originals after growing
+    pub fn rebind(&mut self, device: &wgpu::Device, particles: [&wgpu::Buffer; 2]) {
+        self.bind_groups =
+            create_bind_groups(device, &self.pipeline, &self.params_buffer, particles);
+    }
+
+    /// Integrates the first `count` particles by `dt` seconds within the given environment,
+    /// submitting the work to the queue. The particles are read from the second buffer if
+    /// `flipped`, and otherwise from the first, and written into the other buffer
+    pub fn step(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        dt: f32,
+        environment: &Environment,
+        (count, flipped): (u32, bool),
+    ) {
+        let (restitution, restitution_slope) = environment.restitution.coefficients();
+        queue.write_buffer(
+            &self.params_buffer,
+            0,
+            bytemuck::bytes_of(&Params {
+                dt,
+                gravity: environment.gravity,
+                restitution,
+                floor: environment.floor,
+                count,
+                left: environment.left,
+                right: environment.right,
+                ceiling: environment.ceiling,
+                field_center: environment
+                    .force_field
+                    .map_or([0.0, 0.0], |field| field.center),
+                field_strength: environment.force_field.map_or(0.0, |field| field.strength),
+                boundary: environment.boundary.shader_value(),
+                damping: environment.damping_factor(dt),
+                restitution_slope,
+            }),
+        );
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Integration Encoder"),
+        });
+        {
+            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Integration Pass"),
+                timestamp_writes: None,
+            });
+            compute_pass.set_pipeline(&self.pipeline);
+            let [ref forward, ref backward] = self.bind_groups;
+            compute_pass.set_bind_group(0, if flipped { backward } else { forward }, &[]);
+            compute_pass.dispatch_workgroups(workgroup_count(count, self.workgroup_size), 1, 1);
+        }
+        queue.submit(iter::once(encoder.finish()));
+    }
+}
+
+/// Creates the bind groups binding the parameters and a pair of particle buffers to the
+/// integration pipeline: one reading from the first buffer and writing into the second, and one
+/// the other way around
+fn create_bind_groups(
+    device: &wgpu::Device,
+    pipeline: &wgpu::ComputePipeline,
+    params_buffer: &wgpu::Buffer,
+    [first, second]: [&wgpu::Buffer; 2],
+) -> [wgpu::BindGroup; 2] {
+    let layout = pipeline.get_bind_group_layout(0);
+    [(first, second), (second, first)].map(|(source, destination)| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Integration Bind Group"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: source.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: destination.as_entire_binding(),
+                },
+            ],
+        })
+    })
+}
+
+/// Returns the largest workgroup size no greater than the requested one that the device supports,
+/// and at least 1
+fn clamp_workgroup_size(requested: u32, limits: &wgpu::Limits) -> u32 {
+    requested
+        .min(limits.max_compute_workgroup_size_x)
+        .min(limits.max_compute_invocations_per_workgroup)
+        .max(1)
+}
+
+/// Returns the number of workgroups of the given size needed to cover every particle
+const fn workgroup_count(particles: u32, workgroup_size: u32) -> u32 {
+    particles.div_ceil(workgroup_size)
+}
+
+/// Returns the most particles that a single dispatch can integrate on a device with the given
+/// limits, using workgroups of up to the given size. Workgroups are only dispatched along one
+/// dimension, which limits how many there are
+pub(super) fn particles_dispatchable(preferred_size: u32, limits: &wgpu::Limits) -> usize {
+    let workgroups = usize::try_from(limits.max_compute_workgroups_per_dimension).unwrap_or(0);
+    let workgroup_size =
+        usize::try_from(clamp_workgroup_size(preferred_size, limits)).unwrap_or(usize::MAX);
+    workgroups.saturating_mul(workgroup_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clamp_workgroup_size, particles_dispatchable, workgroup_count};
+
+    #[test]
+    fn workgroups_cover_every_particle() {
+        assert_eq!(workgroup_count(1000, 64), 16);
+        assert_eq!(workgroup_count(1024, 64), 16);
+        assert_eq!(workgroup_count(1025, 64), 17);
+        assert_eq!(workgroup_count(0, 64), 0);
+    }
+
+    #[test]
+    fn workgroup_size_is_clamped_to_the_device() {
+        let limits = wgpu::Limits {
+            max_compute_workgroup_size_x: 128,
+            max_compute_invocations_per_workgroup: 256,
+            ..wgpu::Limits::default()
+        };
+        assert_eq!(clamp_workgroup_size(64, &limits), 64);
+        assert_eq!(clamp_workgroup_size(1024, &limits), 128);
+        assert_eq!(clamp_workgroup_size(0, &limits), 1);
+    }
+
+    #[test]
+    fn dispatches_cover_at_most_the_workgroup_limit() {
+        let limits = wgpu::Limits {
+            max_compute_workgroups_per_dimension: 10,
+            max_compute_workgroup_size_x: 128,
+            max_compute_invocations_per_workgroup: 256,
+            ..wgpu::Limits::default()
+        };
+        assert_eq!(particles_dispatchable(64, &limits), 640);
+        assert_eq!(particles_dispatchable(1024, &limits), 1280);
+    }
+}
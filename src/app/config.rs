@@ -0,0 +1,161 @@
+//! Parameters controlling how the simulation starts
+
+use super::{
+    collider::Collider, color, emitter::Emitter, particles::RestitutionModel,
+    present::PresentPreference, spawn::SpawnPattern,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// When the window is redrawn
+#[non_exhaustive]
+pub enum RedrawPolicy {
+    /// Redraw every frame, whether or not anything changed
+    #[default]
+    Continuous,
+    /// Redraw only while the simulation is running or the camera is moving, and once after any
+    /// input. While paused and idle, nothing is drawn, leaving the GPU idle too
+    OnDemand,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// An icon for the window, as tightly packed 8-bit RGBA pixels, row by row from the top left
+#[non_exhaustive]
+pub struct WindowIcon {
+    /// The pixels of the icon, four bytes each
+    pub rgba: &'static [u8],
+    /// The width of the icon, in pixels
+    pub width: u32,
+    /// The height of the icon, in pixels
+    pub height: u32,
+}
+
+impl WindowIcon {
+    /// Creates an icon of the given size from its RGBA pixels. The number of bytes is only
+    /// checked once the icon is applied to the window
+    #[inline]
+    #[must_use]
+    pub const fn new(rgba: &'static [u8], width: u32, height: u32) -> Self {
+        Self {
+            rgba,
+            width,
+            height,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Parameters of the simulation, fixed when the application starts
+#[expect(
+    clippy::exhaustive_structs,
+    reason = "Configurations are meant to be built from the default with struct update syntax"
+)]
+pub struct SimulationConfig {
+    /// The number of particles spawned when the application starts
+    pub particle_count: usize,
+    /// Vertical acceleration applied to every particle, in meters per second squared. Negative
+    /// values pull downwards, so `-9.81` is the Earth's gravity
+    pub gravity: f32,
+    /// How the fraction of its speed that a particle retains when bouncing off the edges of the
+    /// world, obstacles, or other particles depends on how fast it hits them
+    pub restitution: RestitutionModel,
+    /// The width and height of the world that particles are kept within, in meters, centered on
+    /// the origin and fitted into the window whatever its size. If `None`, the world is whatever
+    /// the flat view shows, two meters tall at unit zoom and as wide as the window's aspect ratio
+    pub world_size: Option<[f32; 2]>,
+    /// The height of the floor that particles bounce off, in meters. If `None`, the floor is the
+    /// bottom of the world when it is sized, or one meter below the origin otherwise
+    pub floor: Option<f32>,
+    /// The color the surface is cleared to before drawing, in linear space
+    pub background: wgpu::Color,
+    /// The preferred way of presenting rendered frames
+    pub present_preference: PresentPreference,
+    /// The number of samples per pixel used for anti-aliasing, from 1 to 8.
+    /// Anti-aliasing is disabled if this is 1, and unsupported counts fall back to the nearest
+    /// supported count
+    pub msaa_samples: u32,
+    /// The seed of the random number generator that all randomness in the simulation is drawn
+    /// from, for reproducible runs. If `None`, a random seed is chosen and logged
+    pub seed: Option<u64>,
+    /// The preferred number of invocations in each workgroup when integrating particles on the
+    /// GPU. Clamped to what the device supports. Ignored on the web, where particles are integrated
+    /// on the CPU
+    pub workgroup_size: u32,
+    /// Whether the simulation pauses while the window is unfocused, so that background windows
+    /// are cheap. A deliberate pause is kept when focus returns
+    pub pause_on_focus_loss: bool,
+    /// The number of frames that may be queued for presentation ahead of the display, from 1
+    /// for the most responsive display to 3 for the smoothest throughput
+    pub frame_latency: u32,
+    /// The most physics steps taken to catch up within a single frame at normal speed, scaled up
+    /// when running faster. Any time beyond that is dropped, so that the simulation recovers from
+    /// stalls rather than falling ever further behind. At least one step is always allowed
+    pub max_steps_per_frame: u32,
+    /// The formats to configure the surface with, in order of preference. The first that the
+    /// surface supports is used, and if none are, an sRGB format is preferred
+    pub surface_formats: &'static [wgpu::TextureFormat],
+    /// When the window is redrawn
+    pub redraw_policy: RedrawPolicy,
+    /// How the particles are arranged when the particle simulation starts or is reset
+    pub spawn_pattern: SpawnPattern,
+    /// The sources continuously spawning particles, such as fountains, when the application
+    /// starts or is reset
+    pub emitters: &'static [Emitter],
+    /// The static obstacles placed in the world, which particles bounce off
+    pub colliders: &'static [Collider],
+    /// The largest GPU buffer, in bytes, requested from the device to hold many particles.
+    /// Raised to at least the default limits, and then clamped to what the adapter supports.
+    /// Ignored on the web, where the WebGL2 limits are used
+    pub max_buffer_size: u64,
+    /// The title of the window created on desktop platforms
+    pub title: &'static str,
+    /// The icon of the window created on desktop platforms, if any. Icons whose pixels do not
+    /// match their size are skipped with an error
+    pub icon: Option<WindowIcon>,
+}
+
+impl Default for SimulationConfig {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            particle_count: 1000,
+            gravity: -1.0,
+            restitution: RestitutionModel::default(),
+            world_size: None,
+            floor: None,
+            background: {
+                let [red, green, blue] = color::TEAL;
+                color::srgb8(red, green, blue)
+            },
+            present_preference: PresentPreference::default(),
+            msaa_samples: 4,
+            seed: None,
+            workgroup_size: 64,
+            pause_on_focus_loss: true,
+            frame_latency: 2,
+            max_steps_per_frame: 5,
+            surface_formats: &[],
+            redraw_policy: RedrawPolicy::default(),
+            spawn_pattern: SpawnPattern::default(),
+            emitters: &[],
+            colliders: &[],
+            max_buffer_size: 1 << 30,
+            title: "Physical Simulation",
+            icon: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{color, SimulationConfig};
+
+    #[test]
+    fn default_background_is_teal() {
+        let config = SimulationConfig::default();
+        let [red, green, blue] = color::TEAL;
+        assert_eq!(config.background, color::srgb8(red, green, blue));
+        assert_eq!(config.particle_count, 1000);
+        assert_eq!(config.msaa_samples, 4);
+        assert!(config.seed.is_none());
+    }
+}
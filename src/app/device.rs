@@ -0,0 +1,380 @@
+//! Choosing the adapter to render with, and the features and limits to request of its device
+
+use core::{future::Future, iter, mem};
+
+use log::{info, warn};
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::compute::particles_dispatchable;
+use super::particles::Particle;
+
+/// Format of the depth buffer
+pub(super) const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// Features that are enabled if the adapter supports them, but are not required
+const OPTIONAL_FEATURES: wgpu::Features =
+    wgpu::Features::TIMESTAMP_QUERY.union(wgpu::Features::POLYGON_MODE_LINE);
+
+/// Returns the optional features to enable, out of those supported by the adapter
+const fn optional_features(available: wgpu::Features) -> wgpu::Features {
+    available.intersection(OPTIONAL_FEATURES)
+}
+
+/// Returns the default adapter for tests to render with, or `None`, skipping the test, if there
+/// is no adapter
+#[cfg(test)]
+pub(super) fn test_adapter() -> Option<wgpu::Adapter> {
+    let instance = wgpu::Instance::default();
+    let adapter =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()));
+    if adapter.is_none() {
+        eprintln!("No adapter to render with; skipping");
+    }
+    adapter
+}
+
+/// Returns a device of the default adapter for tests to render with, with the same optional
+/// features as the application enables, or `None`, skipping the test, if there is no adapter
+#[cfg(test)]
+pub(super) fn test_device() -> Option<(wgpu::Device, wgpu::Queue)> {
+    let adapter = test_adapter()?;
+    pollster::block_on(request_device(&adapter, 0)).ok()
+}
+
+/// Requests an adapter compatible with the given surface, trying each of the `adapter_requests`
+/// in turn. Returns `None` if not even the fallback adapter is available
+pub(super) async fn request_adapter(
+    instance: &wgpu::Instance,
+    surface: &wgpu::Surface<'_>,
+    power_preference: wgpu::PowerPreference,
+) -> Option<wgpu::Adapter> {
+    let (adapter, fallback) = first_adapter(power_preference, |preference, fallback| {
+        instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: preference,
+            compatible_surface: Some(surface),
+            force_fallback_adapter: fallback,
+        })
+    })
+    .await?;
+    let adapter_info = adapter.get_info();
+    if fallback {
+        warn!(
+            "No hardware adapter found, so using the fallback adapter {} ({:?} backend), \
+             which may be a slow software rasterizer",
+            adapter_info.name, adapter_info.backend
+        );
+    } else {
+        info!(
+            "Using adapter {} ({:?} backend)",
+            adapter_info.name, adapter_info.backend
+        );
+    }
+    Some(adapter)
+}
+
+/// Makes each of the `adapter_requests` in turn with the given function, until one finds an
+/// adapter. Returns that adapter, and whether it is the fallback adapter
+async fn first_adapter<A, F>(
+    preferred: wgpu::PowerPreference,
+    mut request: impl FnMut(wgpu::PowerPreference, bool) -> F,
+) -> Option<(A, bool)>
+where
+    F: Future<Output = Option<A>>,
+{
+    for (preference, fallback) in adapter_requests(preferred) {
+        if let Some(adapter) = request(preference, fallback).await {
+            return Some((adapter, fallback));
+        }
+        if fallback {
+            warn!("No fallback adapter found either");
+        } else {
+            warn!("No adapter found with power preference {preference:?}");
+        }
+    }
+    None
+}
+
+/// Returns the requests to make for an adapter, in order, as power preferences and whether to
+/// force the fallback adapter: each of the power preference fallbacks for a hardware adapter, and
+/// then the fallback adapter, which is a software rasterizer where one is available
+fn adapter_requests(
+    preferred: wgpu::PowerPreference,
+) -> impl Iterator<Item = (wgpu::PowerPreference, bool)> {
+    power_preference_fallbacks(preferred)
+        .map(|preference| (preference, false))
+        .chain(iter::once((wgpu::PowerPreference::default(), true)))
+}
+
+/// Returns the power preferences to request an adapter with, in order of preference: the given
+/// preference, followed by the default if it differs
+fn power_preference_fallbacks(
+    preferred: wgpu::PowerPreference,
+) -> impl Iterator<Item = wgpu::PowerPreference> {
+    let default = wgpu::PowerPreference::default();
+    iter::once(preferred).chain((preferred != default).then_some(default))
+}
+
+/// Requests a device and its queue from the adapter, enabling whichever optional features it
+/// supports, and raising the largest buffer sizes towards the preferred size, in bytes
+pub(super) async fn request_device(
+    adapter: &wgpu::Adapter,
+    preferred_buffer_size: u64,
+) -> Result<(wgpu::Device, wgpu::Queue), wgpu::RequestDeviceError> {
+    let features = optional_features(adapter.features());
+    info!("Enabling optional features {features:?}");
+    adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                required_features: features,
+                required_limits: required_limits(&adapter.limits(), preferred_buffer_size),
+                label: None,
+            },
+            None, // Trace path
+        )
+        .await
+}
+
+/// Returns the limits to request from a device on an adapter with the given limits, raising the
+/// largest buffer sizes towards the preferred size, in bytes, as far as the adapter supports
+fn required_limits(adapter: &wgpu::Limits, preferred_buffer_size: u64) -> wgpu::Limits {
+    // WebGL doesn't support all of wgpu's features, so if
+    // we're building for the web, we'll have to disable some.
+    if cfg!(target_arch = "wasm32") {
+        return wgpu::Limits::downlevel_webgl2_defaults();
+    }
+    let defaults = wgpu::Limits::default();
+    // Clamping to the adapter comes last, as requesting more than it supports fails, even when
+    // that is less than the defaults
+    let max_buffer_size = preferred_buffer_size
+        .max(defaults.max_buffer_size)
+        .min(adapter.max_buffer_size);
+    // Storage bindings cannot exceed the buffers they bind, nor the range of a `u32`
+    let max_storage_buffer_binding_size = u32::try_from(max_buffer_size)
+        .unwrap_or(u32::MAX)
+        .max(defaults.max_storage_buffer_binding_size)
+        .min(adapter.max_storage_buffer_binding_size);
+    wgpu::Limits {
+        max_buffer_size,
+        max_storage_buffer_binding_size,
+        ..defaults
+    }
+}
+
+/// Returns the most particles that fit within a single buffer under the given limits. When there
+/// are storage buffers, particles must also fit within a single storage binding. Natively, where
+/// particles are integrated on the GPU, they must also be covered by a single dispatch of
+/// workgroups of up to the given size
+#[cfg_attr(
+    target_arch = "wasm32",
+    expect(
+        unused_variables,
+        reason = "Particles are integrated on the CPU on the web, so are never dispatched"
+    )
+)]
+pub(super) fn particles_supported(limits: &wgpu::Limits, workgroup_size: u32) -> usize {
+    let storage = u64::from(limits.max_storage_buffer_binding_size);
+    let bytes = if storage > 0 {
+        limits.max_buffer_size.min(storage)
+    } else {
+        limits.max_buffer_size
+    };
+    let buffered = usize::try_from(bytes)
+        .unwrap_or(usize::MAX)
+        .checked_div(mem::size_of::<Particle>())
+        .unwrap_or(0);
+    #[cfg(not(target_arch = "wasm32"))]
+    let dispatchable = particles_dispatchable(workgroup_size, limits);
+    #[cfg(target_arch = "wasm32")]
+    let dispatchable = usize::MAX;
+    buffered.min(dispatchable)
+}
+
+/// Returns the format to configure a surface with, out of those it supports: the first of the
+/// preferred formats that is supported, or otherwise any sRGB format, or otherwise the first.
+/// Returns `None` if the surface supports no formats at all
+pub(super) fn select_surface_format(
+    preferred: &[wgpu::TextureFormat],
+    supported: &[wgpu::TextureFormat],
+) -> Option<wgpu::TextureFormat> {
+    // Shaders output linear colors, which an sRGB surface encodes for display. Other surfaces are
+    // drawn through an sRGB view where possible, so sRGB formats are only preferred
+    preferred
+        .iter()
+        .find(|format| supported.contains(format))
+        .or_else(|| supported.iter().find(|format| format.is_srgb()))
+        .or(supported.first())
+        .copied()
+}
+
+/// The numbers of samples per pixel that anti-aliasing may be configured with
+const MSAA_LEVELS: [u32; 4] = [1, 2, 4, 8];
+
+/// Returns the numbers of samples per pixel, from `MSAA_LEVELS`, that both the given color format
+/// and the depth format support on the adapter. Without an adapter, support cannot be checked, so
+/// only a single sample is supported
+pub(super) fn supported_msaa_samples(
+    adapter: Option<&wgpu::Adapter>,
+    format: wgpu::TextureFormat,
+) -> Vec<u32> {
+    let Some(available) = adapter else {
+        return vec![1];
+    };
+    MSAA_LEVELS
+        .into_iter()
+        .filter(|&samples| {
+            samples == 1
+                || [format, DEPTH_FORMAT].into_iter().all(|attachment_format| {
+                    available
+                        .get_texture_format_features(attachment_format)
+                        .flags
+                        .sample_count_supported(samples)
+                })
+        })
+        .collect()
+}
+
+/// Returns the supported number of samples nearest to the preferred number, preferring fewer
+/// samples between two equally near. A single sample is always supported
+fn nearest_msaa_samples(preferred: u32, supported: &[u32]) -> u32 {
+    supported
+        .iter()
+        .copied()
+        .min_by_key(|&samples| (samples.abs_diff(preferred), samples))
+        .unwrap_or(1)
+}
+
+/// Returns the number of samples per pixel to render with: the preferred number of samples for
+/// anti-aliasing if both the color format and the depth format support it, and otherwise the
+/// nearest number that they do
+pub(super) fn select_msaa_samples(
+    adapter: Option<&wgpu::Adapter>,
+    format: wgpu::TextureFormat,
+    preferred: u32,
+) -> u32 {
+    let samples = nearest_msaa_samples(preferred, &supported_msaa_samples(adapter, format));
+    if samples != preferred.max(1) {
+        warn!("{preferred}x anti-aliasing is unsupported; using {samples}x instead");
+    }
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem;
+
+    use wgpu::{PowerPreference, TextureFormat};
+
+    use super::{
+        first_adapter, nearest_msaa_samples, optional_features, particles_supported,
+        power_preference_fallbacks, select_surface_format,
+    };
+    use crate::app::particles::Particle;
+
+    #[test]
+    fn preferred_power_is_tried_before_the_default() {
+        assert!(power_preference_fallbacks(PowerPreference::HighPerformance)
+            .eq([PowerPreference::HighPerformance, PowerPreference::default()]));
+        assert!(
+            power_preference_fallbacks(PowerPreference::default()).eq([PowerPreference::default()])
+        );
+    }
+
+    #[test]
+    fn optional_features_are_only_those_available() {
+        let available = wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::DEPTH_CLIP_CONTROL;
+        let enabled = optional_features(available);
+        assert!(available.contains(enabled));
+        assert_eq!(enabled, wgpu::Features::TIMESTAMP_QUERY);
+        assert!(optional_features(wgpu::Features::empty()).is_empty());
+    }
+
+    #[test]
+    fn fallback_adapter_is_requested_once_hardware_is_not_found() {
+        let mut requests = Vec::new();
+        let found = pollster::block_on(first_adapter(
+            PowerPreference::default(),
+            |preference, fallback| {
+                requests.push((preference, fallback));
+                async move { fallback.then_some("software") }
+            },
+        ));
+        assert_eq!(found, Some(("software", true)));
+        assert_eq!(
+            requests,
+            [
+                (PowerPreference::default(), false),
+                (PowerPreference::default(), true)
+            ]
+        );
+    }
+
+    #[test]
+    fn no_adapter_is_found_if_even_the_fallback_fails() {
+        let found = pollster::block_on(first_adapter(
+            PowerPreference::HighPerformance,
+            |_, _| async { None::<()> },
+        ));
+        assert_eq!(found, None);
+    }
+
+    #[test]
+    fn first_supported_preferred_format_is_selected() {
+        let supported = [
+            TextureFormat::Bgra8Unorm,
+            TextureFormat::Bgra8UnormSrgb,
+            TextureFormat::Rgba8UnormSrgb,
+        ];
+        let preferred = [TextureFormat::Rgba16Float, TextureFormat::Rgba8UnormSrgb];
+        assert_eq!(
+            select_surface_format(&preferred, &supported),
+            Some(TextureFormat::Rgba8UnormSrgb)
+        );
+        assert_eq!(
+            select_surface_format(&[TextureFormat::Rgba16Float], &supported),
+            Some(TextureFormat::Bgra8UnormSrgb)
+        );
+        assert_eq!(
+            select_surface_format(&[], &[TextureFormat::Bgra8Unorm]),
+            Some(TextureFormat::Bgra8Unorm)
+        );
+        assert_eq!(select_surface_format(&preferred, &[]), None);
+    }
+
+    #[test]
+    fn unsupported_msaa_falls_back_to_the_highest_supported() {
+        assert_eq!(nearest_msaa_samples(8, &[1, 2, 4]), 4);
+        assert_eq!(nearest_msaa_samples(8, &[1]), 1);
+        assert_eq!(nearest_msaa_samples(4, &[1, 4, 8]), 4);
+        assert_eq!(nearest_msaa_samples(3, &[1, 2, 4]), 2);
+        assert_eq!(nearest_msaa_samples(4, &[]), 1);
+    }
+
+    #[test]
+    fn supported_particles_fit_the_smaller_buffer_limit() {
+        assert_eq!(mem::size_of::<Particle>(), 24);
+        let limits = wgpu::Limits {
+            max_buffer_size: 24_000,
+            max_storage_buffer_binding_size: 2_400,
+            ..wgpu::Limits::default()
+        };
+        assert_eq!(particles_supported(&limits, 64), 100);
+        let without_storage = wgpu::Limits {
+            max_storage_buffer_binding_size: 0,
+            ..limits
+        };
+        assert_eq!(particles_supported(&without_storage, 64), 1_000);
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn supported_particles_fit_a_single_dispatch() {
+        let limits = wgpu::Limits {
+            max_compute_workgroups_per_dimension: 10,
+            max_compute_workgroup_size_x: 128,
+            max_compute_invocations_per_workgroup: 256,
+            ..wgpu::Limits::default()
+        };
+        assert_eq!(particles_supported(&limits, 64), 640);
+        assert_eq!(particles_supported(&limits, 1024), 1280);
+    }
+}
@@ -0,0 +1,134 @@
+//! A secondary window showing statistics about the simulation, rendered with the main window's
+//! device
+
+use core::{iter, time::Duration};
+use std::sync::Arc;
+
+#[cfg(not(target_arch = "wasm32"))]
+use winit::window::WindowId;
+use winit::{dpi, window::Window};
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::gamma::GammaHandling;
+use super::hud::Hud;
+
+/// Returns the text shown in a diagnostics window for the given statistics. The GPU frame time
+/// is `N/A` if it is not being measured
+#[expect(
+    clippy::float_arithmetic,
+    reason = "GPU time is shown in fractional milliseconds"
+)]
+pub(super) fn diagnostics_text(
+    fps: f32,
+    particle_count: usize,
+    gpu_time: Option<Duration>,
+) -> String {
+    let gpu_millis = gpu_time.map_or_else(
+        || "N/A".to_owned(),
+        |time| format!("{:.3} ms", time.as_secs_f64() * 1000.0_f64),
+    );
+    format!("FPS: {fps:.1}\nParticles: {particle_count}\nGPU: {gpu_millis}")
+}
+
+/// A window with its own surface, sharing the device and queue of the main window, that shows
+/// statistics instead of the simulation
+pub(super) struct DiagnosticsWindow {
+    /// The window the surface presents to
+    window: Arc<Window>,
+    /// The surface to render to
+    surface: wgpu::Surface<'static>,
+    /// Configuration for the surface
+    config: wgpu::SurfaceConfiguration,
+    /// The format that the statistics are drawn in, which views of the surface are created with
+    render_format: wgpu::TextureFormat,
+    /// Draws the statistics
+    hud: Hud,
+}
+
+impl DiagnosticsWindow {
+    /// Creates a diagnostics window rendering to the given surface of the given window,
+    /// configuring it as given. The configuration should allow any sRGB view format required by
+    /// its `GammaHandling`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(
+        device: &wgpu::Device,
+        window: Arc<Window>,
+        surface: wgpu::Surface<'static>,
+        config: wgpu::SurfaceConfiguration,
+    ) -> Self {
+        surface.configure(device, &config);
+        let render_format = GammaHandling::for_format(config.format).render_format(config.format);
+        let hud = Hud::new(device, render_format);
+        Self {
+            window,
+            surface,
+            config,
+            render_format,
+            hud,
+        }
+    }
+
+    /// Returns the id of the window, which its events are dispatched by
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    /// Returns the window that this renders to
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    /// Reconfigures the surface to the given size. Zero-sized windows are ignored, as surfaces
+    /// cannot be empty
+    pub fn resize(&mut self, device: &wgpu::Device, new_size: dpi::PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.config.width = new_size.width;
+            self.config.height = new_size.height;
+            self.surface.configure(device, &self.config);
+        }
+    }
+
+    /// Reconfigures the surface to match the window, such as after the surface was lost
+    pub fn reconfigure(&mut self, device: &wgpu::Device) {
+        let size = self.window.inner_size();
+        self.resize(device, size);
+    }
+
+    /// Clears the window and draws the given text onto it
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        text: &str,
+    ) -> Result<(), wgpu::SurfaceError> {
+        let output = self.surface.get_current_texture()?;
+        let view = output.texture.create_view(&wgpu::TextureViewDescriptor {
+            format: Some(self.render_format),
+            ..wgpu::TextureViewDescriptor::default()
+        });
+        self.hud
+            .set_text(queue, text, self.config.width, self.config.height);
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Diagnostics Encoder"),
+        });
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Diagnostics Clear Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        self.hud.draw(&mut encoder, &view);
+        queue.submit(iter::once(encoder.finish()));
+        output.present();
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+//! A soft circular alpha texture that particles can be drawn with
+
+use wgpu::util::{DeviceExt as _, TextureDataOrder};
+
+/// The side length of the disc texture, in texels
+const SIZE: u32 = 32;
+
+/// The distance from the center, as a fraction of the radius, at which the disc starts to fade out
+const FEATHER_START: f32 = 0.6;
+
+/// Returns the opacity of the disc at the given distance from its center, as a fraction of its
+/// radius: opaque up to `FEATHER_START`, then smoothly fading out to fully transparent at the edge
+#[expect(
+    clippy::float_arithmetic,
+    reason = "The feathered edge is a smooth curve over the continuous distance from the center"
+)]
+fn disc_alpha(distance: f32) -> f32 {
+    let fade = ((distance - FEATHER_START) / (1.0 - FEATHER_START)).clamp(0.0, 1.0);
+    1.0 - fade * fade * (3.0 - 2.0 * fade)
+}
+
+/// Returns the texels of the disc texture, row by row, as 8-bit opacities
+#[expect(
+    clippy::as_conversions,
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "Texel coordinates are tiny, and opacities are rounded from [0, 255]"
+)]
+#[expect(
+    clippy::float_arithmetic,
+    reason = "Texels are sampled at their continuous centers"
+)]
+fn disc_texels() -> Vec<u8> {
+    let half = SIZE as f32 / 2.0;
+    (0..SIZE)
+        .flat_map(|row| (0..SIZE).map(move |column| (row, column)))
+        .map(|(row, column)| {
+            // Texels are sampled at their centers
+            let x = (column as f32 + 0.5 - half) / half;
+            let y = (row as f32 + 0.5 - half) / half;
+            (disc_alpha(x.hypot(y)) * f32::from(u8::MAX)).round() as u8
+        })
+        .collect()
+}
+
+/// The texture that particles are drawn with in the disc style, bound for the particle shader
+pub(super) struct DiscTexture {
+    /// The layout of `bind_group`, for use in pipeline layouts
+    layout: wgpu::BindGroupLayout,
+    /// Binds the texture and its sampler to the fragment shader
+    bind_group: wgpu::BindGroup,
+}
+
+impl DiscTexture {
+    /// Creates and uploads the disc texture
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let texture = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("Disc Texture"),
+                size: wgpu::Extent3d {
+                    width: SIZE,
+                    height: SIZE,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::R8Unorm,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            TextureDataOrder::LayerMajor,
+            &disc_texels(),
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Disc Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..wgpu::SamplerDescriptor::default()
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Disc Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Disc Bind Group"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+        Self { layout, bind_group }
+    }
+
+    /// Returns the layout of the disc bind group
+    pub const fn layout(&self) -> &wgpu::BindGroupLayout {
+        &self.layout
+    }
+
+    /// Returns the bind group exposing the disc texture and its sampler
+    pub const fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+}
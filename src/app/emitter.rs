@@ -0,0 +1,136 @@
+//! Sources that continuously spawn particles, such as to make fountains
+
+use core::iter;
+
+use rand::Rng;
+
+use super::particles::{Particle, SizeDistribution};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Spawns particles at a steady rate from a point, heading out within a cone
+pub struct Emitter {
+    /// Where particles are spawned, in meters
+    position: [f32; 2],
+    /// The number of particles spawned per second
+    rate: f32,
+    /// The direction that the cone of velocities is centered on, in radians anticlockwise from
+    /// the positive x-axis
+    direction: f32,
+    /// How far either side of `direction` that particles may head, in radians
+    spread: f32,
+    /// The speed that particles are spawned with, in meters per second
+    speed: f32,
+    /// The fraction of a particle due but not yet spawned, carried between steps so that rates
+    /// too low to spawn a particle every step still spawn at the right rate overall
+    pending: f32,
+}
+
+impl Emitter {
+    /// Creates an emitter at the given position, spawning `rate` particles per second heading
+    /// within `spread` radians either side of `direction` at the given speed. Directions are in
+    /// radians anticlockwise from the positive x-axis
+    #[inline]
+    #[must_use]
+    pub const fn new(
+        position: [f32; 2],
+        rate: f32,
+        direction: f32,
+        spread: f32,
+        speed: f32,
+    ) -> Self {
+        Self {
+            position,
+            rate,
+            direction,
+            spread,
+            speed,
+            pending: 0.0,
+        }
+    }
+
+    /// Returns the number of whole particles due after a further `dt` seconds, keeping any
+    /// fraction of a particle for later
+    #[expect(
+        clippy::as_conversions,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        reason = "The count is a non-negative whole number, and small for any sensible rate"
+    )]
+    #[expect(
+        clippy::float_arithmetic,
+        reason = "Emission accumulates fractions of a particle between frames"
+    )]
+    fn due(&mut self, dt: f32) -> usize {
+        self.pending += self.rate.max(0.0) * dt;
+        let count = self.pending.floor();
+        self.pending -= count;
+        count as usize
+    }
+
+    /// Returns the particles spawned over a further `dt` seconds, with radii drawn from the given
+    /// distribution
+    #[expect(
+        clippy::float_arithmetic,
+        reason = "Velocities are spread around the emitter's direction by a random angle"
+    )]
+    pub(super) fn emit<R>(&mut self, dt: f32, rng: &mut R, sizes: SizeDistribution) -> Vec<Particle>
+    where
+        R: Rng,
+    {
+        let spread = self.spread.abs();
+        let count = self.due(dt);
+        iter::repeat_with(|| {
+            let angle = self.direction + rng.gen_range(-spread..=spread);
+            let velocity = [self.speed * angle.cos(), self.speed * angle.sin()];
+            Particle::new(self.position, velocity, sizes.sample(rng))
+        })
+        .take(count)
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::iter;
+
+    use rand::{rngs::StdRng, SeedableRng as _};
+
+    use super::Emitter;
+    use crate::app::particles::SizeDistribution;
+
+    #[test]
+    fn fractional_emissions_accumulate() {
+        let mut emitter = Emitter::new([0.0, 0.0], 10.0, 0.0, 0.0, 1.0);
+        let count: usize = iter::repeat_with(|| emitter.due(0.0625)).take(4).sum();
+        assert!((2..=3).contains(&count), "emitted {count}");
+    }
+
+    #[test]
+    fn low_rates_still_emit() {
+        let mut emitter = Emitter::new([0.0, 0.0], 1.0, 0.0, 0.0, 1.0);
+        let count: usize = iter::repeat_with(|| emitter.due(0.015)).take(100).sum();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn negative_rates_emit_nothing() {
+        let mut emitter = Emitter::new([0.0, 0.0], -10.0, 0.0, 0.0, 1.0);
+        assert_eq!(emitter.due(1.0), 0);
+        assert_eq!(emitter.due(1.0), 0);
+    }
+
+    #[test]
+    fn particles_leave_at_the_emitter_speed() {
+        let mut emitter = Emitter::new([1.0, 2.0], 4.0, 0.0, 0.5, 3.0);
+        let mut rng = StdRng::seed_from_u64(0);
+        let particles = emitter.emit(1.0, &mut rng, SizeDistribution::default());
+        assert_eq!(particles.len(), 4);
+        for particle in particles {
+            let [x, y] = particle.position;
+            assert!((x - 1.0).abs() < f32::EPSILON && (y - 2.0).abs() < f32::EPSILON);
+            let [horizontal, vertical] = particle.velocity;
+            assert!((horizontal.hypot(vertical) - 3.0).abs() < 1e-5);
+            assert!(vertical.atan2(horizontal).abs() <= 0.5 + 1e-5);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! Tracking of frame times to measure the frame rate
+
+use core::time::Duration;
+use std::collections::VecDeque;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
+
+/// The number of most recent frames averaged over
+const WINDOW: usize = 60;
+
+/// How often the frame rate should be reported
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A rolling record of the time taken by recent frames
+pub(super) struct FrameTimer {
+    /// The durations of the most recent frames, oldest first
+    frame_times: VecDeque<Duration>,
+    /// When the last frame was rendered. `None` before the first frame
+    last_frame: Option<Instant>,
+    /// When the frame rate was last reported
+    last_report: Option<Instant>,
+}
+
+impl FrameTimer {
+    /// Creates a timer with no recorded frames
+    pub fn new() -> Self {
+        Self {
+            frame_times: VecDeque::with_capacity(WINDOW),
+            last_frame: None,
+            last_report: None,
+        }
+    }
+
+    /// Records that a frame was rendered at `now`.
+    /// The first frame only establishes a starting point, as it has no prior frame to measure from
+    pub fn frame(&mut self, now: Instant) {
+        if let Some(last_frame) = self.last_frame.replace(now) {
+            self.push(now.duration_since(last_frame));
+        }
+    }
+
+    /// Records the duration of a single frame, discarding the oldest if the window is full
+    fn push(&mut self, frame_time: Duration) {
+        if self.frame_times.len() >= WINDOW {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+    }
+
+    /// Returns the average frame rate over the recorded frames, or `0` if there are none
+    #[expect(
+        clippy::as_conversions,
+        clippy::cast_precision_loss,
+        reason = "The window is small enough to be represented exactly"
+    )]
+    #[expect(
+        clippy::float_arithmetic,
+        reason = "A frame rate is a fraction of frames over time"
+    )]
+    pub fn fps(&self) -> f32 {
+        let total: Duration = self.frame_times.iter().sum();
+        if total.is_zero() {
+            0.0
+        } else {
+            self.frame_times.len() as f32 / total.as_secs_f32()
+        }
+    }
+
+    /// Returns whether the frame rate should be reported at `now`, which is at most once per
+    /// `REPORT_INTERVAL`
+    pub fn should_report(&mut self, now: Instant) -> bool {
+        let due = self
+            .last_report
+            .is_none_or(|last_report| now.duration_since(last_report) >= REPORT_INTERVAL);
+        if due {
+            self.last_report = Some(now);
+        }
+        due
+    }
+}
+
+/// Returns a line of `key=value` statistics about recent frames, for logging. The GPU frame time
+/// is `n/a` if it is not being measured
+#[expect(
+    clippy::float_arithmetic,
+    reason = "GPU time is shown in fractional milliseconds"
+)]
+pub(super) fn frame_stats(fps: f32, particle_count: usize, gpu_time: Option<Duration>) -> String {
+    let gpu_millis = gpu_time.map_or_else(
+        || "n/a".to_owned(),
+        |time| format!("{:.3}ms", time.as_secs_f64() * 1000.0_f64),
+    );
+    format!("fps={fps:.1} particles={particle_count} gpu_time={gpu_millis}")
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{frame_stats, FrameTimer, Instant};
+
+    #[test]
+    fn first_frame_is_not_a_sample() {
+        let mut timer = FrameTimer::new();
+        timer.frame(Instant::now());
+        assert!(timer.frame_times.is_empty());
+    }
+
+    #[test]
+    fn fps_averages_the_recorded_frames() {
+        let mut timer = FrameTimer::new();
+        for millis in [10, 20, 30] {
+            timer.push(Duration::from_millis(millis));
+        }
+        // Three frames in 60ms
+        assert!((timer.fps() - 50.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn stats_are_logged_as_fields() {
+        assert_eq!(
+            frame_stats(59.94, 1000, Some(Duration::from_micros(1500))),
+            "fps=59.9 particles=1000 gpu_time=1.500ms"
+        );
+        assert_eq!(
+            frame_stats(60.0, 0, None),
+            "fps=60.0 particles=0 gpu_time=n/a"
+        );
+    }
+}
@@ -0,0 +1,76 @@
+//! Encoding of the linear colors written by shaders for display, whatever the surface format
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// How the linear colors written by shaders are encoded into sRGB for display
+pub(super) enum GammaHandling {
+    /// The surface format is sRGB, so colors are encoded as they are written
+    SrgbSurface,
+    /// The surface format is not sRGB, so colors are drawn through a view of the surface with
+    /// this equivalent sRGB format, which encodes them as they are written
+    SrgbView(wgpu::TextureFormat),
+    /// The surface format has no sRGB equivalent, so colors are written as they are
+    Linear,
+}
+
+impl GammaHandling {
+    /// Returns how colors should be encoded for a surface of the given format
+    pub fn for_format(format: wgpu::TextureFormat) -> Self {
+        if format.is_srgb() {
+            Self::SrgbSurface
+        } else {
+            let srgb = format.add_srgb_suffix();
+            if srgb == format {
+                Self::Linear
+            } else {
+                Self::SrgbView(srgb)
+            }
+        }
+    }
+
+    /// Returns the format that is drawn into, given the format of the surface: the format of
+    /// the sRGB view if there is one, and otherwise the surface's own format
+    pub const fn render_format(self, surface_format: wgpu::TextureFormat) -> wgpu::TextureFormat {
+        match self {
+            Self::SrgbView(format) => format,
+            Self::SrgbSurface | Self::Linear => surface_format,
+        }
+    }
+
+    /// Adds the sRGB view format, if any, to the formats that views of the configured surface
+    /// may have
+    pub fn configure(self, config: &mut wgpu::SurfaceConfiguration) {
+        if let Self::SrgbView(format) = self {
+            if !config.view_formats.contains(&format) {
+                config.view_formats.push(format);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu::TextureFormat;
+
+    use super::GammaHandling;
+
+    #[test]
+    fn unorm_surfaces_are_drawn_through_an_srgb_view() {
+        let handling = GammaHandling::for_format(TextureFormat::Bgra8Unorm);
+        assert_eq!(
+            handling,
+            GammaHandling::SrgbView(TextureFormat::Bgra8UnormSrgb)
+        );
+        assert_eq!(
+            handling.render_format(TextureFormat::Bgra8Unorm),
+            TextureFormat::Bgra8UnormSrgb
+        );
+        assert_eq!(
+            GammaHandling::for_format(TextureFormat::Rgba8UnormSrgb),
+            GammaHandling::SrgbSurface
+        );
+        assert_eq!(
+            GammaHandling::for_format(TextureFormat::Rgba16Float),
+            GammaHandling::Linear
+        );
+    }
+}
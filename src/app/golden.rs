@@ -0,0 +1,220 @@
+//! Rendering a fixed, seeded scene offscreen for comparison against a stored golden image, so
+//! that changes to the shaders or physics show up as differences in the rendered pixels
+
+use core::{error::Error, fmt};
+
+use pollster::block_on;
+
+use super::{
+    app::{Application, OffscreenError},
+    config::SimulationConfig,
+};
+
+/// The width and height of golden images, in pixels
+pub(super) const GOLDEN_SIZE: (u32, u32) = (256, 256);
+
+/// The seed that golden scenes are simulated with, unless the configuration sets its own
+const GOLDEN_SEED: u64 = 0;
+
+#[derive(Debug)]
+/// Errors that may arise from rendering or comparing golden images
+pub(super) enum GoldenError {
+    /// No adapter could be found to render with
+    Adapter,
+    /// A device could not be retrieved from the adapter
+    Device(wgpu::RequestDeviceError),
+    /// The scene could not be rendered offscreen
+    Render(OffscreenError),
+    /// The stored golden image could not be decoded
+    Decode(png::DecodingError),
+    /// The images being compared have different numbers of bytes
+    SizeMismatch {
+        /// The number of bytes in the rendered image
+        actual: usize,
+        /// The number of bytes in the golden image
+        expected: usize,
+    },
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Adapter => f.write_str("no adapter to render with"),
+            Self::Device(ref err) => write!(f, "failed to request a device: {err}"),
+            Self::Render(ref err) => write!(f, "failed to render the scene: {err}"),
+            Self::Decode(ref err) => write!(f, "failed to decode the golden image: {err}"),
+            Self::SizeMismatch { actual, expected } => write!(
+                f,
+                "the rendered image has {actual} bytes, but the golden image has {expected}"
+            ),
+        }
+    }
+}
+
+impl Error for GoldenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            Self::Device(ref err) => Some(err),
+            Self::Render(ref err) => Some(err),
+            Self::Decode(ref err) => Some(err),
+            Self::Adapter | Self::SizeMismatch { .. } => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// How much two images differ, over every channel of every pixel
+pub(super) struct ImageDifference {
+    /// The largest difference of any channel
+    pub max: u8,
+    /// The average difference across all channels
+    pub mean: f64,
+}
+
+impl ImageDifference {
+    /// Returns whether no channel differs by more than the given tolerance
+    pub const fn within(self, tolerance: u8) -> bool {
+        self.max <= tolerance
+    }
+}
+
+/// Renders the configured scene offscreen after simulating it for `steps` fixed timesteps,
+/// returning its RGBA pixels row by row, starting from the top left. The scene is seeded with the
+/// configured seed, or a fixed one if there is none, and drawn without the HUD, so that rendering
+/// it again gives the same image
+pub(super) fn render_golden(config: &SimulationConfig, steps: u32) -> Result<Vec<u8>, GoldenError> {
+    let instance = wgpu::Instance::default();
+    let adapter = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+        .ok_or(GoldenError::Adapter)?;
+    let (device, queue) =
+        block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+            .map_err(GoldenError::Device)?;
+    let (width, height) = GOLDEN_SIZE;
+    let mut app = Application::new_offscreen(
+        device,
+        queue,
+        width,
+        height,
+        &SimulationConfig {
+            seed: Some(config.seed.unwrap_or(GOLDEN_SEED)),
+            ..*config
+        },
+    );
+    app.set_hud_visible(false);
+    for _ in 0..steps {
+        app.step_once();
+    }
+    app.render_offscreen().map_err(GoldenError::Render)
+}
+
+/// Decodes a golden image stored as an 8-bit RGBA PNG, such as one written by
+/// [`encode_png`](super::capture::encode_png), into its pixels
+pub(super) fn decode_golden(png_bytes: &[u8]) -> Result<Vec<u8>, GoldenError> {
+    let mut reader = png::Decoder::new(png_bytes)
+        .read_info()
+        .map_err(GoldenError::Decode)?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut pixels)
+        .map_err(GoldenError::Decode)?;
+    pixels.truncate(info.buffer_size());
+    Ok(pixels)
+}
+
+/// Compares rendered pixels against golden pixels, channel by channel
+#[expect(
+    clippy::as_conversions,
+    clippy::cast_precision_loss,
+    reason = "Image sizes are far below the precision of an `f64`"
+)]
+#[expect(clippy::float_arithmetic, reason = "The mean difference is a fraction")]
+pub(super) fn compare(actual: &[u8], expected: &[u8]) -> Result<ImageDifference, GoldenError> {
+    if actual.len() != expected.len() {
+        return Err(GoldenError::SizeMismatch {
+            actual: actual.len(),
+            expected: expected.len(),
+        });
+    }
+    let (max, total) = actual
+        .iter()
+        .zip(expected)
+        .map(|(&first, &second)| first.abs_diff(second))
+        .fold((0, 0_u64), |(max, total), difference| {
+            (
+                max.max(difference),
+                total.saturating_add(u64::from(difference)),
+            )
+        });
+    Ok(ImageDifference {
+        max,
+        mean: if actual.is_empty() {
+            0.0
+        } else {
+            total as f64 / actual.len() as f64
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, decode_golden, render_golden, GoldenError, GOLDEN_SIZE};
+    use crate::app::{capture, config::SimulationConfig};
+
+    #[test]
+    fn image_matches_itself() {
+        let image: Vec<u8> = (0..=255).collect();
+        let difference = compare(&image, &image).expect("images are the same size");
+        assert_eq!(difference.max, 0);
+        assert!(difference.mean.abs() < f64::EPSILON);
+        assert!(difference.within(0));
+    }
+
+    #[test]
+    fn difference_is_measured_per_channel() {
+        let difference = compare(&[0, 10, 20, 30], &[0, 10, 20, 34]).expect("same size");
+        assert_eq!(difference.max, 4);
+        assert!((difference.mean - 1.0).abs() < f64::EPSILON);
+        assert!(!difference.within(3));
+    }
+
+    #[test]
+    fn images_of_different_sizes_do_not_compare() {
+        assert!(matches!(
+            compare(&[0; 8], &[0; 4]),
+            Err(GoldenError::SizeMismatch {
+                actual: 8,
+                expected: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn stored_images_decode_to_their_pixels() {
+        let pixels = [255, 0, 0, 255, 0, 0, 255, 128];
+        let png = capture::encode_png(2, 1, &pixels).expect("pixels match the size");
+        assert_eq!(decode_golden(&png).expect("encoded as a PNG"), pixels);
+    }
+
+    #[test]
+    fn rendering_is_repeatable() {
+        let config = SimulationConfig {
+            particle_count: 16,
+            ..SimulationConfig::default()
+        };
+        let first = match render_golden(&config, 10) {
+            Ok(pixels) => pixels,
+            Err(GoldenError::Adapter) => {
+                eprintln!("No adapter to render with; skipping");
+                return;
+            }
+            Err(err) => panic!("failed to render: {err:?}"),
+        };
+        let (width, height) = GOLDEN_SIZE;
+        assert_eq!(
+            first.len(),
+            usize::try_from(width * height * 4).expect("fits")
+        );
+        let second = render_golden(&config, 10).expect("rendered once already");
+        assert!(compare(&first, &second).expect("same size").within(0));
+    }
+}
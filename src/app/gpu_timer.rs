@@ -0,0 +1,148 @@
+//! Measurement of how long the GPU spends on the render pass, using timestamp queries
+
+use core::{mem, time::Duration};
+use std::sync::mpsc;
+
+use log::error;
+
+/// The number of timestamps written per render pass: one at its beginning, and one at its end
+const TIMESTAMP_COUNT: u32 = 2;
+
+/// The query written at the beginning of the render pass
+const BEGINNING_INDEX: u32 = 0;
+
+/// The query written at the end of the render pass
+const END_INDEX: u32 = 1;
+
+/// The size of the buffers holding the resolved timestamps
+#[expect(
+    clippy::as_conversions,
+    reason = "`usize` to `u64` is lossless on all supported platforms"
+)]
+const TIMESTAMPS_SIZE: wgpu::BufferAddress =
+    TIMESTAMP_COUNT as wgpu::BufferAddress * mem::size_of::<u64>() as wgpu::BufferAddress;
+
+/// Records timestamps at the beginning and end of a render pass, and reads them back to measure
+/// its duration. Requires `wgpu::Features::TIMESTAMP_QUERY`
+pub(super) struct GpuTimer {
+    /// The queries the timestamps are written to
+    query_set: wgpu::QuerySet,
+    /// The buffer the queries are resolved into
+    resolve_buffer: wgpu::Buffer,
+    /// A mappable buffer that the resolved timestamps are copied into to be read back
+    readback_buffer: wgpu::Buffer,
+    /// The number of nanoseconds per timestamp tick
+    period: f32,
+    /// The duration of the most recently measured render pass
+    last: Option<Duration>,
+}
+
+impl GpuTimer {
+    /// Creates a timer for render passes submitted to the given queue
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Render Pass Timestamps"),
+            ty: wgpu::QueryType::Timestamp,
+            count: TIMESTAMP_COUNT,
+        });
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Timestamp Resolve Buffer"),
+            size: TIMESTAMPS_SIZE,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Timestamp Readback Buffer"),
+            size: TIMESTAMPS_SIZE,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            period: queue.get_timestamp_period(),
+            last: None,
+        }
+    }
+
+    /// Returns where a render pass should write its beginning and end timestamps
+    pub const fn timestamp_writes(&self) -> wgpu::RenderPassTimestampWrites<'_> {
+        wgpu::RenderPassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: Some(BEGINNING_INDEX),
+            end_of_pass_write_index: Some(END_INDEX),
+        }
+    }
+
+    /// Records commands copying the timestamps written by the render pass to be read back.
+    /// Must be recorded after the render pass, in the same submission
+    pub fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+        encoder.resolve_query_set(&self.query_set, 0..TIMESTAMP_COUNT, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.readback_buffer,
+            0,
+            TIMESTAMPS_SIZE,
+        );
+    }
+
+    /// Reads back the timestamps copied by a submitted `resolve`, blocking until they are
+    /// available, and records the duration between them
+    #[expect(
+        clippy::as_conversions,
+        clippy::cast_precision_loss,
+        reason = "Render passes are far shorter than the range where ticks lose precision"
+    )]
+    #[expect(
+        clippy::float_arithmetic,
+        reason = "Timestamp ticks are converted into time by the queue's floating-point period"
+    )]
+    pub fn read(&mut self, device: &wgpu::Device) {
+        let slice = self.readback_buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            if let Err(err) = sender.send(result) {
+                error!("Failed to report timestamp read back: {err}");
+            }
+        });
+        device.poll(wgpu::Maintain::Wait);
+        match receiver.try_recv() {
+            Ok(Ok(())) => {
+                let timestamps: Vec<u64> = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+                self.readback_buffer.unmap();
+                if let [begin, end] = timestamps[..] {
+                    let ticks = end.saturating_sub(begin) as f32;
+                    self.last = Some(Duration::from_secs_f32(ticks * self.period * 1e-9));
+                }
+            }
+            Ok(Err(err)) => error!("Failed to read back timestamps: {err:?}"),
+            Err(err) => error!("Timestamp read back was never completed: {err:?}"),
+        }
+    }
+
+    /// Returns the duration of the most recently measured render pass, if any
+    pub const fn last(&self) -> Option<Duration> {
+        self.last
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem;
+
+    use super::{BEGINNING_INDEX, END_INDEX, TIMESTAMPS_SIZE, TIMESTAMP_COUNT};
+
+    #[test]
+    fn query_set_holds_every_timestamp_written() {
+        let writes = [BEGINNING_INDEX, END_INDEX];
+        assert_eq!(usize::try_from(TIMESTAMP_COUNT), Ok(writes.len()));
+        assert!(writes.iter().all(|&index| index < TIMESTAMP_COUNT));
+        assert_ne!(BEGINNING_INDEX, END_INDEX);
+        assert_eq!(
+            usize::try_from(TIMESTAMPS_SIZE),
+            Ok(writes.len() * mem::size_of::<u64>())
+        );
+    }
+}
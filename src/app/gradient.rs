@@ -0,0 +1,128 @@
+//! A background that blends vertically between two colors, drawn instead of a flat clear
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt as _;
+
+use super::color::color_components;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+/// The colors blended between, matching `Gradient` in the gradient shader
+struct GradientUniform {
+    /// The color at the top of the screen
+    top: [f32; 4],
+    /// The color at the bottom of the screen
+    bottom: [f32; 4],
+}
+
+/// The pipeline and colors of a vertical gradient drawn behind everything else
+pub(super) struct Gradient {
+    /// The color at the top of the screen
+    top: wgpu::Color,
+    /// The color at the bottom of the screen
+    bottom: wgpu::Color,
+    /// Draws a full-screen triangle shaded by the gradient
+    pipeline: wgpu::RenderPipeline,
+    /// Uniform buffer holding the `GradientUniform`
+    buffer: wgpu::Buffer,
+    /// Binds `buffer` to the pipeline
+    bind_group: wgpu::BindGroup,
+}
+
+impl Gradient {
+    /// Creates a gradient between the given colors, drawn within a pass with the given color
+    /// format, number of samples, and depth format
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        depth_format: wgpu::TextureFormat,
+        top: wgpu::Color,
+        bottom: wgpu::Color,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("gradient.wgsl"));
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Gradient Pipeline"),
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            // The gradient lies behind everything drawn after it
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: depth_format,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..wgpu::MultisampleState::default()
+            },
+            multiview: None,
+        });
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Gradient Buffer"),
+            contents: bytemuck::bytes_of(&GradientUniform {
+                top: color_components(top),
+                bottom: color_components(bottom),
+            }),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Gradient Bind Group"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        Self {
+            top,
+            bottom,
+            pipeline,
+            buffer,
+            bind_group,
+        }
+    }
+
+    /// Returns the colors at the top and bottom of the screen
+    pub const fn colors(&self) -> (wgpu::Color, wgpu::Color) {
+        (self.top, self.bottom)
+    }
+
+    /// Sets the colors at the top and bottom of the screen, in linear space
+    pub fn set_colors(&mut self, queue: &wgpu::Queue, top: wgpu::Color, bottom: wgpu::Color) {
+        self.top = top;
+        self.bottom = bottom;
+        queue.write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::bytes_of(&GradientUniform {
+                top: color_components(top),
+                bottom: color_components(bottom),
+            }),
+        );
+    }
+
+    /// Covers the screen with the gradient. Must be drawn first in the render pass
+    pub fn draw<'pass>(&'pass self, render_pass: &mut wgpu::RenderPass<'pass>) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,32 @@
+// Shader for drawing the background as a vertical gradient
+
+struct Gradient {
+    // The color at the top of the screen
+    top: vec4<f32>,
+    // The color at the bottom of the screen
+    bottom: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> gradient: Gradient;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // How far down the screen the vertex is, from 0 at the top to 1 at the bottom
+    @location(0) height: f32,
+};
+
+// Covers the screen with a single triangle, from three vertices without any buffers.
+// The triangle is defined in clip space, so it covers the screen whatever its size
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.height = uv.y;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return mix(gradient.top, gradient.bottom, in.height);
+}
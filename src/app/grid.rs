@@ -0,0 +1,109 @@
+//! A uniform spatial hash grid for finding nearby particles
+
+#![expect(
+    clippy::float_arithmetic,
+    reason = "Points are hashed into cells from their continuous world coordinates"
+)]
+
+use std::collections::HashMap;
+
+/// The index and position of every point in a single cell
+type Bucket = Vec<(usize, [f32; 2])>;
+
+/// Buckets points into equally-sized square cells, so that points near a location can be found
+/// without checking every point
+pub(super) struct SpatialGrid {
+    /// The side length of each cell
+    cell_size: f32,
+    /// The position of every point, in index order
+    points: Vec<[f32; 2]>,
+    /// The points in each cell, keyed by the cell's coordinates
+    cells: HashMap<(i32, i32), Bucket>,
+}
+
+impl SpatialGrid {
+    /// Creates an empty grid with cells of the given side length
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "Cell size should be positive");
+        Self {
+            cell_size,
+            points: Vec::new(),
+            cells: HashMap::new(),
+        }
+    }
+
+    /// Returns the coordinates of the cell containing the given position
+    #[expect(
+        clippy::as_conversions,
+        clippy::cast_possible_truncation,
+        reason = "Cell coordinates of positions within the simulation fit easily in an `i32`"
+    )]
+    fn cell(&self, position: [f32; 2]) -> (i32, i32) {
+        (
+            (position[0] / self.cell_size).floor() as i32,
+            (position[1] / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Replaces the contents of the grid with the given positions, identified by their index
+    pub fn rebuild<I>(&mut self, positions: I)
+    where
+        I: IntoIterator<Item = [f32; 2]>,
+    {
+        self.points.clear();
+        self.points.extend(positions);
+        self.cells.clear();
+        for (index, &position) in self.points.iter().enumerate() {
+            self.cells
+                .entry(self.cell(position))
+                .or_default()
+                .push((index, position));
+        }
+    }
+
+    /// Returns the indices of every point within `radius` of `position`, inclusive.
+    /// Every cell overlapping the query's bounding box is searched, so points on or across cell
+    /// boundaries are always found
+    pub fn neighbors(&self, position: [f32; 2], radius: f32) -> impl Iterator<Item = usize> + '_ {
+        let (min_x, min_y) = self.cell([position[0] - radius, position[1] - radius]);
+        let (max_x, max_y) = self.cell([position[0] + radius, position[1] + radius]);
+        (min_x..=max_x)
+            .flat_map(move |x| (min_y..=max_y).map(move |y| (x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .filter(move |&&(_, other)| distance_squared(position, other) <= radius * radius)
+            .map(|&(index, _)| index)
+    }
+
+    /// Returns every pair of distinct points within `radius` of each other, as `(lower, higher)`
+    /// indices. Pairs are ordered by their lower index, so the result is deterministic
+    pub fn pairs(&self, radius: f32) -> Vec<(usize, usize)> {
+        self.points
+            .iter()
+            .enumerate()
+            .flat_map(|(index, &position)| {
+                self.neighbors(position, radius)
+                    .filter(move |&neighbor| neighbor > index)
+                    .map(move |neighbor| (index, neighbor))
+            })
+            .collect()
+    }
+}
+
+/// Returns the squared Euclidean distance between two points
+pub(super) fn distance_squared(first: [f32; 2], second: [f32; 2]) -> f32 {
+    let (dx, dy) = (second[0] - first[0], second[1] - first[1]);
+    dx * dx + dy * dy
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpatialGrid;
+
+    #[test]
+    fn neighbors_are_found_across_cells() {
+        let mut grid = SpatialGrid::new(1.0);
+        grid.rebuild([[0.95, 0.5], [1.05, 0.5], [1.0, 0.5], [3.0, 0.5]]);
+        assert_eq!(grid.pairs(0.2), [(0, 1), (0, 2), (1, 2)]);
+    }
+}
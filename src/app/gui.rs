@@ -0,0 +1,304 @@
+//! An interactive control panel, drawn with egui over the simulation
+
+use core::{iter, mem};
+
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+
+use super::spawn::SpawnPattern;
+
+/// The diameter in pixels that particles are drawn at when a fixed size is first chosen
+const DEFAULT_POINT_SIZE: f32 = 4.0;
+
+/// The range of radii, in meters, that spawned particles may be given
+const RADII: (f32, f32) = (0.002, 0.05);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// The settings adjustable from the control panel
+pub(super) struct Controls {
+    /// The vertical acceleration applied to every particle
+    pub gravity: f32,
+    /// The fraction of vertical speed kept when bouncing off the floor
+    pub restitution: f32,
+    /// The number of particles spawned by the particle simulation
+    pub particle_count: usize,
+    /// The most particles that may be spawned
+    pub max_particles: usize,
+    /// The rate per second at which air resistance slows every particle
+    pub damping: f32,
+    /// The most air resistance that may slow particles
+    pub max_damping: f32,
+    /// The color behind the simulation, in linear RGB
+    pub background: [f32; 3],
+    /// The Barnes–Hut opening angle for approximating gravitation between particles
+    pub theta: f32,
+    /// The colors at the top and bottom of the gradient background, in linear RGB
+    pub gradient: [[f32; 3]; 2],
+    /// Whether the background is drawn as the gradient rather than the flat color
+    pub gradient_enabled: bool,
+    /// The diameter in pixels that every particle is drawn at, or `None` to draw particles at
+    /// their own radius
+    pub point_size: Option<f32>,
+    /// The smallest and largest radii of spawned particles, in meters, in either order
+    pub radii: [f32; 2],
+    /// Whether the simulation is paused while the window is unfocused
+    pub pause_on_focus_loss: bool,
+    /// The number of frames that may be queued for presentation ahead of the display
+    pub frame_latency: u32,
+    /// The fewest and most frames that may be queued for presentation
+    pub frame_latency_range: (u32, u32),
+    /// Whether the window is only redrawn when something shown changes
+    pub redraw_on_demand: bool,
+    /// How the particles are arranged when the particle simulation is restarted
+    pub spawn_pattern: SpawnPattern,
+}
+
+impl Controls {
+    /// Shows the control panel, updating the settings as they are adjusted
+    fn show(&mut self, context: &egui::Context) {
+        egui::Window::new("Controls")
+            .default_pos([8.0, 48.0])
+            .show(context, |ui| {
+                ui.add(egui::Slider::new(&mut self.gravity, -5.0..=5.0).text("Gravity"));
+                ui.add(egui::Slider::new(&mut self.restitution, 0.0..=1.0).text("Restitution"));
+                ui.add(
+                    egui::Slider::new(&mut self.particle_count, 0..=self.max_particles)
+                        .text("Particles"),
+                );
+                ui.add(
+                    egui::Slider::new(&mut self.damping, 0.0..=self.max_damping)
+                        .text("Air resistance"),
+                );
+                ui.horizontal(|row| {
+                    row.color_edit_button_rgb(&mut self.background);
+                    row.label("Background");
+                });
+                ui.horizontal(|row| {
+                    let [ref mut top, ref mut bottom] = self.gradient;
+                    row.color_edit_button_rgb(top);
+                    row.color_edit_button_rgb(bottom);
+                    row.checkbox(&mut self.gradient_enabled, "Gradient");
+                });
+                ui.horizontal(|row| {
+                    let mut fixed = self.point_size.is_some();
+                    let mut pixels = self.point_size.unwrap_or(DEFAULT_POINT_SIZE);
+                    row.checkbox(&mut fixed, "");
+                    row.add_enabled(
+                        fixed,
+                        egui::Slider::new(&mut pixels, 1.0..=32.0).text("Point size"),
+                    );
+                    self.point_size = fixed.then_some(pixels);
+                });
+                ui.add(egui::Slider::new(&mut self.theta, 0.0..=1.5).text("Opening angle"));
+                let [ref mut smallest, ref mut largest] = self.radii;
+                ui.add(egui::Slider::new(smallest, RADII.0..=RADII.1).text("Smallest radius"));
+                ui.add(egui::Slider::new(largest, RADII.0..=RADII.1).text("Largest radius"));
+                ui.checkbox(&mut self.pause_on_focus_loss, "Pause when unfocused");
+                let (fewest, most) = self.frame_latency_range;
+                ui.add(
+                    egui::Slider::new(&mut self.frame_latency, fewest..=most).text("Frame latency"),
+                );
+                ui.checkbox(&mut self.redraw_on_demand, "Redraw only on change");
+                egui::ComboBox::from_label("Spawn pattern")
+                    .selected_text(format!("{:?}", self.spawn_pattern))
+                    .show_ui(ui, |menu| {
+                        for pattern in SpawnPattern::ALL {
+                            menu.selectable_value(
+                                &mut self.spawn_pattern,
+                                pattern,
+                                format!("{pattern:?}"),
+                            );
+                        }
+                    });
+            });
+    }
+}
+
+/// Converts a mouse button into the corresponding egui button, if there is one
+const fn pointer_button(button: MouseButton) -> Option<egui::PointerButton> {
+    match button {
+        MouseButton::Left => Some(egui::PointerButton::Primary),
+        MouseButton::Right => Some(egui::PointerButton::Secondary),
+        MouseButton::Middle => Some(egui::PointerButton::Middle),
+        MouseButton::Back => Some(egui::PointerButton::Extra1),
+        MouseButton::Forward => Some(egui::PointerButton::Extra2),
+        MouseButton::Other(_) => None,
+    }
+}
+
+/// Runs and draws an egui user interface over the simulation
+pub(super) struct Gui {
+    /// The egui state persisting across frames
+    context: egui::Context,
+    /// Uploads and draws what egui paints
+    renderer: egui_wgpu::Renderer,
+    /// The input received since the interface was last run
+    events: Vec<egui::Event>,
+    /// The last position of the pointer, in points
+    pointer: egui::Pos2,
+    /// The size and scale of the surface the interface was last laid out for
+    screen: egui_wgpu::ScreenDescriptor,
+    /// The shapes painted by the interface when it was last run
+    paint_jobs: Vec<egui::ClippedPrimitive>,
+    /// Changes to egui's textures that have not been uploaded yet
+    textures: egui::TexturesDelta,
+    /// Textures that were in use by the last drawn frame, to be freed once it is finished
+    to_free: Vec<egui::TextureId>,
+}
+
+impl Gui {
+    /// Creates an interface drawing into targets of the given format
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        Self {
+            context: egui::Context::default(),
+            // The interface is drawn in its own pass over the resolved frame, so it needs
+            // neither depth nor multisampling
+            renderer: egui_wgpu::Renderer::new(device, format, None, 1),
+            events: Vec::new(),
+            pointer: egui::Pos2::ZERO,
+            screen: egui_wgpu::ScreenDescriptor {
+                size_in_pixels: [0, 0],
+                pixels_per_point: 1.0,
+            },
+            paint_jobs: Vec::new(),
+            textures: egui::TexturesDelta::default(),
+            to_free: Vec::new(),
+        }
+    }
+
+    /// Feeds a window event into the interface, given the window's scale factor.
+    /// Returns `true` if the interface claims the event for itself, such as a click on the
+    /// control panel, in which case the simulation should ignore it
+    #[expect(
+        clippy::as_conversions,
+        clippy::cast_possible_truncation,
+        reason = "Pointer positions are well within `f32` precision"
+    )]
+    #[expect(
+        clippy::float_arithmetic,
+        reason = "Physical pixels are converted into logical points by the fractional scale factor"
+    )]
+    #[expect(
+        clippy::wildcard_enum_match_arm,
+        reason = "The interface does not use any other event, including any added later"
+    )]
+    pub fn on_window_event(&mut self, event: &WindowEvent, scale_factor: f64) -> bool {
+        let points = |pixels: f64| (pixels / scale_factor) as f32;
+        match *event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.pointer = egui::pos2(points(position.x), points(position.y));
+                self.events.push(egui::Event::PointerMoved(self.pointer));
+                // The simulation still tracks the cursor, for when it leaves the panel
+                false
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.events.push(egui::Event::PointerGone);
+                false
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                if let Some(egui_button) = pointer_button(button) {
+                    self.events.push(egui::Event::PointerButton {
+                        pos: self.pointer,
+                        button: egui_button,
+                        pressed: state == ElementState::Pressed,
+                        modifiers: egui::Modifiers::default(),
+                    });
+                }
+                // Releases always reach the simulation, so that drags begun outside of the panel
+                // still end
+                state == ElementState::Pressed && self.context.wants_pointer_input()
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let (unit, egui_delta) = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => {
+                        (egui::MouseWheelUnit::Line, egui::vec2(x, y))
+                    }
+                    MouseScrollDelta::PixelDelta(pixels) => (
+                        egui::MouseWheelUnit::Point,
+                        egui::vec2(points(pixels.x), points(pixels.y)),
+                    ),
+                };
+                self.events.push(egui::Event::MouseWheel {
+                    unit,
+                    delta: egui_delta,
+                    modifiers: egui::Modifiers::default(),
+                });
+                self.context.wants_pointer_input()
+            }
+            _ => false,
+        }
+    }
+
+    /// Runs the interface for a surface of the given size and scale factor, showing the given
+    /// controls and updating them with any adjustments
+    #[expect(
+        clippy::as_conversions,
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        reason = "Surface dimensions and scale factors are well within `f32` precision"
+    )]
+    pub fn run(&mut self, width: u32, height: u32, scale_factor: f64, controls: &mut Controls) {
+        let pixels_per_point = scale_factor as f32;
+        self.screen = egui_wgpu::ScreenDescriptor {
+            size_in_pixels: [width, height],
+            pixels_per_point,
+        };
+        self.context.set_pixels_per_point(pixels_per_point);
+        let input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(
+                egui::Pos2::ZERO,
+                egui::vec2(width as f32, height as f32) / pixels_per_point,
+            )),
+            events: mem::take(&mut self.events),
+            ..egui::RawInput::default()
+        };
+        let output = self.context.run(input, |context| controls.show(context));
+        self.textures.append(output.textures_delta);
+        self.paint_jobs = self
+            .context
+            .tessellate(output.shapes, output.pixels_per_point);
+    }
+
+    /// Uploads the textures and shapes painted by the last run of the interface through the given
+    /// queue, freeing textures that are no longer in use
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        for id in self.to_free.drain(..) {
+            self.renderer.free_texture(&id);
+        }
+        for (id, delta) in self.textures.set.drain(..) {
+            self.renderer.update_texture(device, queue, id, &delta);
+        }
+        self.to_free = mem::take(&mut self.textures.free);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("GUI Upload Encoder"),
+        });
+        let callbacks = self.renderer.update_buffers(
+            device,
+            queue,
+            &mut encoder,
+            &self.paint_jobs,
+            &self.screen,
+        );
+        queue.submit(callbacks.into_iter().chain(iter::once(encoder.finish())));
+    }
+
+    /// Draws the prepared interface in its own pass over the given view, keeping its contents
+    pub fn draw(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("GUI Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        self.renderer
+            .render(&mut render_pass, &self.paint_jobs, &self.screen);
+    }
+}
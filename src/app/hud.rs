@@ -0,0 +1,241 @@
+//! A heads-up display of statistics, drawn as text from a built-in bitmap font
+
+use core::mem;
+
+/// The width of every glyph in the font, in font pixels
+const GLYPH_WIDTH: usize = 3;
+
+/// The height of every glyph in the font, in font pixels
+const GLYPH_HEIGHT: usize = 5;
+
+/// The number of screen pixels along each side of a font pixel
+const SCALE: f32 = 3.0;
+
+/// The distance of the text from the top left corner of the screen, in screen pixels
+const MARGIN: f32 = 8.0;
+
+/// The most characters that can be drawn at once
+const MAX_CHARS: usize = 64;
+
+/// The number of vertices making up the quad of each font pixel
+const VERTICES_PER_PIXEL: usize = 6;
+
+/// The size of the vertex buffer, in bytes: enough for every font pixel of the longest line
+const VERTEX_BUFFER_SIZE: usize =
+    MAX_CHARS * GLYPH_WIDTH * GLYPH_HEIGHT * VERTICES_PER_PIXEL * mem::size_of::<[f32; 2]>();
+
+/// The attributes of a text vertex, matching the locations in the HUD shader
+const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![0 => Float32x2];
+
+/// Returns the rows of font pixels making up the glyph of the given character, from the top, with
+/// the leftmost pixel of each row in its most significant bit.
+/// Letters are drawn in uppercase, and unsupported characters are blank
+const fn glyph(character: char) -> [u8; GLYPH_HEIGHT] {
+    match character.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        _ => [0; GLYPH_HEIGHT],
+    }
+}
+
+/// Returns the text shown on the HUD for the given statistics
+pub(super) fn hud_text(fps: f32, particle_count: usize) -> String {
+    format!("FPS: {fps:.1}\nParticles: {particle_count}")
+}
+
+/// Returns the vertices of the triangles drawing the given text, in normalized device
+/// coordinates on a surface of the given size, starting from its top left corner
+#[expect(
+    clippy::as_conversions,
+    clippy::cast_precision_loss,
+    reason = "Text positions are small enough to be represented exactly"
+)]
+#[expect(
+    clippy::float_arithmetic,
+    reason = "Glyphs are laid out in continuous pixel and normalized device coordinates"
+)]
+#[expect(
+    clippy::arithmetic_side_effects,
+    reason = "Glyph positions are bounded by the length of the text"
+)]
+fn text_vertices(text: &str, width: u32, height: u32) -> Vec<[f32; 2]> {
+    let to_ndc = |x: f32, y: f32| [2.0 * x / width as f32 - 1.0, 1.0 - 2.0 * y / height as f32];
+    let mut vertices = Vec::new();
+    for (line_index, line) in text.lines().enumerate() {
+        for (char_index, character) in line.chars().take(MAX_CHARS).enumerate() {
+            for (row, bits) in glyph(character).into_iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                        continue;
+                    }
+                    // Glyphs are separated by a blank font pixel
+                    let left = MARGIN + ((char_index * (GLYPH_WIDTH + 1) + column) as f32) * SCALE;
+                    let top = MARGIN + ((line_index * (GLYPH_HEIGHT + 1) + row) as f32) * SCALE;
+                    let (right, bottom) = (left + SCALE, top + SCALE);
+                    vertices.extend([
+                        to_ndc(left, top),
+                        to_ndc(left, bottom),
+                        to_ndc(right, bottom),
+                        to_ndc(left, top),
+                        to_ndc(right, bottom),
+                        to_ndc(right, top),
+                    ]);
+                }
+            }
+        }
+    }
+    vertices.truncate(MAX_CHARS * GLYPH_WIDTH * GLYPH_HEIGHT * VERTICES_PER_PIXEL);
+    vertices
+}
+
+/// Draws text over the top left corner of the screen
+pub(super) struct Hud {
+    /// The pipeline drawing the text
+    pipeline: wgpu::RenderPipeline,
+    /// The triangles of the text, with room for `MAX_CHARS` characters
+    vertex_buffer: wgpu::Buffer,
+    /// The number of vertices in `vertex_buffer` making up the current text
+    vertex_count: u32,
+}
+
+impl Hud {
+    /// Creates a HUD drawing into targets of the given format, with no text
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("hud.wgsl"));
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("HUD Pipeline Layout"),
+            bind_group_layouts: &[],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("HUD Pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[wgpu::VertexBufferLayout {
+                    #[expect(
+                        clippy::as_conversions,
+                        reason = "`usize` to `u64` is lossless on all supported platforms"
+                    )]
+                    array_stride: mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &VERTEX_ATTRIBUTES,
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            // The HUD is drawn in its own pass over the resolved frame, so it needs neither depth
+            // nor multisampling
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        #[expect(
+            clippy::as_conversions,
+            reason = "`usize` to `u64` is lossless on all supported platforms"
+        )]
+        let size = VERTEX_BUFFER_SIZE as u64;
+        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("HUD Vertex Buffer"),
+            size,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self {
+            pipeline,
+            vertex_buffer,
+            vertex_count: 0,
+        }
+    }
+
+    /// Lays out the given text for a surface of the given size, replacing the previous text
+    #[expect(
+        clippy::expect_used,
+        reason = "The vertices fit in the vertex buffer, far fewer than `u32::MAX`"
+    )]
+    pub fn set_text(&mut self, queue: &wgpu::Queue, text: &str, width: u32, height: u32) {
+        let vertices = text_vertices(text, width, height);
+        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
+        self.vertex_count =
+            u32::try_from(vertices.len()).expect("Vertex count should fit in a `u32`");
+    }
+
+    /// Draws the current text in its own pass over the given view, keeping its contents
+    pub fn draw(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("HUD Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.draw(0..self.vertex_count, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::hud_text;
+
+    #[test]
+    fn hud_shows_the_particle_count() {
+        assert!(hud_text(60.0, 1234).contains("Particles: 1234"));
+    }
+}
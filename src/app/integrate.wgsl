@@ -0,0 +1,86 @@
+// Compute shader integrating every particle by one timestep, from one buffer into another so that
+// every read sees the previous step's state
+
+struct Particle {
+    position: vec2<f32>,
+    velocity: vec2<f32>,
+    radius: f32,
+    mass: f32,
+};
+
+struct Params {
+    dt: f32,
+    gravity: f32,
+    restitution: f32,
+    floor: f32,
+    count: u32,
+    left: f32,
+    right: f32,
+    ceiling: f32,
+    field_center: vec2<f32>,
+    field_strength: f32,
+    boundary: u32,
+    damping: f32,
+    restitution_slope: f32,
+};
+
+// Values of `Params::boundary`, matching `BoundaryMode::shader_value`
+const BOUNDARY_BOUNCE: u32 = 0u;
+const BOUNDARY_WRAP: u32 = 1u;
+
+// Must match `ForceField::MIN_DISTANCE_SQUARED`
+const MIN_DISTANCE_SQUARED: f32 = 0.01;
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> source: array<Particle>;
+@group(0) @binding(2) var<storage, read_write> destination: array<Particle>;
+
+// The fraction of its speed that a particle hitting at the given velocity retains, matching
+// `RestitutionModel::at`
+fn restitution_at(velocity: f32) -> f32 {
+    return clamp(params.restitution - params.restitution_slope * abs(velocity), 0.0, 1.0);
+}
+
+// `WORKGROUP_SIZE` is prepended to this shader when it is built
+@compute @workgroup_size(WORKGROUP_SIZE)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if index >= params.count {
+        return;
+    }
+    var particle = source[index];
+    var acceleration = vec2<f32>(0.0, params.gravity);
+    let offset = particle.position - params.field_center;
+    let distance_squared = dot(offset, offset);
+    // The field applies a force, whereas gravity accelerates every particle equally
+    if distance_squared > 0.0 {
+        acceleration += normalize(offset) * params.field_strength
+            / (max(distance_squared, MIN_DISTANCE_SQUARED) * particle.mass);
+    }
+    particle.velocity = particle.velocity * params.damping + acceleration * params.dt;
+    particle.position += particle.velocity * params.dt;
+    let lower = vec2<f32>(params.left, params.floor);
+    let upper = vec2<f32>(params.right, params.ceiling);
+    for (var axis = 0; axis < 2; axis++) {
+        if params.boundary == BOUNDARY_BOUNCE {
+            if particle.position[axis] < lower[axis] {
+                particle.position[axis] = lower[axis];
+                if particle.velocity[axis] < 0.0 {
+                    particle.velocity[axis] = -particle.velocity[axis] * restitution_at(particle.velocity[axis]);
+                }
+            } else if particle.position[axis] > upper[axis] {
+                particle.position[axis] = upper[axis];
+                if particle.velocity[axis] > 0.0 {
+                    particle.velocity[axis] = -particle.velocity[axis] * restitution_at(particle.velocity[axis]);
+                }
+            }
+        } else if params.boundary == BOUNDARY_WRAP {
+            let extent = upper[axis] - lower[axis];
+            if extent > 0.0 {
+                let offset = particle.position[axis] - lower[axis];
+                particle.position[axis] = lower[axis] + offset - extent * floor(offset / extent);
+            }
+        }
+    }
+    destination[index] = particle;
+}
@@ -0,0 +1,125 @@
+//! The keys that control the application, and the action each is bound to
+//!
+//! The camera's movement keys are handled by the camera controller before these bindings apply
+
+use winit::keyboard::KeyCode;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Something the application does when a key is pressed
+pub(super) enum Action {
+    /// Pauses or resumes the simulation
+    TogglePause,
+    /// Advances the simulation by a single step
+    StepOnce,
+    /// Switches to the next way of handling the edges of the world
+    CycleBoundary,
+    /// Turns gravitation between particles on or off
+    ToggleGravitation,
+    /// Halves how fast the simulation runs
+    SlowDown,
+    /// Doubles how fast the simulation runs
+    SpeedUp,
+    /// Switches between the flat and perspective views
+    ToggleCameraMode,
+    /// Returns the flat view to its initial zoom, centered on the origin
+    ResetView,
+    /// Shows or hides the HUD
+    ToggleHud,
+    /// Turns vertical sync on or off
+    ToggleVsync,
+    /// Switches between coloring particles flatly and by speed
+    ToggleVelocityColor,
+    /// Switches between the gradient and the flat background
+    ToggleGradient,
+    /// Shows or hides the outline of the world's bounds
+    ToggleBounds,
+    /// Switches to the next supported level of anti-aliasing
+    CycleMsaa,
+    /// Switches to the next visual theme
+    CycleTheme,
+    /// Switches how particles are drawn
+    ToggleParticleStyle,
+    /// Turns motion trails on or off
+    ToggleTrails,
+    /// Adds a batch of particles
+    AddParticles,
+    /// Removes a batch of particles
+    RemoveParticles,
+    /// Resets the simulation to its initial state
+    Reset,
+    /// Switches to a simulation of free particles
+    ParticleSim,
+    /// Switches to a simulation of cloth
+    ClothSim,
+    /// Switches to a simulation of fluid
+    FluidSim,
+    /// Switches to the other integrator
+    ToggleIntegrator,
+    /// Stops every emitter, until the simulation is reset
+    ClearEmitters,
+    /// Removes every obstacle from the world
+    ClearColliders,
+    /// Starts recording input, or stops and keeps the recording if already recording
+    ToggleRecording,
+    /// Replays the last recording
+    Replay,
+    /// Keeps a snapshot of the current state of the simulation
+    SaveState,
+    /// Restores the last snapshot kept
+    LoadState,
+    /// Saves a screenshot of the current frame
+    #[cfg(not(target_arch = "wasm32"))]
+    Screenshot,
+}
+
+impl Action {
+    /// Returns whether performing this action is recorded as input, so that it is repeated when
+    /// replaying. Controlling recording and replaying is not itself recorded, and neither are
+    /// snapshots, which replays do not restart with
+    pub const fn is_recorded(self) -> bool {
+        !matches!(
+            self,
+            Self::ToggleRecording | Self::Replay | Self::SaveState | Self::LoadState
+        )
+    }
+}
+
+/// Returns the action the given key is bound to, if any
+pub(super) const fn action(key: KeyCode) -> Option<Action> {
+    Some(match key {
+        KeyCode::Space => Action::TogglePause,
+        KeyCode::Period => Action::StepOnce,
+        KeyCode::KeyB => Action::CycleBoundary,
+        KeyCode::KeyN => Action::ToggleGravitation,
+        KeyCode::BracketLeft => Action::SlowDown,
+        KeyCode::BracketRight => Action::SpeedUp,
+        KeyCode::KeyC => Action::ToggleCameraMode,
+        KeyCode::Home => Action::ResetView,
+        KeyCode::KeyF => Action::ToggleHud,
+        KeyCode::KeyY => Action::ToggleVsync,
+        KeyCode::KeyG => Action::ToggleVelocityColor,
+        KeyCode::KeyH => Action::ToggleGradient,
+        KeyCode::KeyO => Action::ToggleBounds,
+        KeyCode::KeyM => Action::CycleMsaa,
+        KeyCode::Tab => Action::CycleTheme,
+        KeyCode::KeyP => Action::ToggleParticleStyle,
+        KeyCode::KeyT => Action::ToggleTrails,
+        KeyCode::Equal | KeyCode::NumpadAdd => Action::AddParticles,
+        KeyCode::Minus | KeyCode::NumpadSubtract => Action::RemoveParticles,
+        KeyCode::KeyR => Action::Reset,
+        KeyCode::Digit1 => Action::ParticleSim,
+        KeyCode::Digit2 => Action::ClothSim,
+        KeyCode::Digit3 => Action::FluidSim,
+        KeyCode::KeyV => Action::ToggleIntegrator,
+        KeyCode::Backspace => Action::ClearEmitters,
+        KeyCode::Delete => Action::ClearColliders,
+        KeyCode::F5 => Action::ToggleRecording,
+        KeyCode::F6 => Action::Replay,
+        KeyCode::F9 => Action::SaveState,
+        KeyCode::F10 => Action::LoadState,
+        // S moves the camera, so screenshots use the conventional F12 instead
+        #[cfg(not(target_arch = "wasm32"))]
+        KeyCode::F12 => Action::Screenshot,
+        _ => return None,
+    })
+}
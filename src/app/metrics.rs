@@ -0,0 +1,21 @@
+//! Performance measurements of the most recent frame, gathered in one place for display and for
+//! automated performance checks
+
+use core::time::Duration;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+/// How long the most recent frame took to simulate and render, and what it drew
+pub(super) struct Metrics {
+    /// The CPU time spent stepping the simulation during the most recent update, across all of
+    /// its steps
+    pub cpu_step: Duration,
+    /// The CPU time spent encoding and submitting the most recent frame's rendering commands
+    pub cpu_render_encode: Duration,
+    /// How long the GPU spent on the most recent render pass, or `None` if this is unsupported or
+    /// not yet known
+    pub gpu_frame: Option<Duration>,
+    /// The average frame rate over recent frames
+    pub fps: f32,
+    /// The number of particles simulated
+    pub particles: usize,
+}
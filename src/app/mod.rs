@@ -2,108 +2,606 @@
 //!
 //! This consists of the body of the event loop as well as managing all the state regarding the whole application
 
+#[expect(
+    clippy::module_inception,
+    reason = "The application itself lives alongside the modules it is built from"
+)]
 mod app;
-use std::rc::Rc;
+mod bounds;
+mod builder;
+mod camera;
+#[cfg(not(target_arch = "wasm32"))]
+mod capture;
+mod cloth;
+mod collider;
+mod color;
+mod coloring;
+#[cfg(not(target_arch = "wasm32"))]
+mod compute;
+mod config;
+mod device;
+mod diagnostics;
+mod disc;
+mod emitter;
+mod frame_timer;
+mod gamma;
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod golden;
+mod gpu_timer;
+mod gradient;
+mod grid;
+mod gui;
+mod hud;
+mod keys;
+mod metrics;
+mod particles;
+mod physics;
+mod pointer_lock;
+mod present;
+mod quadtree;
+#[cfg(any(test, not(target_arch = "wasm32")))]
+mod readback;
+mod recording;
+#[cfg(all(not(target_arch = "wasm32"), debug_assertions))]
+mod shader_watch;
+mod simulation;
+mod spawn;
+mod sph;
+mod stage;
+mod state;
+mod theme;
+mod trails;
+use core::{error::Error, fmt};
+use std::{collections::HashMap, sync::Arc};
 
-use self::app::Application;
-use log::{error, info, warn};
+use self::{app::Application, builder::validate, diagnostics::DiagnosticsWindow};
+pub use self::{
+    builder::{BuilderError, PhysicalSimulation, PhysicalSimulationBuilder},
+    collider::Collider,
+    config::{RedrawPolicy, SimulationConfig, WindowIcon},
+    emitter::Emitter,
+    particles::RestitutionModel,
+    present::PresentPreference,
+    spawn::SpawnPattern,
+};
+use log::{error, info, trace, warn};
 use pollster::block_on;
 use wgpu::SurfaceError;
+#[cfg(not(target_arch = "wasm32"))]
+use winit::dpi;
+#[cfg(target_arch = "wasm32")]
+use winit::platform::web::WindowAttributesExtWebSys as _;
 use winit::{
     application::ApplicationHandler,
-    event::{self, WindowEvent},
-    event_loop,
-    platform::web::WindowAttributesExtWebSys,
+    error::{EventLoopError, OsError},
+    event::{self, ElementState, KeyEvent, WindowEvent},
+    event_loop::{self, EventLoop},
+    keyboard::{KeyCode, PhysicalKey},
     window::{self, WindowAttributes},
 };
 
+#[derive(Debug)]
+/// Errors that may arise from running the simulation
+#[non_exhaustive]
+pub enum RunError {
+    /// The configuration cannot start a simulation
+    InvalidConfig(BuilderError),
+    /// The event loop could not be created or failed while running
+    EventLoop(EventLoopError),
+}
+
+impl fmt::Display for RunError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::InvalidConfig(ref err) => write!(f, "invalid configuration: {err}"),
+            Self::EventLoop(ref err) => write!(f, "event loop failed: {err}"),
+        }
+    }
+}
+
+impl Error for RunError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            Self::InvalidConfig(ref err) => Some(err),
+            Self::EventLoop(ref err) => Some(err),
+        }
+    }
+}
+
+/// Runs the simulation as configured, in a new window on desktop platforms or in the page's
+/// canvas on the web. Natively, this returns once the window is closed; on the web, it returns as
+/// soon as the event loop has been handed to the browser
+///
+/// # Errors
+/// Returns an error if the configuration is invalid, before any event loop is created, or if
+/// creating or running the event loop fails
+#[inline]
+pub fn run(config: SimulationConfig) -> Result<(), RunError> {
+    validate(&config).map_err(RunError::InvalidConfig)?;
+    let event_loop = EventLoop::new().map_err(RunError::EventLoop)?;
+    #[cfg(target_arch = "wasm32")]
+    {
+        use winit::platform::web::EventLoopExtWebSys as _;
+        event_loop.spawn_app(AppWrapper::with_config(config));
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    event_loop
+        .run_app(&mut AppWrapper::with_config(config))
+        .map_err(RunError::EventLoop)?;
+    Ok(())
+}
+
+#[derive(Debug)]
+/// Errors that may arise from creating the window to render to
+enum WindowInitError {
+    /// The page has no canvas element with the given id to render to
+    #[cfg_attr(
+        not(any(target_arch = "wasm32", test)),
+        expect(dead_code, reason = "Only pages on the web have canvases to look up")
+    )]
+    MissingCanvas(String),
+    /// The platform failed to create the window
+    Os(OsError),
+}
+
+impl fmt::Display for WindowInitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::MissingCanvas(ref id) => write!(f, "the page has no canvas with the id `{id}`"),
+            Self::Os(ref err) => write!(f, "the platform failed to create it: {err}"),
+        }
+    }
+}
+
+impl Error for WindowInitError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            Self::MissingCanvas(_) => None,
+            Self::Os(ref err) => Some(err),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// How to recover from failing to acquire a frame to render into
+enum FrameRecovery {
+    /// Reconfigure the surface, which no longer matches the window, and render the next frame
+    Reconfigure,
+    /// Drop this frame and try again with the next one
+    Skip,
+    /// Give up, as rendering cannot continue
+    Exit,
+}
+
+impl FrameRecovery {
+    /// Returns how to recover from the given error
+    const fn for_error(err: &SurfaceError) -> Self {
+        match *err {
+            SurfaceError::Lost | SurfaceError::Outdated => Self::Reconfigure,
+            SurfaceError::Timeout => Self::Skip,
+            SurfaceError::OutOfMemory => Self::Exit,
+        }
+    }
+}
+
 /// A wrapper around the application state to handle creation/destruction of windows.
 /// Manages and dispatches events.
+#[expect(
+    clippy::partial_pub_fields,
+    reason = "The configuration is public to be adjusted before running, unlike the running state"
+)]
 pub struct AppWrapper<'app> {
     /// The application. `None` if the application has not yet been initialized
     app: Option<Application<'app>>,
+    /// Secondary windows showing statistics, sharing the application's device, keyed by the id
+    /// of their window
+    diagnostics: HashMap<window::WindowId, DiagnosticsWindow>,
+    /// How the simulation starts
+    pub config: SimulationConfig,
+    /// Whether to prefer a high-performance or a low-power GPU
+    pub power_preference: wgpu::PowerPreference,
+    /// The initial inner size of the window created on desktop platforms
+    #[cfg(not(target_arch = "wasm32"))]
+    pub inner_size: dpi::Size,
+    /// The id of the canvas element rendered to on the web. Ignored on desktop platforms, which
+    /// create their own window
+    pub canvas_id: String,
+}
+
+/// Advances and renders the next frame of the application, recovering from failure to acquire
+/// the frame where possible, and then requests the frame after
+fn redraw(app: &mut Application<'_>, event_loop: &event_loop::ActiveEventLoop) {
+    app.update();
+    match app.render() {
+        Ok(()) => {}
+        Err(err) => match FrameRecovery::for_error(&err) {
+            FrameRecovery::Reconfigure => {
+                if let Some(size) = app.window().map(window::Window::inner_size) {
+                    if let Err(resize_err) = app.resize(size) {
+                        info!("Not reconfiguring the surface to {size:?}: {resize_err}");
+                    }
+                }
+            }
+            // Timeouts can recur every frame, so they are not worth reporting
+            FrameRecovery::Skip => {}
+            FrameRecovery::Exit => {
+                error!("Failed to acquire a frame: {err:?}; exiting");
+                event_loop.exit();
+                return;
+            }
+        },
+    }
+    app.schedule_redraw();
+}
+
+/// Returns the canvas that the lookup finds with the given id, such as by searching the page, or
+/// an error naming the id if there is none
+#[cfg_attr(
+    not(any(target_arch = "wasm32", test)),
+    expect(dead_code, reason = "Only pages on the web have canvases to look up")
+)]
+fn find_canvas<C, L>(id: &str, lookup: L) -> Result<C, WindowInitError>
+where
+    L: FnOnce(&str) -> Option<C>,
+{
+    lookup(id).ok_or_else(|| WindowInitError::MissingCanvas(id.to_owned()))
+}
+
+/// Converts a configured icon into one for the window, or `None`, logging an error, if its pixels
+/// do not match its size
+#[cfg(not(target_arch = "wasm32"))]
+fn window_icon(icon: WindowIcon) -> Option<window::Icon> {
+    window::Icon::from_rgba(icon.rgba.to_vec(), icon.width, icon.height)
+        .inspect_err(|err| error!("Skipping the invalid window icon: {err}"))
+        .ok()
+}
+
+/// Opens a new diagnostics window for the given application, titled after its main window.
+/// Returns `None`, after logging why, if the window could not be opened
+#[cfg(not(target_arch = "wasm32"))]
+fn open_diagnostics(
+    event_loop: &event_loop::ActiveEventLoop,
+    app: &Application<'_>,
+    title: &str,
+) -> Option<DiagnosticsWindow> {
+    let attributes = WindowAttributes::default()
+        .with_title(format!("{title} Diagnostics"))
+        .with_inner_size(dpi::LogicalSize::new(320.0_f64, 180.0_f64));
+    let window = event_loop
+        .create_window(attributes)
+        .inspect_err(|err| error!("Failed to create a diagnostics window: {err:?}"))
+        .ok()?;
+    let diagnostics = app
+        .open_diagnostics(Arc::new(window))
+        .inspect_err(|err| error!("Failed to open a diagnostics window: {err}"))
+        .ok()?;
+    diagnostics.window().request_redraw();
+    Some(diagnostics)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// The window that an event is dispatched to
+enum EventTarget {
+    /// The main window, showing the simulation
+    Main,
+    /// The diagnostics window with the given id
+    Diagnostics(window::WindowId),
+    /// No open window, such as one that has already been closed
+    Ignored,
+}
+
+/// Returns which window an event from the window with the given id is dispatched to: the main
+/// window, with the given id if there is one, or one of the given diagnostics windows
+fn event_target<W>(
+    main: Option<window::WindowId>,
+    diagnostics: &HashMap<window::WindowId, W>,
+    window_id: window::WindowId,
+) -> EventTarget {
+    if main == Some(window_id) {
+        EventTarget::Main
+    } else if diagnostics.contains_key(&window_id) {
+        EventTarget::Diagnostics(window_id)
+    } else {
+        EventTarget::Ignored
+    }
+}
+
+/// Removes the diagnostics window with the given id if the event closes it, leaving the others
+/// and the simulation running. Returns whether the window was closed
+fn close_diagnostics<W>(
+    diagnostics: &mut HashMap<window::WindowId, W>,
+    window_id: window::WindowId,
+    event: &WindowEvent,
+) -> bool {
+    matches!(*event, WindowEvent::CloseRequested) && diagnostics.remove(&window_id).is_some()
+}
+
+/// Handles an event for one of the diagnostics windows, drawing the statistics of the given
+/// application into it
+#[expect(
+    clippy::wildcard_enum_match_arm,
+    reason = "Diagnostics windows ignore every other event, including any added later"
+)]
+fn diagnostics_event(
+    diagnostics: &mut HashMap<window::WindowId, DiagnosticsWindow>,
+    app: &Application<'_>,
+    window_id: window::WindowId,
+    event: WindowEvent,
+) {
+    if close_diagnostics(diagnostics, window_id, &event) {
+        return;
+    }
+    let Some(window) = diagnostics.get_mut(&window_id) else {
+        trace!("Received window event {event:?} from unknown window {window_id:?}");
+        return;
+    };
+    match event {
+        WindowEvent::RedrawRequested => {
+            if let Err(err) = app.render_diagnostics(window) {
+                match FrameRecovery::for_error(&err) {
+                    FrameRecovery::Reconfigure => window.reconfigure(app.device()),
+                    FrameRecovery::Skip => {}
+                    // Only the diagnostics are lost, so the simulation carries on without them
+                    FrameRecovery::Exit => {
+                        error!("Failed to acquire a diagnostics frame: {err:?}; closing it");
+                        diagnostics.remove(&window_id);
+                        return;
+                    }
+                }
+            }
+            window.window().request_redraw();
+        }
+        WindowEvent::Resized(physical_size) => window.resize(app.device(), physical_size),
+        other => trace!("Received diagnostics window event {other:?}"),
+    }
 }
 
 impl AppWrapper<'_> {
-    /// Creates a new, uninitialized application
-    pub const fn new() -> Self {
-        Self { app: None }
+    /// Creates a new, uninitialized application, which starts the simulation with the default
+    /// configuration
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_config(SimulationConfig::default())
+    }
+
+    /// Creates a new, uninitialized application, which starts the simulation as configured
+    #[inline]
+    #[must_use]
+    pub fn with_config(config: SimulationConfig) -> Self {
+        Self {
+            app: None,
+            diagnostics: HashMap::new(),
+            config,
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            #[cfg(not(target_arch = "wasm32"))]
+            inner_size: dpi::LogicalSize::new(1280.0, 720.0).into(),
+            canvas_id: "canvas".to_owned(),
+        }
+    }
+
+    /// Creates a new, uninitialized application that renders to the canvas element with the given
+    /// id on the web
+    #[inline]
+    #[must_use]
+    pub fn with_canvas_id<S>(id: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            canvas_id: id.into(),
+            ..Self::new()
+        }
+    }
+
+    /// Creates the window to render to: the page's canvas on the web, or a new window on desktop
+    #[cfg_attr(
+        target_arch = "wasm32",
+        expect(
+            clippy::expect_used,
+            reason = "The page is always loaded by the time the application is resumed"
+        )
+    )]
+    fn create_window(
+        &self,
+        event_loop: &event_loop::ActiveEventLoop,
+    ) -> Result<window::Window, WindowInitError> {
+        #[cfg(target_arch = "wasm32")]
+        let attributes = {
+            let document = web_sys::window()
+                .expect("Window should be loaded")
+                .document()
+                .expect("Document should be loaded");
+            let canvas = find_canvas(&self.canvas_id, |id| {
+                use wasm_bindgen::JsCast as _;
+                document
+                    .get_element_by_id(id)?
+                    .dyn_into::<web_sys::HtmlCanvasElement>()
+                    .ok()
+            })?;
+            // Leaving default browser behavior in place keeps the page scrollable by touch outside
+            // of the simulation; touches that the browser claims for scrolling are cancelled
+            WindowAttributes::default()
+                .with_prevent_default(false)
+                .with_canvas(Some(canvas))
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        let attributes = WindowAttributes::default()
+            .with_title(self.config.title)
+            .with_inner_size(self.inner_size)
+            .with_window_icon(self.config.icon.and_then(window_icon));
+        event_loop
+            .create_window(attributes)
+            .map_err(WindowInitError::Os)
+    }
+}
+
+impl Default for AppWrapper<'_> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl ApplicationHandler for AppWrapper<'_> {
+    #[inline]
     fn resumed(&mut self, event_loop: &event_loop::ActiveEventLoop) {
         if self.app.is_none() {
-            let window = Rc::new(if cfg!(target_arch = "wasm32") {
-                use wasm_bindgen::JsCast;
-                // let canvas =
-                let document = web_sys::window()
-                    .expect("Window should be loaded")
-                    .document()
-                    .expect("Document should be loaded");
-                let attributes = WindowAttributes::default().with_prevent_default(false);
-                if let Some(canvas) = document
-                    .get_element_by_id("canvas")
-                    .and_then(|canvas| canvas.dyn_into::<web_sys::HtmlCanvasElement>().ok())
-                {
-                    event_loop
-                        .create_window(attributes.with_canvas(Some(canvas)))
-                        .expect("Creating a window should not fail")
-                } else {
-                    unreachable!("Canvas should exist");
-                }
-            } else {
-                unimplemented!("Application resuming not implemented for current platform");
-            });
-            self.app = Some(
-                block_on(Application::new(Rc::clone(&window)))
-                    .expect("Creating application should not fail"),
-            );
+            let window = match self.create_window(event_loop) {
+                Ok(window) => Arc::new(window),
+                Err(err) => {
+                    error!("Failed to create a window: {err}");
+                    return;
+                }
+            };
+            match block_on(Application::new(
+                Arc::clone(&window),
+                &self.config,
+                self.power_preference,
+            )) {
+                Ok(app) => self.app = Some(app),
+                Err(err) => error!("Failed to initialize the application: {err}"),
+            }
         }
     }
 
+    #[inline]
+    #[expect(
+        clippy::too_many_lines,
+        reason = "Every window event is routed and handled in one place"
+    )]
     fn window_event(
         &mut self,
-        _: &event_loop::ActiveEventLoop,
+        event_loop: &event_loop::ActiveEventLoop,
         window_id: window::WindowId,
         event: WindowEvent,
     ) {
         if let Some(ref mut app) = self.app {
-            assert_eq!(
-                app.window().id(),
+            match event_target(
+                app.window().map(window::Window::id),
+                &self.diagnostics,
                 window_id,
-                "Only the created window should emit events"
-            );
+            ) {
+                EventTarget::Main => {}
+                EventTarget::Diagnostics(id) => {
+                    diagnostics_event(&mut self.diagnostics, app, id, event);
+                    return;
+                }
+                EventTarget::Ignored => {
+                    trace!("Received window event {event:?} from unknown window {window_id:?}");
+                    return;
+                }
+            }
+            // Any event may change what is shown, so another frame is drawn after it
+            if !matches!(event, WindowEvent::RedrawRequested) {
+                app.request_redraw();
+            }
+            if app.gui_event(&event) {
+                return;
+            }
             match event {
-                WindowEvent::RedrawRequested => {
-                    app.update();
-                    match app.render() {
-                        Ok(()) => {}
-                        // Reconfigure the surface if lost
-                        Err(SurfaceError::Lost) => app
-                            .resize(app.window().inner_size())
-                            .expect("Window size should be valid"),
-
-                        Err(err) => error!("{:?}", err),
+                WindowEvent::RedrawRequested => redraw(app, event_loop),
+                WindowEvent::Resized(physical_size) => {
+                    app.request_resize(physical_size);
+                }
+                // A resize to the new physical size is requested alongside the new scale factor,
+                // as the window may not report one separately
+                WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                    if let Some(size) = app.window().map(window::Window::inner_size) {
+                        app.scale_factor_changed(scale_factor, size);
                     }
-                    app.window().request_redraw();
                 }
-                WindowEvent::Resized(physical_size) => {
-                    app.resize(physical_size).expect("New size should be valid");
+                WindowEvent::Focused(focused) => app.focus_changed(focused),
+                WindowEvent::Occluded(occluded) => app.occlusion_changed(occluded),
+                // Exiting ends the event loop, which first drops the application in `exiting`,
+                // releasing its windows and GPU resources in order.
+                // On the web, there is no window to close, so these never exit
+                WindowEvent::CloseRequested => {
+                    info!("Window close requested; exiting");
+                    event_loop.exit();
                 }
-                event => {
-                    info!("Received window event {event:?}");
+                // Escape first releases a captured cursor, as browsers also do
+                WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            physical_key: PhysicalKey::Code(KeyCode::Escape),
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                    ..
+                } if app.is_pointer_locked() || cfg!(not(target_arch = "wasm32")) => {
+                    if app.is_pointer_locked() {
+                        app.release_pointer_lock();
+                    } else {
+                        info!("Escape pressed; exiting");
+                        event_loop.exit();
+                    }
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            physical_key: PhysicalKey::Code(KeyCode::F2),
+                            state: ElementState::Pressed,
+                            repeat: false,
+                            ..
+                        },
+                    ..
+                } => {
+                    if let Some(diagnostics) = open_diagnostics(event_loop, app, self.config.title)
+                    {
+                        self.diagnostics.insert(diagnostics.id(), diagnostics);
+                    }
+                }
+                WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            physical_key: PhysicalKey::Code(key),
+                            state,
+                            repeat,
+                            ..
+                        },
+                    ..
+                } => {
+                    app.keyboard_input(key, state, repeat);
+                }
+                WindowEvent::CursorMoved { position, .. } => {
+                    app.cursor_moved(position);
+                }
+                WindowEvent::ModifiersChanged(modifiers) => {
+                    app.modifiers_changed(modifiers.state());
+                }
+                WindowEvent::MouseInput { state, button, .. } => {
+                    app.mouse_input(state, button);
+                }
+                WindowEvent::MouseWheel { delta, .. } => {
+                    app.mouse_wheel(delta);
+                }
+                WindowEvent::Touch(event::Touch {
+                    phase,
+                    location,
+                    id,
+                    ..
+                }) => {
+                    app.touch(id, phase, location);
+                }
+                other => {
+                    trace!("Received window event {other:?}");
                 }
             }
         } else {
-            info!("[UNINITIALIZED] Receieved window event {event:?} from window {window_id:?}");
+            trace!("[UNINITIALIZED] Receieved window event {event:?} from window {window_id:?}");
         }
     }
 
+    #[inline]
     fn new_events(&mut self, _: &event_loop::ActiveEventLoop, cause: event::StartCause) {
-        info!("Received new OS event: {cause:?}");
+        trace!("Received new OS event: {cause:?}");
     }
 
+    #[inline]
     fn user_event(&mut self, _: &event_loop::ActiveEventLoop, (): ()) {
         #[expect(clippy::unreachable, reason = "No user events should ever be sent")]
         {
@@ -111,26 +609,164 @@ impl ApplicationHandler for AppWrapper<'_> {
         }
     }
 
+    #[inline]
     fn device_event(
         &mut self,
         _: &event_loop::ActiveEventLoop,
         device_id: event::DeviceId,
         event: event::DeviceEvent,
     ) {
-        info!("Received new device event {event:?} from device {device_id:?}");
+        match (event, self.app.as_mut()) {
+            (event::DeviceEvent::MouseMotion { delta }, Some(app)) => app.mouse_motion(delta),
+            (other, _) => trace!("Received new device event {other:?} from device {device_id:?}"),
+        }
     }
 
+    #[inline]
     fn about_to_wait(&mut self, _: &event_loop::ActiveEventLoop) {}
 
+    #[inline]
     fn suspended(&mut self, _: &event_loop::ActiveEventLoop) {
         info!("Application suspended");
     }
 
+    #[inline]
     fn exiting(&mut self, _: &event_loop::ActiveEventLoop) {
         info!("Application exiting");
+        // The diagnostics windows' surfaces are dropped before the device they share, which is
+        // released along with the application
+        self.diagnostics.clear();
+        self.app = None;
     }
 
+    #[inline]
     fn memory_warning(&mut self, _: &event_loop::ActiveEventLoop) {
         warn!("Memory warning");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(target_arch = "wasm32"))]
+    use super::{window_icon, WindowIcon};
+    use std::collections::HashMap;
+
+    use winit::{event::WindowEvent, window::WindowId};
+
+    use super::{
+        close_diagnostics, event_target, find_canvas, run, AppWrapper, BuilderError, EventTarget,
+        FrameRecovery, RunError, SimulationConfig, SurfaceError, WindowInitError,
+    };
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn new_wrapper_has_no_application_yet() {
+        let wrapper = AppWrapper::new();
+        assert!(wrapper.app.is_none());
+        assert!(wrapper.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn missing_canvas_is_an_error() {
+        let page = |id: &str| (id == "canvas").then_some("the canvas");
+        assert!(matches!(find_canvas("canvas", page), Ok("the canvas")));
+        let missing = find_canvas("no-such-canvas", page);
+        assert!(matches!(
+            missing,
+            Err(WindowInitError::MissingCanvas(ref id)) if id == "no-such-canvas"
+        ));
+        assert_eq!(
+            missing.map_err(|err| err.to_string()),
+            Err("the page has no canvas with the id `no-such-canvas`".to_owned())
+        );
+    }
+
+    #[test]
+    fn canvas_id_is_kept() {
+        assert_eq!(AppWrapper::new().canvas_id, "canvas");
+        assert_eq!(AppWrapper::with_canvas_id("sim").canvas_id, "sim");
+    }
+
+    #[test]
+    fn frame_errors_map_onto_recoveries() {
+        assert_eq!(
+            FrameRecovery::for_error(&SurfaceError::Lost),
+            FrameRecovery::Reconfigure
+        );
+        assert_eq!(
+            FrameRecovery::for_error(&SurfaceError::Outdated),
+            FrameRecovery::Reconfigure
+        );
+        assert_eq!(
+            FrameRecovery::for_error(&SurfaceError::Timeout),
+            FrameRecovery::Skip
+        );
+        assert_eq!(
+            FrameRecovery::for_error(&SurfaceError::OutOfMemory),
+            FrameRecovery::Exit
+        );
+    }
+
+    #[test]
+    fn events_route_to_the_window_they_are_for() {
+        let [main, first, second, unknown] = [1, 2, 3, 4].map(WindowId::from);
+        let mut diagnostics = HashMap::from([(first, "first"), (second, "second")]);
+        assert_eq!(
+            event_target(Some(main), &diagnostics, main),
+            EventTarget::Main
+        );
+        assert_eq!(
+            event_target(Some(main), &diagnostics, first),
+            EventTarget::Diagnostics(first)
+        );
+        assert_eq!(
+            event_target(Some(main), &diagnostics, second),
+            EventTarget::Diagnostics(second)
+        );
+        assert_eq!(
+            event_target(Some(main), &diagnostics, unknown),
+            EventTarget::Ignored
+        );
+        assert_eq!(event_target(None, &diagnostics, main), EventTarget::Ignored);
+
+        assert!(!close_diagnostics(
+            &mut diagnostics,
+            first,
+            &WindowEvent::Focused(true)
+        ));
+        assert!(close_diagnostics(
+            &mut diagnostics,
+            first,
+            &WindowEvent::CloseRequested
+        ));
+        assert_eq!(diagnostics, HashMap::from([(second, "second")]));
+        assert_eq!(
+            event_target(Some(main), &diagnostics, first),
+            EventTarget::Ignored
+        );
+        assert_eq!(
+            event_target(Some(main), &diagnostics, second),
+            EventTarget::Diagnostics(second)
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn icons_must_have_a_pixel_for_every_four_bytes() {
+        assert!(window_icon(WindowIcon::new(&[0; 16], 2, 2)).is_some());
+        assert!(window_icon(WindowIcon::new(&[0; 12], 2, 2)).is_none());
+        assert!(window_icon(WindowIcon::new(&[0; 15], 2, 2)).is_none());
+    }
+
+    #[test]
+    fn invalid_configurations_do_not_start_the_event_loop() {
+        let config = SimulationConfig {
+            particle_count: 0,
+            ..SimulationConfig::default()
+        };
+        assert!(matches!(
+            run(config),
+            Err(RunError::InvalidConfig(BuilderError::NoParticles))
+        ));
+    }
+}
@@ -0,0 +1,93 @@
+// Shaders for drawing each particle as an instance of a quad, either cut down to a solid circle or
+// textured with a soft disc
+
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> camera: CameraUniform;
+
+struct Coloring {
+    // Normalizes speeds into [0, 1]
+    speed_scale: f32,
+    // Nonzero if particles are colored by speed, rather than flatly
+    by_velocity: u32,
+    // Half the size of every particle in normalized device coordinates, or zero to draw particles
+    // at their own radius
+    point_size: vec2<f32>,
+    // The color of every particle when not colored by speed
+    flat: vec4<f32>,
+    // The colors of the ramp from the slowest particles, through half the top speed, to the
+    // fastest
+    slow: vec4<f32>,
+    middle: vec4<f32>,
+    fast: vec4<f32>,
+};
+
+@group(1) @binding(0) var<uniform> coloring: Coloring;
+
+// The opacity of a soft disc filling the quad, only bound when drawing in the disc style
+@group(2) @binding(0) var disc_texture: texture_2d<f32>;
+@group(2) @binding(1) var disc_sampler: sampler;
+
+struct CornerInput {
+    @location(0) corner: vec2<f32>,
+};
+
+struct ParticleInput {
+    @location(1) position: vec2<f32>,
+    @location(2) velocity: vec2<f32>,
+    @location(3) radius: f32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // Position within the quad, relative to the particle's center and in units of its radius
+    @location(0) local: vec2<f32>,
+    @location(1) color: vec3<f32>,
+};
+
+// Maps a speed normalized into [0, 1] onto the ramp from the slow color, through the middle
+// color, to the fast color
+fn speed_color(speed: f32) -> vec3<f32> {
+    let t = clamp(speed, 0.0, 1.0);
+    if t < 0.5 {
+        return mix(coloring.slow.rgb, coloring.middle.rgb, 2.0 * t);
+    }
+    return mix(coloring.middle.rgb, coloring.fast.rgb, 2.0 * t - 1.0);
+}
+
+@vertex
+fn vs_main(vertex: CornerInput, particle: ParticleInput) -> VertexOutput {
+    var out: VertexOutput;
+    if coloring.point_size.x > 0.0 {
+        // The quad is offset after projection, so that it keeps the same size on screen
+        let center = camera.view_proj * vec4<f32>(particle.position, 0.0, 1.0);
+        let offset = vertex.corner * coloring.point_size * center.w;
+        out.clip_position = center + vec4<f32>(offset, 0.0, 0.0);
+    } else {
+        let position = particle.position + vertex.corner * particle.radius;
+        out.clip_position = camera.view_proj * vec4<f32>(position, 0.0, 1.0);
+    }
+    out.local = vertex.corner;
+    if coloring.by_velocity != 0u {
+        out.color = speed_color(length(particle.velocity) * coloring.speed_scale);
+    } else {
+        out.color = coloring.flat.rgb;
+    }
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    if dot(in.local, in.local) > 1.0 {
+        discard;
+    }
+    return vec4<f32>(in.color, 1.0);
+}
+
+@fragment
+fn fs_disc(in: VertexOutput) -> @location(0) vec4<f32> {
+    let alpha = textureSample(disc_texture, disc_sampler, in.local * 0.5 + 0.5).r;
+    return vec4<f32>(in.color, alpha);
+}
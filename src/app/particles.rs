@@ -0,0 +1,1121 @@
+//! Particles making up the simulation, and their GPU-side storage
+
+#![expect(
+    clippy::float_arithmetic,
+    reason = "Particles move, collide, and bounce through continuous floating-point physics"
+)]
+
+#[cfg(not(target_arch = "wasm32"))]
+use core::iter;
+use core::mem;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::mpsc;
+
+use bytemuck::{Pod, Zeroable};
+#[cfg(not(target_arch = "wasm32"))]
+use log::error;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use wgpu::util::DeviceExt as _;
+
+use super::{grid, physics::SimulationCore, quadtree::QuadTree};
+
+/// The radius of a particle of unit mass, in meters. Particles are given this radius unless
+/// spawned with another
+pub(super) const RADIUS: f32 = 0.01;
+
+/// The number of particles that room is made for up front, unless fewer are allowed
+const INITIAL_CAPACITY: usize = 1024;
+
+/// The corners of the quad each particle is drawn as, relative to the particle's center and in
+/// units of its radius
+const QUAD_CORNERS: [[f32; 2]; 4] = [[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]];
+
+/// Indices into [`QUAD_CORNERS`] making up the quad's two triangles, in counter-clockwise order
+const QUAD_INDICES: [u16; 6] = [0, 1, 2, 0, 2, 3];
+
+/// The attributes of a quad corner, matching the locations in the particle shader
+const CORNER_ATTRIBUTES: [wgpu::VertexAttribute; 1] = wgpu::vertex_attr_array![0 => Float32x2];
+
+/// Returns the layout of a buffer of quad corners, shared by every particle
+const fn corner_desc() -> wgpu::VertexBufferLayout<'static> {
+    wgpu::VertexBufferLayout {
+        #[expect(
+            clippy::as_conversions,
+            reason = "`usize` to `u64` is lossless on all supported platforms"
+        )]
+        array_stride: mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &CORNER_ATTRIBUTES,
+    }
+}
+
+/// Returns the mass of a particle of the given radius. Every particle has the same density, with
+/// a particle of radius [`RADIUS`] having unit mass
+pub(super) fn mass_for_radius(radius: f32) -> f32 {
+    (radius / RADIUS).powi(2)
+}
+
+/// Returns the radius of particles saved without one
+const fn default_radius() -> f32 {
+    RADIUS
+}
+
+/// Returns the mass of particles saved without one
+const fn default_mass() -> f32 {
+    1.0
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable, Serialize, Deserialize)]
+/// A single disc-shaped body in the simulation
+pub(super) struct Particle {
+    /// Position of the particle, in meters
+    pub position: [f32; 2],
+    /// Velocity of the particle, in meters per second
+    pub velocity: [f32; 2],
+    /// Radius of the particle, in meters, which it collides and is drawn at
+    #[serde(default = "default_radius")]
+    pub radius: f32,
+    /// Mass of the particle, which forces are divided by to accelerate it. Must be positive
+    #[serde(default = "default_mass")]
+    pub mass: f32,
+}
+
+impl Particle {
+    /// The attributes of a particle used for rendering, matching the locations in the particle
+    /// shader. The mass follows the radius, but is not needed for rendering
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![1 => Float32x2, 2 => Float32x2, 3 => Float32];
+
+    /// Creates a particle of the given radius, with a mass in proportion to its area
+    pub fn new(position: [f32; 2], velocity: [f32; 2], radius: f32) -> Self {
+        debug_assert!(
+            radius.is_finite() && radius > 0.0,
+            "Particles should have a finite, positive radius"
+        );
+        Self {
+            position,
+            velocity,
+            radius,
+            mass: mass_for_radius(radius),
+        }
+    }
+
+    /// Returns the layout of a buffer of particles, when used as instances of a quad
+    const fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            #[expect(
+                clippy::as_conversions,
+                reason = "`usize` to `u64` is lossless on all supported platforms"
+            )]
+            array_stride: mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+
+    /// Returns whether every component of the particle's state is finite
+    pub fn is_finite(&self) -> bool {
+        self.position
+            .iter()
+            .chain(&self.velocity)
+            .chain([&self.radius, &self.mass])
+            .all(|component| component.is_finite())
+    }
+
+    /// Creates a particle at a uniformly random position within the given half extents of the
+    /// view, moving with a uniformly random velocity, with a radius drawn from the given
+    /// distribution
+    pub fn random<R>(rng: &mut R, half_extents: [f32; 2], sizes: SizeDistribution) -> Self
+    where
+        R: Rng,
+    {
+        /// The largest initial speed along each axis
+        const MAX_SPEED: f32 = 0.5;
+        Self::new(
+            half_extents.map(|extent| rng.gen_range(-extent..=extent)),
+            [
+                rng.gen_range(-MAX_SPEED..=MAX_SPEED),
+                rng.gen_range(-MAX_SPEED..=MAX_SPEED),
+            ],
+            sizes.sample(rng),
+        )
+    }
+
+    /// Accelerates the particle for `dt` seconds and then moves it along its new velocity,
+    /// using semi-implicit Euler integration
+    pub fn integrate(&mut self, acceleration: [f32; 2], dt: f32) {
+        for ((position, velocity), component) in self
+            .position
+            .iter_mut()
+            .zip(&mut self.velocity)
+            .zip(acceleration)
+        {
+            *velocity += component * dt;
+            *position += *velocity * dt;
+        }
+    }
+
+    /// Moves the particle from its current and previous positions under the given acceleration
+    /// using position Verlet integration, updating `previous` to its current position.
+    /// As Verlet integration does not track velocity, the particle's velocity is then derived as
+    /// the distance it moved over `dt`
+    pub fn integrate_verlet(&mut self, previous: &mut [f32; 2], acceleration: [f32; 2], dt: f32) {
+        for (((position, previous_position), velocity), component) in self
+            .position
+            .iter_mut()
+            .zip(previous)
+            .zip(&mut self.velocity)
+            .zip(acceleration)
+        {
+            let next = 2.0 * *position - *previous_position + component * dt * dt;
+            *previous_position = *position;
+            *velocity = (next - *position) / dt;
+            *position = next;
+        }
+    }
+
+    /// Keeps the particle within the floor, ceiling, and walls of the environment according to
+    /// its boundary mode
+    pub fn apply_bounds(&mut self, environment: &Environment) {
+        let lower = [environment.left, environment.floor];
+        let upper = [environment.right, environment.ceiling];
+        for (((position, velocity), min), max) in self
+            .position
+            .iter_mut()
+            .zip(&mut self.velocity)
+            .zip(lower)
+            .zip(upper)
+        {
+            match environment.boundary {
+                // Particles that passed a boundary are clamped back onto it, and the component of
+                // their velocity towards it is reflected, scaled by the restitution
+                BoundaryMode::Bounce => {
+                    if *position < min {
+                        *position = min;
+                        if *velocity < 0.0 {
+                            *velocity = -*velocity * environment.restitution.at(*velocity);
+                        }
+                    } else if *position > max {
+                        *position = max;
+                        if *velocity > 0.0 {
+                            *velocity = -*velocity * environment.restitution.at(*velocity);
+                        }
+                    } else {
+                        // Particles within the bounds move freely
+                    }
+                }
+                // Wrapping by the remainder also handles particles that moved across more than
+                // the whole extent in one step
+                BoundaryMode::Wrap => {
+                    let extent = max - min;
+                    if extent > 0.0 {
+                        *position = min + (*position - min).rem_euclid(extent);
+                    }
+                }
+                BoundaryMode::Open => {}
+            }
+        }
+    }
+
+    /// Resolves a collision between two particles, if they overlap.
+    /// The particles are pushed apart so they just touch, each moving in inverse proportion to its
+    /// mass, and if they are approaching each other, they exchange an impulse along the line
+    /// between them, which conserves their total momentum. The speed at which they approach is
+    /// reversed, scaled by the restitution at that speed
+    pub fn collide(&mut self, other: &mut Self, restitution: RestitutionModel) {
+        let offset = [
+            other.position[0] - self.position[0],
+            other.position[1] - self.position[1],
+        ];
+        let distance = grid::distance_squared(self.position, other.position).sqrt();
+        let overlap = self.radius + other.radius - distance;
+        if overlap <= 0.0 || distance == 0.0 {
+            return;
+        }
+        let normal = offset.map(|component| component / distance);
+        let (inverse_mass, other_inverse_mass) = (1.0 / self.mass, 1.0 / other.mass);
+        let total_inverse_mass = inverse_mass + other_inverse_mass;
+        for ((position, other_position), component) in self
+            .position
+            .iter_mut()
+            .zip(&mut other.position)
+            .zip(normal)
+        {
+            let push = component * overlap / total_inverse_mass;
+            *position -= push * inverse_mass;
+            *other_position += push * other_inverse_mass;
+        }
+
+        let approach_speed = (self.velocity[0] - other.velocity[0]) * normal[0]
+            + (self.velocity[1] - other.velocity[1]) * normal[1];
+        if approach_speed > 0.0 {
+            let impulse =
+                (1.0 + restitution.at(approach_speed)) * approach_speed / total_inverse_mass;
+            for ((velocity, other_velocity), component) in self
+                .velocity
+                .iter_mut()
+                .zip(&mut other.velocity)
+                .zip(normal)
+            {
+                *velocity -= impulse * component * inverse_mass;
+                *other_velocity += impulse * component * other_inverse_mass;
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// The range that the radii of spawned particles are drawn uniformly from
+pub(super) struct SizeDistribution {
+    /// The smallest radius, in meters
+    pub min_radius: f32,
+    /// The largest radius, in meters. Must be at least `min_radius`
+    pub max_radius: f32,
+}
+
+impl SizeDistribution {
+    /// Returns a radius drawn from the distribution
+    pub fn sample<R>(self, rng: &mut R) -> f32
+    where
+        R: Rng,
+    {
+        rng.gen_range(self.min_radius..=self.max_radius)
+    }
+}
+
+impl Default for SizeDistribution {
+    /// Radii from half to twice the radius of a unit mass, so masses vary sixteenfold
+    fn default() -> Self {
+        Self {
+            min_radius: RADIUS / 2.0,
+            max_radius: RADIUS * 2.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// The global forces and boundaries acting on every particle
+pub(super) struct Environment {
+    /// Vertical acceleration applied to every particle, in meters per second squared
+    pub gravity: f32,
+    /// The fraction of its speed that a particle retains when bouncing off the floor, ceiling,
+    /// walls, obstacles, or other particles, depending on how fast it hits them
+    pub restitution: RestitutionModel,
+    /// The height of the floor, in meters
+    pub floor: f32,
+    /// The horizontal position of the left wall, in meters
+    pub left: f32,
+    /// The horizontal position of the right wall, in meters
+    pub right: f32,
+    /// The height of the ceiling, in meters
+    pub ceiling: f32,
+    /// A radial force pushing particles around, if any
+    pub force_field: Option<ForceField>,
+    /// What happens to particles that reach the floor, ceiling, or walls
+    pub boundary: BoundaryMode,
+    /// Mutual gravitational attraction between particles, if any
+    pub gravitation: Option<Gravitation>,
+    /// The rate at which air resistance slows every particle, per second. Each second, speeds
+    /// shrink by a factor of `e^-damping`, so zero leaves them unchanged
+    pub damping: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// How the fraction of its speed that a particle retains when bouncing depends on the speed it
+/// hits at. Whatever the model, the fraction is clamped to `[0, 1]`
+#[non_exhaustive]
+pub enum RestitutionModel {
+    /// The same fraction at every speed
+    Constant(f32),
+    /// A fraction falling off linearly with the impact speed, so that harder impacts lose more of
+    /// their energy, as real ones do
+    Linear {
+        /// The fraction retained by an impact at no speed
+        e0: f32,
+        /// How much the fraction falls for every meter per second of impact speed
+        slope: f32,
+    },
+}
+
+impl RestitutionModel {
+    /// Returns the fraction retained by an impact at no speed, and how much it falls for every
+    /// meter per second of impact speed
+    pub(super) const fn coefficients(self) -> (f32, f32) {
+        match self {
+            Self::Constant(restitution) => (restitution, 0.0),
+            Self::Linear { e0, slope } => (e0, slope),
+        }
+    }
+
+    /// Returns the fraction of its speed that a particle hitting at the given speed retains
+    #[inline]
+    #[must_use]
+    pub fn at(self, impact_speed: f32) -> f32 {
+        let (e0, slope) = self.coefficients();
+        slope.mul_add(-impact_speed.abs(), e0).clamp(0.0, 1.0)
+    }
+
+    /// Returns the fraction retained by an impact at no speed
+    pub(super) const fn base(self) -> f32 {
+        self.coefficients().0
+    }
+
+    /// Returns the model with the fraction retained by an impact at no speed replaced, keeping
+    /// how it falls off with speed
+    pub(super) const fn with_base(self, base: f32) -> Self {
+        match self {
+            Self::Constant(_) => Self::Constant(base),
+            Self::Linear { slope, .. } => Self::Linear { e0: base, slope },
+        }
+    }
+}
+
+impl Default for RestitutionModel {
+    /// Four fifths of the speed retained at every speed
+    #[inline]
+    fn default() -> Self {
+        Self::Constant(0.8)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// What happens to particles that reach the edges of the world
+pub(super) enum BoundaryMode {
+    /// Particles bounce off the edges
+    #[default]
+    Bounce,
+    /// Particles leaving through one edge reappear at the opposite edge
+    Wrap,
+    /// Particles fly off past the edges
+    Open,
+}
+
+impl BoundaryMode {
+    /// Returns the mode after this one, cycling back to the first
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Bounce => Self::Wrap,
+            Self::Wrap => Self::Open,
+            Self::Open => Self::Bounce,
+        }
+    }
+
+    /// Returns the value identifying this mode in the integration shader
+    #[cfg(not(target_arch = "wasm32"))]
+    pub const fn shader_value(self) -> u32 {
+        match self {
+            Self::Bounce => 0,
+            Self::Wrap => 1,
+            Self::Open => 2,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// A radial force centered on a point, falling off with the square of the distance from it
+pub(super) struct ForceField {
+    /// The point the force pushes away from, in meters
+    pub center: [f32; 2],
+    /// The force at unit distance from the center, which accelerates unit masses by as much.
+    /// Negative strengths pull particles towards the center instead
+    pub strength: f32,
+}
+
+impl ForceField {
+    /// The squared distance below which the force stops growing, so that particles passing close
+    /// to the center are not flung away. Must match `MIN_DISTANCE_SQUARED` in the integration
+    /// shader
+    const MIN_DISTANCE_SQUARED: f32 = 0.01;
+
+    /// Returns the force the field applies to a particle at the given position.
+    /// A particle exactly at the center has no direction to be pushed in, so is left alone
+    pub fn force(&self, position: [f32; 2]) -> [f32; 2] {
+        let offset = [position[0] - self.center[0], position[1] - self.center[1]];
+        let distance_squared = grid::distance_squared(position, self.center);
+        if distance_squared == 0.0 {
+            return [0.0, 0.0];
+        }
+        let magnitude = self.strength / distance_squared.max(Self::MIN_DISTANCE_SQUARED);
+        let distance = distance_squared.sqrt();
+        offset.map(|component| component / distance * magnitude)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Newtonian gravity pulling every pair of particles towards each other, so that each particle
+/// accelerates towards every other by `constant * mass / r^2`, where `mass` is the other's
+pub(super) struct Gravitation {
+    /// The gravitational constant, in meters cubed per second squared
+    pub constant: f32,
+    /// The Barnes–Hut opening angle: clusters of particles smaller than `theta` times their
+    /// distance are approximated by their center of mass. Zero computes every pair exactly
+    pub theta: f32,
+}
+
+impl Gravitation {
+    /// The distance added in quadrature to the separation of every pair, so that particles
+    /// passing through each other are not flung apart by an unbounded force
+    const SOFTENING: f32 = 2.0 * RADIUS;
+
+    /// Returns the acceleration that a body of the given mass at `source` applies to a particle
+    /// at `target`
+    fn acceleration(self, target: [f32; 2], source: [f32; 2], mass: f32) -> [f32; 2] {
+        let offset = [source[0] - target[0], source[1] - target[1]];
+        let softened = grid::distance_squared(target, source) + Self::SOFTENING * Self::SOFTENING;
+        let scale = self.constant * mass / (softened * softened.sqrt());
+        offset.map(|component| component * scale)
+    }
+
+    /// Returns the acceleration of each of the given bodies, as positions and masses, due to all
+    /// others, approximated with a Barnes–Hut quadtree in `O(n log n)` time
+    pub fn accelerations(self, bodies: &[([f32; 2], f32)]) -> Vec<[f32; 2]> {
+        let tree = QuadTree::new(bodies);
+        bodies
+            .iter()
+            .map(|&(position, _)| {
+                // A particle's own mass lies at zero offset from it, so contributes nothing
+                let mut total = [0.0, 0.0];
+                tree.visit(position, self.theta, |source, mass| {
+                    let [x, y] = self.acceleration(position, source, mass);
+                    total[0] += x;
+                    total[1] += y;
+                });
+                total
+            })
+            .collect()
+    }
+}
+
+impl Environment {
+    /// Returns the factor velocities are scaled by over `dt` seconds of air resistance, which
+    /// lies in `(0, 1]` for non-negative damping
+    pub fn damping_factor(&self, dt: f32) -> f32 {
+        (-self.damping * dt).exp()
+    }
+
+    /// Returns the acceleration applied to the given particle. Gravity accelerates every particle
+    /// equally, while the force field pushes lighter particles harder
+    pub fn acceleration(&self, particle: &Particle) -> [f32; 2] {
+        let [x, y] = self.force_field.map_or([0.0, 0.0], |field| {
+            field
+                .force(particle.position)
+                .map(|component| component / particle.mass)
+        });
+        [x, y + self.gravity]
+    }
+}
+
+/// A collection of particles, mirrored into a GPU buffer for rendering
+pub(super) struct ParticleSystem {
+    /// The CPU-side copy of every particle, and the physics acting on them
+    core: SimulationCore,
+    /// The pair of GPU-side copies of every particle. The current one, chosen by `flipped`, holds
+    /// the latest state and is drawn. Integrating on the GPU reads from the current buffer and
+    /// writes into the other, which then becomes current, so that every read sees the previous
+    /// step's state. Only valid after an `upload`
+    buffers: [wgpu::Buffer; 2],
+    /// Whether the second of `buffers` is current, rather than the first
+    flipped: bool,
+    /// A mappable buffer that the GPU-side particles are copied into to be read back
+    #[cfg(not(target_arch = "wasm32"))]
+    staging_buffer: wgpu::Buffer,
+    /// The maximum number of particles that fit in `buffers`, which grow as needed up to `limit`
+    capacity: usize,
+    /// The most particles that the system may hold at once
+    limit: usize,
+    /// The index of the longest-lived particle, which is replaced first once the system is full.
+    /// Particles are replaced in the order they were added, treating `particles` as a ring
+    oldest: usize,
+    /// The corners of the quad drawn for every particle
+    quad_vertex_buffer: wgpu::Buffer,
+    /// The indices into `quad_vertex_buffer` describing the quad's triangles
+    quad_index_buffer: wgpu::Buffer,
+}
+
+/// Returns the size of a buffer holding `capacity` particles
+const fn buffer_size(capacity: usize) -> wgpu::BufferAddress {
+    #[expect(
+        clippy::as_conversions,
+        reason = "`usize` to `u64` is lossless on all supported platforms"
+    )]
+    let size = capacity.saturating_mul(mem::size_of::<Particle>()) as wgpu::BufferAddress;
+    size
+}
+
+/// Creates the GPU buffer holding up to `capacity` particles
+fn create_particle_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Particle Buffer"),
+        size: buffer_size(capacity),
+        usage: wgpu::BufferUsages::VERTEX
+            | wgpu::BufferUsages::STORAGE
+            | wgpu::BufferUsages::COPY_DST
+            | wgpu::BufferUsages::COPY_SRC,
+        mapped_at_creation: false,
+    })
+}
+
+/// Creates a mappable buffer that up to `capacity` particles are copied into to be read back
+#[cfg(not(target_arch = "wasm32"))]
+fn create_staging_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Particle Staging Buffer"),
+        size: buffer_size(capacity),
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+impl ParticleSystem {
+    /// Creates a particle system holding the given particles, which may hold up to `limit`
+    /// particles in total
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        particles: Vec<Particle>,
+        limit: usize,
+    ) -> Self {
+        assert!(
+            particles.len() <= limit,
+            "Initial particles should fit within the limit"
+        );
+        let capacity = particles.len().max(INITIAL_CAPACITY.min(limit));
+        let buffers = [(); 2].map(|()| create_particle_buffer(device, capacity));
+        #[cfg(not(target_arch = "wasm32"))]
+        let staging_buffer = create_staging_buffer(device, capacity);
+        let quad_vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particle Quad Vertex Buffer"),
+            contents: bytemuck::cast_slice(&QUAD_CORNERS),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let quad_index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Particle Quad Index Buffer"),
+            contents: bytemuck::cast_slice(&QUAD_INDICES),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        let system = Self {
+            core: SimulationCore::new(particles),
+            buffers,
+            flipped: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            staging_buffer,
+            capacity,
+            limit,
+            oldest: 0,
+            quad_vertex_buffer,
+            quad_index_buffer,
+        };
+        system.upload(queue);
+        system
+    }
+
+    /// Adds a particle to the system, writing it directly into the GPU buffer.
+    /// The GPU buffer doubles in size whenever it fills up, until the system reaches its limit.
+    /// From then on, the oldest particle is replaced instead, so the number of particles stays
+    /// bounded. Returns `false`, without adding the particle, only if the limit is zero
+    pub fn spawn(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        particle: Particle,
+    ) -> bool {
+        let index = if self.core.particles().len() < self.limit {
+            if self.core.particles().len() >= self.capacity {
+                self.grow(device, queue);
+            }
+            self.core.particles_mut().push(particle);
+            self.core.particles().len().saturating_sub(1)
+        } else if let Some(oldest) = self.core.particles_mut().get_mut(self.oldest) {
+            *oldest = particle;
+            let index = self.oldest;
+            self.oldest = self
+                .oldest
+                .saturating_add(1)
+                .checked_rem(self.core.particles().len())
+                .unwrap_or(0);
+            index
+        } else {
+            return false;
+        };
+        #[expect(
+            clippy::as_conversions,
+            reason = "`usize` to `u64` is lossless on all supported platforms"
+        )]
+        let offset = index.saturating_mul(mem::size_of::<Particle>()) as wgpu::BufferAddress;
+        queue.write_buffer(self.current_buffer(), offset, bytemuck::bytes_of(&particle));
+        true
+    }
+
+    /// Adds as many of the given particles to the system as fit within its limit, growing the GPU
+    /// buffers as needed and then uploading every particle. Unlike `spawn`, no particles are
+    /// replaced. Returns the number of particles added
+    pub fn add(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        mut particles: Vec<Particle>,
+    ) -> usize {
+        particles.truncate(self.limit.saturating_sub(self.core.particles().len()));
+        let added = particles.len();
+        // The buffers must grow before the particles are added, as growing uploads them
+        while self.capacity < self.core.particles().len().saturating_add(added) {
+            self.grow(device, queue);
+        }
+        self.core.particles_mut().append(&mut particles);
+        self.upload(queue);
+        added
+    }
+
+    /// Removes up to `count` of the most recently added particles, stopping once none are left.
+    /// The GPU buffers keep their capacity. Returns the number of particles removed
+    pub fn remove(&mut self, count: usize) -> usize {
+        let remaining = self.core.particles().len().saturating_sub(count);
+        let removed = self.core.particles().len().saturating_sub(remaining);
+        self.core.particles_mut().truncate(remaining);
+        if self.oldest >= remaining {
+            self.oldest = 0;
+        }
+        removed
+    }
+
+    /// Replaces the GPU buffers with ones of double the capacity, up to the limit, and uploads
+    /// every particle into them.
+    /// Anything bound to the old particle buffers must be rebound to the new ones
+    fn grow(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.capacity = self.capacity.saturating_mul(2).clamp(1, self.limit);
+        self.buffers = [(); 2].map(|()| create_particle_buffer(device, self.capacity));
+        self.flipped = false;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.staging_buffer = create_staging_buffer(device, self.capacity);
+        }
+        self.upload(queue);
+    }
+
+    /// Returns the number of particles that fit in the GPU buffers before they must grow
+    #[cfg_attr(
+        all(target_arch = "wasm32", not(test)),
+        expect(
+            dead_code,
+            reason = "Only the GPU integrator is bound to the buffer, and must notice it growing"
+        )
+    )]
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the layouts of the vertex buffers bound when drawing particles: the quad corners,
+    /// followed by the particles as instances
+    pub const fn buffer_layouts() -> [wgpu::VertexBufferLayout<'static>; 2] {
+        [corner_desc(), Particle::desc()]
+    }
+
+    /// Returns the GPU buffer holding the latest state of the particles
+    const fn current_buffer(&self) -> &wgpu::Buffer {
+        let [ref first, ref second] = self.buffers;
+        if self.flipped {
+            second
+        } else {
+            first
+        }
+    }
+
+    /// Returns both GPU buffers holding the particles, in a fixed order regardless of which is
+    /// current
+    #[cfg(not(target_arch = "wasm32"))]
+    pub const fn buffers(&self) -> [&wgpu::Buffer; 2] {
+        let [ref first, ref second] = self.buffers;
+        [first, second]
+    }
+
+    /// Returns whether the second of the `buffers` holds the latest state, rather than the first
+    #[cfg(not(target_arch = "wasm32"))]
+    pub const fn flipped(&self) -> bool {
+        self.flipped
+    }
+
+    /// Makes the other GPU buffer current, once the latest state has been written into it
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn swap_buffers(&mut self) {
+        self.flipped = !self.flipped;
+    }
+
+    /// Returns every particle in the system
+    pub fn particles(&self) -> &[Particle] {
+        self.core.particles()
+    }
+
+    /// Removes every particle whose state is no longer finite, returning how many were removed.
+    /// The particles are uploaded again on the next `upload`
+    pub fn sanitize(&mut self) -> usize {
+        let removed = self.core.sanitize();
+        if self.oldest >= self.core.particles().len() {
+            self.oldest = 0;
+        }
+        removed
+    }
+
+    /// Returns the number of particles removed so far for having non-finite state
+    pub const fn non_finite_count(&self) -> usize {
+        self.core.non_finite_count()
+    }
+
+    /// Returns the CPU-side particles and the physics acting on them
+    pub fn core_mut(&mut self) -> &mut SimulationCore {
+        &mut self.core
+    }
+
+    /// Returns the number of particles in the system
+    #[expect(
+        clippy::expect_used,
+        reason = "Particles are limited to what fits in a GPU buffer, far fewer than `u32::MAX`"
+    )]
+    pub fn len(&self) -> u32 {
+        u32::try_from(self.core.particles().len()).expect("Particle count should fit in a `u32`")
+    }
+
+    /// Replaces the CPU-side particles with the contents of the current GPU buffer, blocking until the
+    /// copy completes
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn download(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        #[expect(
+            clippy::as_conversions,
+            reason = "`usize` to `u64` is lossless on all supported platforms"
+        )]
+        let size = mem::size_of_val(self.core.particles()) as wgpu::BufferAddress;
+        if size == 0 {
+            return;
+        }
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Particle Download Encoder"),
+        });
+        encoder.copy_buffer_to_buffer(self.current_buffer(), 0, &self.staging_buffer, 0, size);
+        queue.submit(iter::once(encoder.finish()));
+
+        let slice = self.staging_buffer.slice(..size);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            if let Err(err) = sender.send(result) {
+                error!("Failed to report particle read back: {err}");
+            }
+        });
+        device.poll(wgpu::Maintain::Wait);
+        match receiver.recv() {
+            Ok(Ok(())) => {
+                self.core
+                    .particles_mut()
+                    .copy_from_slice(bytemuck::cast_slice(&slice.get_mapped_range()));
+                self.staging_buffer.unmap();
+            }
+            Ok(Err(err)) => error!("Failed to read back particles: {err:?}"),
+            Err(err) => error!("Particle read back was never completed: {err:?}"),
+        }
+    }
+
+    /// Copies the current particle state into the current GPU buffer, which also holds the
+    /// instances drawn
+    pub fn upload(&self, queue: &wgpu::Queue) {
+        queue.write_buffer(
+            self.current_buffer(),
+            0,
+            bytemuck::cast_slice(self.core.particles()),
+        );
+    }
+
+    /// Draws every particle as an instance of a quad, in a single call, using the currently bound
+    /// pipeline
+    pub fn draw<'pass>(&'pass self, render_pass: &mut wgpu::RenderPass<'pass>) {
+        if self.core.particles().is_empty() {
+            return;
+        }
+        render_pass.set_vertex_buffer(0, self.quad_vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.current_buffer().slice(..));
+        render_pass.set_index_buffer(self.quad_index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        #[expect(
+            clippy::as_conversions,
+            clippy::cast_possible_truncation,
+            reason = "The quad has only a handful of indices"
+        )]
+        render_pass.draw_indexed(0..QUAD_INDICES.len() as u32, 0, 0..self.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem;
+
+    use super::{
+        BoundaryMode, Environment, ForceField, Gravitation, Particle, ParticleSystem,
+        RestitutionModel, INITIAL_CAPACITY, RADIUS,
+    };
+    use crate::app::device;
+
+    /// The fraction of its speed that a particle retains when bouncing in tests
+    const RESTITUTION: f32 = 0.5;
+
+    /// Returns an environment without gravity, bounded by the unit square around the origin
+    fn environment() -> Environment {
+        Environment {
+            gravity: 0.0,
+            restitution: RestitutionModel::Constant(RESTITUTION),
+            floor: -1.0,
+            left: -1.0,
+            right: 1.0,
+            ceiling: 1.0,
+            force_field: None,
+            boundary: BoundaryMode::Bounce,
+            gravitation: None,
+            damping: 0.0,
+        }
+    }
+
+    #[test]
+    fn quad_corners_are_laid_out_by_vertex() {
+        let [corners, _] = ParticleSystem::buffer_layouts();
+        assert_eq!(
+            usize::try_from(corners.array_stride),
+            Ok(mem::size_of::<[f32; 2]>())
+        );
+        assert_eq!(corners.step_mode, wgpu::VertexStepMode::Vertex);
+    }
+
+    #[test]
+    #[expect(
+        clippy::float_cmp,
+        reason = "Moving from the origin at unit speed is exact"
+    )]
+    fn free_particle_moves_by_its_velocity() {
+        let dt = 1.0 / 60.0;
+        let mut particle = Particle::new([0.0, 0.0], [1.0, 0.0], RADIUS);
+        particle.integrate([0.0, 0.0], dt);
+        assert_eq!(particle.position, [dt, 0.0]);
+        assert_eq!(particle.velocity, [1.0, 0.0]);
+    }
+
+    #[test]
+    #[expect(
+        clippy::float_cmp,
+        reason = "Particles are clamped exactly onto the floor"
+    )]
+    fn dropped_particle_bounces_off_the_floor() {
+        let dt = 1.0 / 60.0;
+        let environment = Environment {
+            gravity: -9.8,
+            ..environment()
+        };
+        let mut particle = Particle::new([0.0, 0.0], [0.0, 0.0], RADIUS);
+        for _ in 0..60_u32 {
+            particle.integrate(environment.acceleration(&particle), dt);
+            let impact_velocity = particle.velocity[1];
+            particle.apply_bounds(&environment);
+            if particle.velocity[1] > 0.0 {
+                assert_eq!(particle.position[1], environment.floor);
+                assert!((particle.velocity[1] + RESTITUTION * impact_velocity).abs() < 1e-6);
+                return;
+            }
+        }
+        panic!("The particle never bounced");
+    }
+
+    #[test]
+    fn particles_are_laid_out_by_instance() {
+        let [_, instances] = ParticleSystem::buffer_layouts();
+        assert_eq!(
+            usize::try_from(instances.array_stride),
+            Ok(mem::size_of::<Particle>())
+        );
+        assert_eq!(instances.step_mode, wgpu::VertexStepMode::Instance);
+    }
+
+    #[test]
+    fn verlet_keeps_free_particles_at_constant_velocity() {
+        let (dt, velocity) = (1.0 / 60.0, [0.5, -0.25]);
+        let mut particle = Particle::new([0.0, 0.0], velocity, RADIUS);
+        let mut previous = [-velocity[0] * dt, -velocity[1] * dt];
+        for _ in 0..10_u32 {
+            particle.integrate_verlet(&mut previous, [0.0, 0.0], dt);
+        }
+        for (actual, expected) in particle.velocity.iter().zip(velocity) {
+            assert!((actual - expected).abs() < 1e-4);
+        }
+        for (actual, expected) in particle.position.iter().zip(velocity) {
+            assert!((actual - expected * dt * 10.0).abs() < 1e-5);
+        }
+    }
+
+    /// Returns a particle at rest at the given distance along the x-axis
+    fn particle_at(x: u16) -> Particle {
+        Particle::new([f32::from(x), 0.0], [0.0, 0.0], RADIUS)
+    }
+
+    #[test]
+    fn spawning_beyond_the_limit_evicts_the_oldest() {
+        let Some((device, queue)) = device::test_device() else {
+            return;
+        };
+        let limit = 10;
+        let mut system = ParticleSystem::new(&device, &queue, Vec::new(), limit);
+        for x in 0..15_u16 {
+            assert!(system.spawn(&device, &queue, particle_at(x)));
+        }
+        let mut kept: Vec<_> = system
+            .particles()
+            .iter()
+            .map(|particle| particle.position[0])
+            .collect();
+        kept.sort_by(f32::total_cmp);
+        assert!(kept.into_iter().eq((5..15_u16).map(f32::from)));
+    }
+
+    #[test]
+    fn buffers_double_once_full() {
+        let Some((device, queue)) = device::test_device() else {
+            return;
+        };
+        let particles = vec![particle_at(0); INITIAL_CAPACITY];
+        let mut system = ParticleSystem::new(&device, &queue, particles, 4 * INITIAL_CAPACITY);
+        assert_eq!(system.capacity(), INITIAL_CAPACITY);
+        assert!(system.spawn(&device, &queue, particle_at(1)));
+        assert_eq!(system.capacity(), 2 * INITIAL_CAPACITY);
+    }
+
+    #[test]
+    fn particles_bounce_off_every_wall() {
+        let environment = environment();
+        // Past the left wall, right wall, floor, and ceiling, each moving further past it
+        for (position, velocity, bounced) in [
+            ([-1.5, 0.0], [-1.0, 0.5], ([-1.0, 0.0], [0.5, 0.5])),
+            ([1.5, 0.0], [1.0, 0.5], ([1.0, 0.0], [-0.5, 0.5])),
+            ([0.0, -1.5], [0.5, -1.0], ([0.0, -1.0], [0.5, 0.5])),
+            ([0.0, 1.5], [0.5, 1.0], ([0.0, 1.0], [0.5, -0.5])),
+        ] {
+            let mut particle = Particle::new(position, velocity, RADIUS);
+            particle.apply_bounds(&environment);
+            assert_eq!((particle.position, particle.velocity), bounced);
+        }
+    }
+
+    #[test]
+    #[expect(clippy::float_cmp, reason = "The center is left exactly alone")]
+    fn force_field_pushes_away_from_its_center() {
+        let field = ForceField {
+            center: [0.5, 0.5],
+            strength: 1.0,
+        };
+        assert_eq!(field.force([0.5, 0.5]), [0.0, 0.0]);
+        let [x, y] = field.force([1.5, 0.5]);
+        assert!(x > 0.0);
+        assert!(y.abs() < 1e-6);
+    }
+
+    #[test]
+    #[expect(
+        clippy::float_cmp,
+        reason = "Only positions are wrapped, and open boundaries change nothing"
+    )]
+    fn wrapping_and_open_boundaries_move_particles_as_expected() {
+        let wrap = Environment {
+            boundary: BoundaryMode::Wrap,
+            ..environment()
+        };
+        // Past the right wall, and then past it by more than the whole width of the world
+        for x in [1.25, 5.25] {
+            let mut particle = Particle::new([x, 0.0], [1.0, 0.0], RADIUS);
+            particle.apply_bounds(&wrap);
+            assert!((particle.position[0] + 0.75).abs() < 1e-6);
+            assert_eq!(particle.velocity, [1.0, 0.0]);
+        }
+
+        let open = Environment {
+            boundary: BoundaryMode::Open,
+            ..environment()
+        };
+        let mut particle = Particle::new([1.25, -3.0], [1.0, -1.0], RADIUS);
+        particle.apply_bounds(&open);
+        assert_eq!(particle.position, [1.25, -3.0]);
+    }
+
+    #[test]
+    fn equal_masses_attract_each_other_symmetrically() {
+        let gravitation = Gravitation {
+            constant: 1.0,
+            theta: 0.0,
+        };
+        let accelerations = gravitation.accelerations(&[([-0.5, 0.0], 1.0), ([0.5, 0.0], 1.0)]);
+        let [first, second] = accelerations[..] else {
+            panic!("every body should accelerate");
+        };
+        assert!(first[0] > 0.0);
+        assert!((first[0] + second[0]).abs() < 1e-6);
+        assert!(first[1].abs() < 1e-6 && second[1].abs() < 1e-6);
+    }
+
+    #[test]
+    fn exact_barnes_hut_matches_brute_force() {
+        let gravitation = Gravitation {
+            constant: 0.5,
+            theta: 0.0,
+        };
+        // Including two bodies at the same position
+        let bodies = [
+            ([-0.5, 0.25], 1.0),
+            ([0.75, -0.5], 2.0),
+            ([0.1, 0.9], 0.5),
+            ([0.1, 0.9], 1.5),
+            ([-0.8, -0.7], 3.0),
+        ];
+        for (&(target, _), approximated) in bodies.iter().zip(gravitation.accelerations(&bodies)) {
+            let exact = bodies.iter().fold([0.0, 0.0], |[x, y], &(source, mass)| {
+                let [dx, dy] = gravitation.acceleration(target, source, mass);
+                [x + dx, y + dy]
+            });
+            for (actual, expected) in approximated.into_iter().zip(exact) {
+                assert!((actual - expected).abs() < 1e-4);
+            }
+        }
+        assert!(gravitation.accelerations(&[]).is_empty());
+    }
+
+    #[test]
+    fn unequal_masses_conserve_momentum_when_colliding() {
+        let mut light = Particle::new([-RADIUS / 2.0, 0.0], [1.0, 0.5], RADIUS);
+        let mut heavy = Particle::new([RADIUS / 2.0, 0.0], [-1.0, 0.0], RADIUS);
+        light.mass = 1.0;
+        heavy.mass = 2.0;
+        let momentum = |first: &Particle, second: &Particle| {
+            [0_usize, 1]
+                .map(|axis| first.mass * first.velocity[axis] + second.mass * second.velocity[axis])
+        };
+        let before = momentum(&light, &heavy);
+        light.collide(&mut heavy, RestitutionModel::Constant(RESTITUTION));
+        assert!(
+            light.velocity[0] < 0.0,
+            "The lighter particle should rebound"
+        );
+        for (after, expected) in momentum(&light, &heavy).into_iter().zip(before) {
+            assert!((after - expected).abs() < 1e-6, "{after} != {expected}");
+        }
+    }
+
+    #[test]
+    #[expect(
+        clippy::float_cmp,
+        reason = "The coefficients are chosen to be exact in binary"
+    )]
+    fn linear_restitution_falls_with_speed_within_the_unit_interval() {
+        let model = RestitutionModel::Linear {
+            e0: 0.75,
+            slope: 0.125,
+        };
+        assert_eq!(model.at(2.0), 0.5);
+        assert_eq!(model.at(-4.0), 0.25);
+        assert_eq!(model.at(10.0), 0.0);
+        let bouncy = RestitutionModel::Linear {
+            e0: 1.5,
+            slope: 0.125,
+        };
+        assert_eq!(bouncy.at(0.0), 1.0);
+    }
+}
@@ -0,0 +1,268 @@
+//! The physics of the particle simulation, run entirely on the CPU and independent of any GPU
+//! resources, so that it can be stepped headlessly, such as for benchmarking
+
+use log::warn;
+
+use super::{
+    collider::Collider,
+    grid::SpatialGrid,
+    particles::{Environment, Gravitation, Particle, RestitutionModel, RADIUS},
+};
+
+/// Particles and the physics acting on them, without any rendering
+pub(super) struct SimulationCore {
+    /// Every particle
+    particles: Vec<Particle>,
+    /// Buckets the particles by position to find collisions
+    grid: SpatialGrid,
+    /// The static obstacles that particles bounce off
+    colliders: Vec<Collider>,
+    /// The number of particles removed so far for having non-finite state
+    non_finite_count: usize,
+}
+
+impl SimulationCore {
+    /// Creates a simulation of the given particles
+    #[expect(
+        clippy::float_arithmetic,
+        reason = "The grid's cells are sized from the floating-point radius of particles"
+    )]
+    pub fn new(particles: Vec<Particle>) -> Self {
+        Self {
+            particles,
+            grid: SpatialGrid::new(2.0 * RADIUS),
+            colliders: Vec::new(),
+            non_finite_count: 0,
+        }
+    }
+
+    /// Returns every particle
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Returns every particle, to be added to, removed from, or replaced
+    pub fn particles_mut(&mut self) -> &mut Vec<Particle> {
+        &mut self.particles
+    }
+
+    /// Replaces the static obstacles that particles bounce off
+    pub fn set_colliders(&mut self, colliders: &[Collider]) {
+        self.colliders = colliders.to_vec();
+    }
+
+    /// Advances the simulation by `dt` seconds within the given environment: the particles
+    /// attract each other if gravitation is on, are integrated under the environment's forces,
+    /// and then resolve their collisions
+    pub fn step(&mut self, dt: f32, environment: &Environment) {
+        if let Some(gravitation) = environment.gravitation {
+            self.attract(gravitation, dt);
+        }
+        self.integrate(dt, environment);
+        self.resolve_collisions(environment.restitution);
+    }
+
+    /// Moves every particle by `dt` seconds under the forces of the given environment, ignoring
+    /// the particles' effects on each other
+    #[expect(
+        clippy::float_arithmetic,
+        reason = "Air resistance scales continuous velocities"
+    )]
+    fn integrate(&mut self, dt: f32, environment: &Environment) {
+        let damping = environment.damping_factor(dt);
+        for particle in &mut self.particles {
+            particle.velocity = particle.velocity.map(|component| component * damping);
+            particle.integrate(environment.acceleration(particle), dt);
+            particle.apply_bounds(environment);
+        }
+    }
+
+    /// Removes every particle whose state is no longer finite, such as after an unbounded force,
+    /// as such particles cannot be drawn and would spread their state to any they collide with.
+    /// The first removal is logged. Returns the number of particles removed
+    pub fn sanitize(&mut self) -> usize {
+        let before = self.particles.len();
+        self.particles.retain(Particle::is_finite);
+        let removed = before.saturating_sub(self.particles.len());
+        if removed > 0 {
+            if self.non_finite_count == 0 {
+                warn!("Removed {removed} particles whose state was no longer finite");
+            }
+            self.non_finite_count = self.non_finite_count.saturating_add(removed);
+        }
+        removed
+    }
+
+    /// Returns the number of particles removed so far for having non-finite state
+    pub const fn non_finite_count(&self) -> usize {
+        self.non_finite_count
+    }
+
+    /// Accelerates every particle towards all others by `dt` seconds of gravitation
+    #[expect(
+        clippy::float_arithmetic,
+        reason = "Gravitation changes continuous velocities"
+    )]
+    pub fn attract(&mut self, gravitation: Gravitation, dt: f32) {
+        let bodies: Vec<_> = self
+            .particles
+            .iter()
+            .map(|particle| (particle.position, particle.mass))
+            .collect();
+        for (particle, acceleration) in self
+            .particles
+            .iter_mut()
+            .zip(gravitation.accelerations(&bodies))
+        {
+            for (velocity, component) in particle.velocity.iter_mut().zip(acceleration) {
+                *velocity += component * dt;
+            }
+        }
+    }
+
+    /// Resolves collisions between every pair of overlapping particles, and then between the
+    /// particles and the obstacles, all bouncing with the given restitution
+    #[expect(
+        clippy::float_arithmetic,
+        reason = "Particles collide at continuous distances"
+    )]
+    pub fn resolve_collisions(&mut self, restitution: RestitutionModel) {
+        self.grid
+            .rebuild(self.particles.iter().map(|particle| particle.position));
+        // Every overlapping pair lies within twice the largest radius of each other
+        let reach = 2.0
+            * self
+                .particles
+                .iter()
+                .map(|particle| particle.radius)
+                .fold(0.0, f32::max);
+        for (lower, higher) in self.grid.pairs(reach) {
+            let (head, tail) = self.particles.split_at_mut(higher);
+            if let (Some(first), Some(second)) = (head.get_mut(lower), tail.first_mut()) {
+                first.collide(second, restitution);
+            }
+        }
+        for collider in &self.colliders {
+            for particle in &mut self.particles {
+                collider.collide(particle, restitution);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::iter;
+
+    use rand::{rngs::StdRng, SeedableRng as _};
+
+    use super::SimulationCore;
+    use crate::app::{
+        grid,
+        particles::{
+            BoundaryMode, Environment, Particle, RestitutionModel, SizeDistribution, RADIUS,
+        },
+    };
+
+    /// Returns an environment without gravity or air resistance, bounded far away from the origin
+    fn environment() -> Environment {
+        Environment {
+            gravity: 0.0,
+            restitution: RestitutionModel::Constant(1.0),
+            floor: -100.0,
+            left: -100.0,
+            right: 100.0,
+            ceiling: 100.0,
+            force_field: None,
+            boundary: BoundaryMode::Bounce,
+            gravitation: None,
+            damping: 0.0,
+        }
+    }
+
+    #[test]
+    fn damping_slows_particles_monotonically() {
+        let environment = Environment {
+            damping: 0.5,
+            ..environment()
+        };
+        let mut core = SimulationCore::new(vec![Particle::new([0.0, 0.0], [1.0, 1.0], RADIUS)]);
+        let speed = |simulation: &SimulationCore| {
+            simulation
+                .particles()
+                .first()
+                .map(|particle| grid::distance_squared(particle.velocity, [0.0, 0.0]))
+        };
+        let mut last = speed(&core);
+        for _ in 0..100_u32 {
+            core.step(1.0 / 60.0, &environment);
+            let current = speed(&core);
+            assert!(current < last);
+            last = current;
+        }
+    }
+
+    #[test]
+    fn no_damping_leaves_velocities_unchanged() {
+        let mut core = SimulationCore::new(vec![Particle::new([0.0, 0.0], [1.0, 1.0], RADIUS)]);
+        core.step(1.0 / 60.0, &environment());
+        assert_eq!(
+            core.particles().first().map(|particle| particle.velocity),
+            Some([1.0, 1.0])
+        );
+    }
+
+    #[test]
+    fn many_particles_stay_finite_over_many_steps() {
+        let environment = Environment {
+            gravity: -9.8,
+            ..environment()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut core = SimulationCore::new(
+            iter::repeat_with(|| {
+                Particle::random(&mut rng, [100.0, 100.0], SizeDistribution::default())
+            })
+            .take(10_000)
+            .collect(),
+        );
+        for _ in 0..100_u32 {
+            core.step(1.0 / 60.0, &environment);
+        }
+        assert_eq!(core.particles().len(), 10_000);
+        assert!(core.particles().iter().all(Particle::is_finite));
+    }
+
+    #[test]
+    fn particles_with_nan_velocities_are_culled() {
+        let mut core = SimulationCore::new(vec![
+            Particle::new([0.0, 0.0], [f32::NAN, 0.0], RADIUS),
+            Particle::new([10.0, 0.0], [0.0, 0.0], RADIUS),
+        ]);
+        core.step(1.0 / 60.0, &environment());
+        assert_eq!(core.sanitize(), 1);
+        assert_eq!(core.non_finite_count(), 1);
+        assert_eq!(core.particles().len(), 1);
+        assert!(core.particles().iter().all(Particle::is_finite));
+        assert_eq!(core.sanitize(), 0);
+    }
+
+    #[test]
+    fn dropped_particles_fall_half_g_t_squared() {
+        let (gravity, dt, steps) = (9.81, 1.0 / 60.0, 60_u16);
+        let environment = Environment {
+            gravity: -gravity,
+            ..environment()
+        };
+        let mut core = SimulationCore::new(vec![Particle::new([0.0, 0.0], [0.0, 0.0], RADIUS)]);
+        for _ in 0..steps {
+            core.step(dt, &environment);
+        }
+        let time = dt * f32::from(steps);
+        let fallen = -core.particles()[0].position[1];
+        // Semi-implicit Euler accelerates before moving, overshooting by half a step's worth of
+        // the final speed
+        let overshoot = 0.5 * gravity * dt * time;
+        assert!((fallen - 0.5 * gravity * time * time).abs() <= overshoot + 1e-3);
+    }
+}
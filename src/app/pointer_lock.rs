@@ -0,0 +1,176 @@
+//! Capturing the cursor while looking around with the mouse, so that it cannot leave the window
+//! or canvas. On the web, the browser grants or denies the lock asynchronously, so the request
+//! and its outcome are tracked separately
+
+#[cfg(target_arch = "wasm32")]
+use core::cell::Cell;
+use log::{info, warn};
+#[cfg(target_arch = "wasm32")]
+use std::rc::Rc;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::{closure::Closure, JsCast as _};
+use winit::window::{CursorGrabMode, Window};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Whether the cursor is captured
+pub(super) enum PointerLock {
+    /// The cursor moves freely
+    #[default]
+    Unlocked,
+    /// The cursor has been requested to be captured, but the request has not been answered
+    Pending,
+    /// The cursor is captured, and only its relative motion is reported
+    Locked,
+}
+
+impl PointerLock {
+    /// Returns the state after requesting that the cursor be captured. Requests made while
+    /// already pending or locked change nothing
+    pub const fn requested(self) -> Self {
+        match self {
+            Self::Unlocked => Self::Pending,
+            Self::Pending | Self::Locked => self,
+        }
+    }
+
+    /// Returns the state after the platform reports whether the cursor is now captured, whether
+    /// in answer to a request or because the lock was lost, such as by the browser on Escape.
+    /// The report supersedes any pending request
+    pub const fn reported(locked: bool) -> Self {
+        if locked {
+            Self::Locked
+        } else {
+            Self::Unlocked
+        }
+    }
+
+    /// Returns whether the cursor is captured
+    pub const fn is_locked(self) -> bool {
+        matches!(self, Self::Locked)
+    }
+}
+
+/// Asks the window to capture the cursor. Returns whether the cursor was captured immediately,
+/// or `None` if the outcome is reported later. Platforms that cannot lock the cursor in place
+/// confine it to the window instead
+pub(super) fn request(window: &Window) -> Option<bool> {
+    let result = window
+        .set_cursor_grab(CursorGrabMode::Locked)
+        .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined));
+    match result {
+        Ok(()) => {
+            window.set_cursor_visible(false);
+            // The browser answers the request later through `PointerLockEvents`
+            cfg!(not(target_arch = "wasm32")).then_some(true)
+        }
+        Err(err) => {
+            warn!("Failed to capture the cursor: {err}");
+            Some(false)
+        }
+    }
+}
+
+/// Releases the cursor captured by the window
+pub(super) fn release(window: &Window) {
+    if let Err(err) = window.set_cursor_grab(CursorGrabMode::None) {
+        warn!("Failed to release the cursor: {err}");
+    }
+    window.set_cursor_visible(true);
+    info!("Released the cursor");
+}
+
+/// The callback type of the document's pointer lock listeners
+#[cfg(target_arch = "wasm32")]
+type Listener = Closure<dyn FnMut()>;
+
+/// Listens for the browser granting, denying, or revoking pointer lock on the page
+#[cfg(target_arch = "wasm32")]
+pub(super) struct PointerLockEvents {
+    /// Whether the pointer is locked, as of the latest event not yet taken
+    latest: Rc<Cell<Option<bool>>>,
+    /// The document being listened to
+    document: web_sys::Document,
+    /// Records changes to the lock, as `pointerlockchange`
+    change: Listener,
+    /// Records denied requests, as `pointerlockerror`
+    error: Listener,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl PointerLockEvents {
+    /// Starts listening to the page's document, if there is one
+    pub fn new() -> Option<Self> {
+        let document = web_sys::window()?.document()?;
+        let latest = Rc::new(Cell::new(None));
+        let change = {
+            let lock = Rc::clone(&latest);
+            let page = document.clone();
+            Closure::<dyn FnMut()>::new(move || {
+                lock.set(Some(page.pointer_lock_element().is_some()));
+            })
+        };
+        let error = {
+            let lock = Rc::clone(&latest);
+            Closure::<dyn FnMut()>::new(move || lock.set(Some(false)))
+        };
+        for (event, listener) in [("pointerlockchange", &change), ("pointerlockerror", &error)] {
+            if document
+                .add_event_listener_with_callback(event, listener.as_ref().unchecked_ref())
+                .is_err()
+            {
+                warn!("Failed to listen for {event} events");
+            }
+        }
+        Some(Self {
+            latest,
+            document,
+            change,
+            error,
+        })
+    }
+
+    /// Returns whether the pointer is locked, if this changed since the last call
+    pub fn take(&self) -> Option<bool> {
+        self.latest.take()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Drop for PointerLockEvents {
+    fn drop(&mut self) {
+        for (event, listener) in [
+            ("pointerlockchange", &self.change),
+            ("pointerlockerror", &self.error),
+        ] {
+            // The listeners are dropped along with this, so must not be called afterwards
+            drop(
+                self.document
+                    .remove_event_listener_with_callback(event, listener.as_ref().unchecked_ref()),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PointerLock;
+
+    #[test]
+    fn requests_wait_for_the_platform_to_answer() {
+        let pending = PointerLock::Unlocked.requested();
+        assert_eq!(pending, PointerLock::Pending);
+        assert!(!pending.is_locked());
+        assert_eq!(pending.requested(), PointerLock::Pending);
+        assert_eq!(PointerLock::Locked.requested(), PointerLock::Locked);
+    }
+
+    #[test]
+    fn reports_supersede_pending_requests() {
+        assert!(PointerLock::reported(true).is_locked());
+        assert_eq!(PointerLock::reported(false), PointerLock::Unlocked);
+        assert_eq!(
+            PointerLock::reported(false).requested(),
+            PointerLock::Pending
+        );
+    }
+}
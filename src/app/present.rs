@@ -0,0 +1,81 @@
+//! Selection of how frames are presented to the surface
+
+use log::info;
+use wgpu::PresentMode;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// The preferred way of presenting rendered frames
+#[non_exhaustive]
+pub enum PresentPreference {
+    /// Synchronize with the display's refresh rate, never tearing
+    #[default]
+    Fifo,
+    /// Present the most recent frame at each refresh, without tearing but with lower latency
+    Mailbox,
+    /// Present frames as soon as they are rendered, possibly tearing
+    Immediate,
+}
+
+impl PresentPreference {
+    /// Returns the present mode corresponding to this preference
+    const fn mode(self) -> PresentMode {
+        match self {
+            Self::Fifo => PresentMode::Fifo,
+            Self::Mailbox => PresentMode::Mailbox,
+            Self::Immediate => PresentMode::Immediate,
+        }
+    }
+
+    /// Selects the present mode to use out of the `available` ones.
+    /// Falls back to `Fifo` if the preferred mode is unavailable, and to any available mode if
+    /// even `Fifo` is unavailable. Returns `None` only if no modes are available at all
+    pub(super) fn select(self, available: &[PresentMode]) -> Option<PresentMode> {
+        let preferred = self.mode();
+        let selected = if available.contains(&preferred) {
+            Some(preferred)
+        } else if available.contains(&PresentMode::Fifo) {
+            Some(PresentMode::Fifo)
+        } else {
+            available.first().copied()
+        };
+        if let Some(mode) = selected {
+            info!("Selected present mode {mode:?} (preferred {preferred:?})");
+        }
+        selected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wgpu::PresentMode;
+
+    use super::PresentPreference;
+
+    #[test]
+    fn preferred_mode_is_selected_when_available() {
+        let available = [PresentMode::Fifo, PresentMode::Mailbox];
+        assert_eq!(
+            PresentPreference::Mailbox.select(&available),
+            Some(PresentMode::Mailbox)
+        );
+    }
+
+    #[test]
+    fn unavailable_modes_fall_back_to_fifo() {
+        let available = [PresentMode::Immediate, PresentMode::Fifo];
+        assert_eq!(
+            PresentPreference::Mailbox.select(&available),
+            Some(PresentMode::Fifo)
+        );
+    }
+
+    #[test]
+    fn without_fifo_any_mode_is_selected() {
+        let available = [PresentMode::Immediate];
+        assert_eq!(
+            PresentPreference::Mailbox.select(&available),
+            Some(PresentMode::Immediate)
+        );
+        assert_eq!(PresentPreference::Fifo.select(&[]), None);
+    }
+}
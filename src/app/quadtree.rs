@@ -0,0 +1,180 @@
+//! A Barnes–Hut quadtree for approximating long-range forces between many particles
+
+#![expect(
+    clippy::float_arithmetic,
+    reason = "Cells are bounded and centers of mass are accumulated in continuous world coordinates"
+)]
+
+use super::grid;
+
+/// The deepest that the tree subdivides. Points that still share a cell at this depth, such as
+/// several points at exactly the same position, are aggregated into a single leaf
+const MAX_DEPTH: u32 = 32;
+
+/// A square region of the tree, summarizing the masses within it
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    /// The center of the region
+    center: [f32; 2],
+    /// Half the side length of the region
+    half_size: f32,
+    /// The total mass of the points within the region
+    mass: f32,
+    /// The sum of the positions of the points within the region, weighted by their masses
+    moment: [f32; 2],
+    /// The index of the first of the region's four quadrants, which are stored consecutively,
+    /// or `None` if the region is a leaf
+    children: Option<usize>,
+}
+
+impl Node {
+    /// Creates an empty region with the given center and half side length
+    const fn new(center: [f32; 2], half_size: f32) -> Self {
+        Self {
+            center,
+            half_size,
+            mass: 0.0,
+            moment: [0.0, 0.0],
+            children: None,
+        }
+    }
+
+    /// Returns the center of mass of the points within the region
+    fn center_of_mass(&self) -> [f32; 2] {
+        self.moment.map(|component| component / self.mass)
+    }
+
+    /// Adds the given mass at the given position to the region's summary, without placing it in
+    /// any quadrant
+    fn accumulate(&mut self, position: [f32; 2], mass: f32) {
+        self.mass += mass;
+        self.moment[0] += position[0] * mass;
+        self.moment[1] += position[1] * mass;
+    }
+
+    /// Returns the offset of the quadrant containing the given position from the first quadrant
+    fn quadrant(&self, position: [f32; 2]) -> usize {
+        match (position[0] >= self.center[0], position[1] >= self.center[1]) {
+            (false, false) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (true, true) => 3,
+        }
+    }
+}
+
+/// Recursively subdivides space so that distant clusters of points can be treated as single
+/// pseudo-bodies at their center of mass
+pub(super) struct QuadTree {
+    /// Every region of the tree, starting with the root, which covers every point
+    nodes: Vec<Node>,
+}
+
+impl QuadTree {
+    /// Builds a tree containing each of the given bodies, as positions and masses
+    pub fn new(bodies: &[([f32; 2], f32)]) -> Self {
+        let Some(&(first, _)) = bodies.first() else {
+            return Self { nodes: Vec::new() };
+        };
+        let (min, max) = bodies
+            .iter()
+            .fold((first, first), |(min, max), &(position, _)| {
+                (
+                    [min[0].min(position[0]), min[1].min(position[1])],
+                    [max[0].max(position[0]), max[1].max(position[1])],
+                )
+            });
+        let half_size = ((max[0] - min[0]).max(max[1] - min[1]) / 2.0).max(f32::EPSILON);
+        let center = [min[0].midpoint(max[0]), min[1].midpoint(max[1])];
+        let mut tree = Self {
+            nodes: vec![Node::new(center, half_size)],
+        };
+        for &(position, mass) in bodies {
+            tree.insert(position, mass);
+        }
+        tree
+    }
+
+    /// Adds the given mass at the given position, subdividing leaves as needed
+    #[expect(
+        clippy::float_cmp,
+        reason = "Only points at exactly the same position are impossible to separate"
+    )]
+    fn insert(&mut self, position: [f32; 2], mass: f32) {
+        let mut index = 0;
+        for depth in 0..=MAX_DEPTH {
+            let Some(&node) = self.nodes.get(index) else {
+                return;
+            };
+            if let Some(first) = node.children {
+                if let Some(region) = self.nodes.get_mut(index) {
+                    region.accumulate(position, mass);
+                }
+                index = first.saturating_add(node.quadrant(position));
+            } else if node.mass == 0.0 || depth == MAX_DEPTH || node.center_of_mass() == position {
+                // Empty leaves take the point directly, and points that cannot be separated from
+                // the leaf's contents are merged into it
+                if let Some(region) = self.nodes.get_mut(index) {
+                    region.accumulate(position, mass);
+                }
+                return;
+            } else {
+                // The leaf's existing contents move down into the quadrant containing them, and
+                // the new point is then placed on the next iteration
+                let first = self.nodes.len();
+                let quarter = node.half_size / 2.0;
+                for (dx, dy) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+                    self.nodes.push(Node::new(
+                        [node.center[0] + dx * quarter, node.center[1] + dy * quarter],
+                        quarter,
+                    ));
+                }
+                let existing = node.center_of_mass();
+                if let Some(child) = self
+                    .nodes
+                    .get_mut(first.saturating_add(node.quadrant(existing)))
+                {
+                    child.accumulate(existing, node.mass);
+                }
+                if let Some(region) = self.nodes.get_mut(index) {
+                    region.children = Some(first);
+                    region.accumulate(position, mass);
+                }
+                index = first.saturating_add(node.quadrant(position));
+            }
+        }
+    }
+
+    /// Calls `visit` with the position and mass of each body or pseudo-body that acts on a point
+    /// at `target`. Regions whose side length is less than `theta` times their distance from the
+    /// target are treated as a single pseudo-body at their center of mass, so a `theta` of zero
+    /// visits every point individually (merging only coincident points)
+    pub fn visit<F>(&self, target: [f32; 2], theta: f32, mut visit: F)
+    where
+        F: FnMut([f32; 2], f32),
+    {
+        let mut pending = if self.nodes.is_empty() {
+            Vec::new()
+        } else {
+            vec![0]
+        };
+        while let Some(index) = pending.pop() {
+            let Some(node) = self.nodes.get(index) else {
+                continue;
+            };
+            if node.mass == 0.0 {
+                continue;
+            }
+            let center_of_mass = node.center_of_mass();
+            match node.children {
+                Some(first)
+                    if 2.0 * node.half_size
+                        >= theta * grid::distance_squared(target, center_of_mass).sqrt() =>
+                {
+                    pending.extend(first..first.saturating_add(4));
+                }
+                _ => visit(center_of_mass, node.mass),
+            }
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! Copying rendered textures back to the CPU
+
+use core::{error::Error, fmt, iter};
+use std::sync::mpsc;
+
+/// The number of bytes in each texel of a texture that can be read back
+const BYTES_PER_PIXEL: u32 = 4;
+
+#[derive(Debug)]
+/// Errors that may arise from reading a texture back from the GPU
+pub(super) enum ReadbackError {
+    /// The buffer the texture was copied into could not be mapped
+    Map(wgpu::BufferAsyncError),
+    /// The copy did not complete, e.g. because the platform cannot block on the GPU
+    Incomplete,
+}
+
+impl fmt::Display for ReadbackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Map(ref err) => write!(f, "failed to map the readback buffer: {err}"),
+            Self::Incomplete => f.write_str("the copy from the texture did not complete"),
+        }
+    }
+}
+
+impl Error for ReadbackError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            Self::Map(ref err) => Some(err),
+            Self::Incomplete => None,
+        }
+    }
+}
+
+/// Returns the number of bytes in each row of a texture of the given width once padded, as
+/// required when copying textures into buffers
+pub(super) const fn padded_bytes_per_row(width: u32) -> u32 {
+    width
+        .saturating_mul(BYTES_PER_PIXEL)
+        .next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+}
+
+/// Copies the contents of a texture with 4-byte texels, such as RGBA8, into a tightly packed
+/// array of rows, blocking until the copy completes.
+/// The texture must have been created with `wgpu::TextureUsages::COPY_SRC`
+pub(super) fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> Result<Vec<u8>, ReadbackError> {
+    let (width, height) = (texture.width(), texture.height());
+    let padded_row = padded_bytes_per_row(width);
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Texture Readback Buffer"),
+        size: u64::from(padded_row).saturating_mul(u64::from(height)),
+        usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Texture Readback Encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        texture.as_image_copy(),
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row),
+                rows_per_image: Some(height),
+            },
+        },
+        texture.size(),
+    );
+    queue.submit(iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        // The receiver only disappears once the copy is abandoned, so there is nobody to notify
+        drop(sender.send(result));
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver
+        .try_recv()
+        .ok()
+        .ok_or(ReadbackError::Incomplete)?
+        .map_err(ReadbackError::Map)?;
+
+    #[expect(
+        clippy::as_conversions,
+        reason = "`u32` to `usize` is lossless on all supported platforms"
+    )]
+    let (row, padded) = (
+        width.saturating_mul(BYTES_PER_PIXEL) as usize,
+        padded_row as usize,
+    );
+    let pixels = slice
+        .get_mapped_range()
+        .chunks(padded)
+        .flat_map(|padded_pixels| padded_pixels.iter().take(row).copied())
+        .collect();
+    buffer.unmap();
+    Ok(pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::padded_bytes_per_row;
+
+    #[test]
+    fn rows_are_padded_to_the_copy_alignment() {
+        // 4 bytes per pixel, so 101 pixels take 404 bytes
+        assert_eq!(padded_bytes_per_row(101), 512);
+        assert_eq!(padded_bytes_per_row(64), 256);
+        assert_eq!(padded_bytes_per_row(1), 256);
+    }
+}
@@ -0,0 +1,83 @@
+//! Recording of input with the simulation step it arrived at, so that runs can be replayed
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+use winit::keyboard::KeyCode;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+/// An input to the simulation that can be recorded and replayed
+pub(super) enum InputEvent {
+    /// A fresh press of the given key
+    Key(KeyCode),
+    /// A click or touch spawning a particle at the given position on the simulation plane
+    Spawn([f32; 2]),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+/// A run of input, which reproduces the run exactly when replayed from the same seed
+pub(super) struct Recording {
+    /// The seed that the random number generator was restarted with when recording began
+    pub seed: u64,
+    /// Every recorded event, with the index of the step that it took effect before, in order
+    pub events: Vec<(u64, InputEvent)>,
+}
+
+/// Records input events against the step that they take effect before
+pub(super) struct InputRecorder {
+    /// The events recorded so far
+    recording: Recording,
+}
+
+impl InputRecorder {
+    /// Starts a recording of a run from the given seed
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            recording: Recording {
+                seed,
+                events: Vec::new(),
+            },
+        }
+    }
+
+    /// Records an event that takes effect before the step with the given index
+    pub fn record(&mut self, step: u64, event: InputEvent) {
+        self.recording.events.push((step, event));
+    }
+
+    /// Stops recording, returning everything recorded
+    pub fn finish(self) -> Recording {
+        self.recording
+    }
+}
+
+/// Plays back a recording, handing out its events as their steps come up
+pub(super) struct Replay {
+    /// The events that have not been replayed yet, in order
+    pending: VecDeque<(u64, InputEvent)>,
+}
+
+impl Replay {
+    /// Starts replaying the given recording from its first step
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            pending: recording.events.into(),
+        }
+    }
+
+    /// Removes and returns the next event due before the step with the given index, including any
+    /// that were due earlier and missed
+    pub fn next_due(&mut self, step: u64) -> Option<InputEvent> {
+        self.pending
+            .front()
+            .is_some_and(|&(due, _)| due <= step)
+            .then(|| self.pending.pop_front())
+            .flatten()
+            .map(|(_, event)| event)
+    }
+
+    /// Returns whether every event has been replayed
+    pub fn is_finished(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
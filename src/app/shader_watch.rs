@@ -0,0 +1,92 @@
+//! Reloading of shaders from their source files while developing on the desktop, so that they can
+//! be iterated on without rebuilding. Release and web builds only use the embedded sources
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use log::{error, info};
+use pollster::block_on;
+
+/// Returns when the file at the given path was last modified, or `None` if this is unknown
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Watches a shader source file for changes, by checking when it was last modified
+pub(super) struct ShaderWatcher {
+    /// The source file being watched
+    path: PathBuf,
+    /// When the source file was last modified, as of the last check
+    modified: Option<SystemTime>,
+}
+
+impl ShaderWatcher {
+    /// Watches the shader source file at the given path, ignoring its current contents, which are
+    /// expected to already be in use
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            modified: modified_time(path),
+        }
+    }
+
+    /// Returns the new contents of the source file if it was modified since the last check.
+    /// Files that cannot be read, such as when running away from the source tree, are skipped
+    pub fn poll(&mut self) -> Option<String> {
+        let modified = modified_time(&self.path)?;
+        if self.modified == Some(modified) {
+            return None;
+        }
+        self.modified = Some(modified);
+        info!("Reloading shader {}", self.path.display());
+        fs::read_to_string(&self.path)
+            .inspect_err(|err| error!("Failed to read shader {}: {err}", self.path.display()))
+            .ok()
+    }
+}
+
+/// Runs `build`, which creates GPU objects from reloaded shader source, returning what it built
+/// only if the device raised no validation errors meanwhile. Otherwise, logs the error, so that
+/// the caller can keep the objects built from the last good source
+pub(super) fn validated<T, F>(device: &wgpu::Device, build: F) -> Option<T>
+where
+    F: FnOnce() -> T,
+{
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let objects = build();
+    match block_on(device.pop_error_scope()) {
+        None => Some(objects),
+        Some(err) => {
+            error!("Failed to reload shader; keeping the previous pipelines: {err}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validated;
+    use crate::app::device;
+
+    /// Compiles the given WGSL source on the device
+    fn compile(device: &wgpu::Device, source: &str) -> wgpu::ShaderModule {
+        device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Test shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        })
+    }
+
+    #[test]
+    fn broken_shaders_are_not_kept() {
+        let Some((gpu, _)) = device::test_device() else {
+            return;
+        };
+        assert!(validated(&gpu, || compile(&gpu, "fn main() {}")).is_some());
+        assert!(validated(&gpu, || compile(&gpu, "fn main() { let }")).is_none());
+    }
+}
@@ -0,0 +1,525 @@
+//! The physics models that can be simulated, behind a common interface
+
+#[cfg(not(target_arch = "wasm32"))]
+use core::mem;
+#[cfg(all(not(target_arch = "wasm32"), debug_assertions))]
+use std::path::Path;
+use std::sync::Arc;
+
+#[cfg(not(target_arch = "wasm32"))]
+use super::compute::ParticleIntegrator;
+#[cfg(not(target_arch = "wasm32"))]
+use super::particles::RestitutionModel;
+#[cfg(all(not(target_arch = "wasm32"), debug_assertions))]
+use super::shader_watch::{self, ShaderWatcher};
+use super::{
+    app::{create_blended_render_pipeline, create_render_pipeline, Blending},
+    collider::Collider,
+    coloring::{ColoringBinding, Palette},
+    disc::DiscTexture,
+    particles::{Environment, Particle, ParticleSystem},
+};
+
+/// The resources that simulations are created with
+pub(super) struct SimulationContext<'context> {
+    /// The device doing the rendering and computation
+    pub device: &'context Arc<wgpu::Device>,
+    /// The queue of commands for the device
+    pub queue: &'context Arc<wgpu::Queue>,
+    /// The format of the color target that simulations draw into
+    pub format: wgpu::TextureFormat,
+    /// The number of samples per pixel of the targets that simulations draw into
+    pub sample_count: u32,
+    /// The layout of the camera's bind group, which is bound at group 0 while drawing
+    pub camera_layout: &'context wgpu::BindGroupLayout,
+    /// The preferred number of invocations in each workgroup of compute shaders
+    #[cfg(not(target_arch = "wasm32"))]
+    pub workgroup_size: u32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Schemes for advancing the bodies of a simulation through time
+pub(super) enum Integrator {
+    /// Semi-implicit Euler: each body's velocity is accelerated first, and then its position is
+    /// moved along the new velocity
+    #[default]
+    Euler,
+    /// Position Verlet: each body's new position is found from its current and previous
+    /// positions as `x' = 2x - x_prev + a * dt^2`, which stays stable for stiffer forces and
+    /// larger timesteps than Euler
+    Verlet,
+}
+
+impl Integrator {
+    /// Returns the other integrator
+    pub const fn toggled(self) -> Self {
+        match self {
+            Self::Euler => Self::Verlet,
+            Self::Verlet => Self::Euler,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Ways of drawing the bodies of a simulation
+pub(super) enum ParticleStyle {
+    /// Each body is a solid circle with a hard edge
+    #[default]
+    Solid,
+    /// Each body is a quad textured with a disc that fades out towards its edge, blended over
+    /// whatever lies behind it
+    Disc,
+}
+
+impl ParticleStyle {
+    /// Returns the other style
+    pub const fn toggled(self) -> Self {
+        match self {
+            Self::Solid => Self::Disc,
+            Self::Disc => Self::Solid,
+        }
+    }
+
+    /// Returns how the fragments of bodies drawn in this style combine with what lies behind them
+    pub const fn blending(self) -> Blending {
+        match self {
+            Self::Solid => Blending::Opaque,
+            Self::Disc => Blending::Alpha,
+        }
+    }
+}
+
+/// A physics model that can be stepped through time and drawn
+pub(super) trait Simulation {
+    /// Advances the simulation by `dt` seconds within the given environment
+    fn step(&mut self, dt: f32, environment: &Environment);
+
+    /// Brings the simulation's GPU buffers up to date with the steps taken since the last upload,
+    /// ready for drawing. Called once per frame that stepped, rather than after every step
+    fn upload(&mut self, queue: &wgpu::Queue);
+
+    /// Draws the simulation, binding its own pipeline. The camera is already bound at group 0
+    fn draw<'pass>(&'pass self, render_pass: &mut wgpu::RenderPass<'pass>);
+
+    /// Adds a stationary body of the given radius at the given position, replacing the oldest body
+    /// if the simulation is full. Returns `false`, without adding anything, if the simulation does
+    /// not support adding bodies
+    fn spawn(&mut self, _position: [f32; 2], _radius: f32) -> bool {
+        false
+    }
+
+    /// Adds the given bodies to the simulation, as many as fit without replacing any.
+    /// Returns the number added, which is zero if the simulation does not support adding bodies
+    fn add(&mut self, _particles: Vec<Particle>) -> usize {
+        0
+    }
+
+    /// Removes up to `count` of the most recently added bodies from the simulation.
+    /// Returns the number removed, which is zero if the simulation does not support removing
+    /// bodies
+    fn remove(&mut self, _count: usize) -> usize {
+        0
+    }
+
+    /// Sets the size that every body is drawn at, as half its width and height in normalized
+    /// device coordinates, or `None` to draw bodies at their own size.
+    /// Returns `false`, without resizing, if the simulation does not support this
+    fn set_point_size(&mut self, _point_size: Option<[f32; 2]>) -> bool {
+        false
+    }
+
+    /// Replaces the static obstacles that the simulation's bodies bounce off.
+    /// Returns `false`, without replacing them, if the simulation does not support obstacles
+    fn set_colliders(&mut self, _colliders: &[Collider]) -> bool {
+        false
+    }
+
+    /// Switches the scheme used to advance the simulation through time.
+    /// Returns `false`, without switching, if the simulation does not support the scheme
+    fn set_integrator(&mut self, integrator: Integrator) -> bool {
+        integrator == Integrator::Euler
+    }
+
+    /// Switches between coloring bodies by how fast they move and coloring them flatly.
+    /// Returns `false`, without switching, if the simulation does not support coloring by speed
+    fn set_velocity_color_enabled(&mut self, enabled: bool) -> bool {
+        !enabled
+    }
+
+    /// Sets the colors that the bodies of the simulation are drawn with.
+    /// Returns `false`, without changing anything, if the simulation draws with fixed colors
+    fn set_palette(&mut self, palette: Palette) -> bool {
+        palette == Palette::default()
+    }
+
+    /// Switches how the bodies of the simulation are drawn.
+    /// Returns `false`, without switching, if the simulation does not support the style
+    fn set_particle_style(&mut self, style: ParticleStyle) -> bool {
+        style == ParticleStyle::Solid
+    }
+
+    /// Rebuilds the simulation's pipelines for the given context, such as after the number of
+    /// samples per pixel of the targets that it draws into changes
+    fn rebuild_pipelines(&mut self, context: &SimulationContext<'_>);
+
+    /// Rebuilds the simulation's pipelines from its shader source files if they changed, when
+    /// developing on the desktop. Pipelines that fail to build are kept as they were
+    fn reload_shaders(&mut self, _context: &SimulationContext<'_>) {}
+
+    /// Returns the point masses making up the simulation, for taking snapshots
+    fn particles(&self) -> &[Particle] {
+        &[]
+    }
+
+    /// Returns the number of bodies removed so far for having non-finite state
+    fn non_finite_count(&self) -> usize {
+        0
+    }
+}
+
+/// Creates the pipelines drawing particles from the given particle shader: as solid circles, and
+/// as blended soft discs
+fn create_particle_pipelines(
+    context: &SimulationContext<'_>,
+    coloring: &ColoringBinding,
+    disc: &DiscTexture,
+    shader: &wgpu::ShaderModule,
+) -> (wgpu::RenderPipeline, wgpu::RenderPipeline) {
+    let pipeline = create_render_pipeline(
+        context.device,
+        context.format,
+        &[context.camera_layout, coloring.layout()],
+        shader,
+        &ParticleSystem::buffer_layouts(),
+        wgpu::PrimitiveTopology::TriangleList,
+        context.sample_count,
+    );
+    let disc_pipeline = create_blended_render_pipeline(
+        context.device,
+        context.format,
+        &[context.camera_layout, coloring.layout(), disc.layout()],
+        shader,
+        &ParticleSystem::buffer_layouts(),
+        wgpu::PrimitiveTopology::TriangleList,
+        context.sample_count,
+        ("fs_disc", ParticleStyle::Disc.blending()),
+    );
+    (pipeline, disc_pipeline)
+}
+
+/// Independent particles that fall under gravity, bounce off the floor, and collide with each other
+pub(super) struct ParticleSim {
+    /// The particles, and their GPU-side storage
+    system: ParticleSystem,
+    /// Integrates the particles on the GPU
+    #[cfg(not(target_arch = "wasm32"))]
+    integrator: ParticleIntegrator,
+    /// The restitution of the steps integrated on the GPU since the particles were last read
+    /// back, whose collisions are yet to be resolved on the CPU, or `None` if the CPU-side
+    /// particles are up to date
+    #[cfg(not(target_arch = "wasm32"))]
+    unsettled: Option<RestitutionModel>,
+    /// Whether the CPU-side particles changed since they were last uploaded
+    #[cfg(not(target_arch = "wasm32"))]
+    stale: bool,
+    /// The device the particles are integrated on, and their buffers are created on
+    device: Arc<wgpu::Device>,
+    /// The queue that spawned particles are written through
+    queue: Arc<wgpu::Queue>,
+    /// How the particles are colored
+    coloring: ColoringBinding,
+    /// The pipeline drawing the particles as solid circles
+    pipeline: wgpu::RenderPipeline,
+    /// The texture that particles are drawn with in the disc style
+    disc: DiscTexture,
+    /// The pipeline drawing the particles as blended soft discs
+    disc_pipeline: wgpu::RenderPipeline,
+    /// How the particles are drawn
+    style: ParticleStyle,
+    /// Watches the particle shader's source file, to rebuild the pipelines when it changes
+    #[cfg(all(not(target_arch = "wasm32"), debug_assertions))]
+    shader_watcher: ShaderWatcher,
+}
+
+impl ParticleSim {
+    /// Creates a simulation of the given particles, which may hold up to `limit` particles in
+    /// total
+    pub fn new(context: &SimulationContext<'_>, particles: Vec<Particle>, limit: usize) -> Self {
+        let device = context.device;
+        let system = ParticleSystem::new(device, context.queue, particles, limit);
+        let coloring = ColoringBinding::new(device);
+        let disc = DiscTexture::new(device, context.queue);
+        let shader = device.create_shader_module(wgpu::include_wgsl!("particle.wgsl"));
+        let (pipeline, disc_pipeline) =
+            create_particle_pipelines(context, &coloring, &disc, &shader);
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            integrator: ParticleIntegrator::new(device, system.buffers(), context.workgroup_size),
+            #[cfg(not(target_arch = "wasm32"))]
+            unsettled: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            stale: false,
+            system,
+            device: Arc::clone(device),
+            queue: Arc::clone(context.queue),
+            coloring,
+            pipeline,
+            disc,
+            disc_pipeline,
+            style: ParticleStyle::default(),
+            #[cfg(all(not(target_arch = "wasm32"), debug_assertions))]
+            shader_watcher: ShaderWatcher::new(Path::new(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/src/app/particle.wgsl"
+            ))),
+        }
+    }
+
+    /// Returns the particles, and their GPU-side storage, which are uploaded again on the next
+    /// `upload` in case they are changed
+    pub fn system_mut(&mut self) -> &mut ParticleSystem {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.settle();
+            self.stale = true;
+        }
+        &mut self.system
+    }
+
+    /// Brings the CPU-side particles up to date with any steps integrated on the GPU since they
+    /// were last read back, blocking until the copy completes, and then resolves the collisions
+    /// of those steps
+    #[cfg(not(target_arch = "wasm32"))]
+    fn settle(&mut self) {
+        if let Some(restitution) = self.unsettled.take() {
+            self.system.download(&self.device, &self.queue);
+            self.system.core_mut().resolve_collisions(restitution);
+            self.system.sanitize();
+            self.stale = true;
+        }
+    }
+}
+
+impl Simulation for ParticleSim {
+    fn step(&mut self, dt: f32, environment: &Environment) {
+        // Where supported, integration happens on the GPU, and the results are read back only
+        // once per frame, on `upload`, to resolve collisions on the CPU. Attraction between
+        // particles needs their latest positions every step, so with it, the whole step happens
+        // on the CPU, as it does everywhere else
+        #[cfg(not(target_arch = "wasm32"))]
+        if environment.gravitation.is_none() {
+            if mem::take(&mut self.stale) {
+                self.system.upload(&self.queue);
+            }
+            self.integrator.step(
+                &self.device,
+                &self.queue,
+                dt,
+                environment,
+                (self.system.len(), self.system.flipped()),
+            );
+            self.system.swap_buffers();
+            self.unsettled = Some(environment.restitution);
+            return;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.settle();
+            self.stale = true;
+        }
+        self.system.core_mut().step(dt, environment);
+        self.system.sanitize();
+    }
+
+    fn upload(&mut self, queue: &wgpu::Queue) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.settle();
+            if mem::take(&mut self.stale) {
+                self.system.upload(queue);
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        self.system.upload(queue);
+        self.coloring.update(queue, self.system.particles());
+    }
+
+    fn draw<'pass>(&'pass self, render_pass: &mut wgpu::RenderPass<'pass>) {
+        match self.style {
+            ParticleStyle::Solid => render_pass.set_pipeline(&self.pipeline),
+            ParticleStyle::Disc => {
+                render_pass.set_pipeline(&self.disc_pipeline);
+                render_pass.set_bind_group(2, self.disc.bind_group(), &[]);
+            }
+        }
+        render_pass.set_bind_group(1, self.coloring.bind_group(), &[]);
+        self.system.draw(render_pass);
+    }
+
+    fn spawn(&mut self, position: [f32; 2], radius: f32) -> bool {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.settle();
+        #[cfg(not(target_arch = "wasm32"))]
+        let capacity = self.system.capacity();
+        let spawned = self.system.spawn(
+            &self.device,
+            &self.queue,
+            Particle::new(position, [0.0, 0.0], radius),
+        );
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.system.capacity() != capacity {
+            self.integrator.rebind(&self.device, self.system.buffers());
+        }
+        spawned
+    }
+
+    fn add(&mut self, particles: Vec<Particle>) -> usize {
+        if particles.is_empty() {
+            return 0;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        self.settle();
+        #[cfg(not(target_arch = "wasm32"))]
+        let capacity = self.system.capacity();
+        let added = self.system.add(&self.device, &self.queue, particles);
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.system.capacity() != capacity {
+            self.integrator.rebind(&self.device, self.system.buffers());
+        }
+        added
+    }
+
+    fn remove(&mut self, count: usize) -> usize {
+        self.system.remove(count)
+    }
+
+    fn set_colliders(&mut self, colliders: &[Collider]) -> bool {
+        self.system.core_mut().set_colliders(colliders);
+        true
+    }
+
+    fn set_velocity_color_enabled(&mut self, enabled: bool) -> bool {
+        self.coloring.set_by_velocity(enabled);
+        self.coloring.update(&self.queue, self.system.particles());
+        true
+    }
+
+    fn set_point_size(&mut self, point_size: Option<[f32; 2]>) -> bool {
+        self.coloring.set_point_size(point_size);
+        true
+    }
+
+    fn set_palette(&mut self, palette: Palette) -> bool {
+        self.coloring.set_palette(palette);
+        self.coloring.update(&self.queue, self.system.particles());
+        true
+    }
+
+    fn set_particle_style(&mut self, style: ParticleStyle) -> bool {
+        self.style = style;
+        true
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), debug_assertions))]
+    fn reload_shaders(&mut self, context: &SimulationContext<'_>) {
+        let Some(source) = self.shader_watcher.poll() else {
+            return;
+        };
+        if let Some((pipeline, disc_pipeline)) = shader_watch::validated(context.device, || {
+            let shader = context
+                .device
+                .create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: Some("particle.wgsl"),
+                    source: wgpu::ShaderSource::Wgsl(source.into()),
+                });
+            create_particle_pipelines(context, &self.coloring, &self.disc, &shader)
+        }) {
+            self.pipeline = pipeline;
+            self.disc_pipeline = disc_pipeline;
+        }
+    }
+
+    fn rebuild_pipelines(&mut self, context: &SimulationContext<'_>) {
+        let shader = context
+            .device
+            .create_shader_module(wgpu::include_wgsl!("particle.wgsl"));
+        (self.pipeline, self.disc_pipeline) =
+            create_particle_pipelines(context, &self.coloring, &self.disc, &shader);
+    }
+
+    fn particles(&self) -> &[Particle] {
+        self.system.particles()
+    }
+
+    fn non_finite_count(&self) -> usize {
+        self.system.non_finite_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(target_arch = "wasm32"))]
+    use std::sync::Arc;
+
+    use super::ParticleStyle;
+    #[cfg(not(target_arch = "wasm32"))]
+    use super::{ParticleSim, Simulation as _, SimulationContext};
+    #[cfg(not(target_arch = "wasm32"))]
+    use crate::app::{
+        camera::CameraBinding,
+        device,
+        particles::{BoundaryMode, Environment, Particle, RestitutionModel, RADIUS},
+    };
+
+    #[test]
+    fn discs_are_alpha_blended() {
+        assert_eq!(
+            ParticleStyle::Disc.blending().state(),
+            wgpu::BlendState::ALPHA_BLENDING
+        );
+        assert_eq!(
+            ParticleStyle::Solid.blending().state(),
+            wgpu::BlendState::REPLACE
+        );
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn each_step_on_the_gpu_flips_the_current_buffer() {
+        let Some((gpu, queue)) =
+            device::test_device().map(|(gpu, queue)| (Arc::new(gpu), Arc::new(queue)))
+        else {
+            return;
+        };
+        let camera = CameraBinding::new(&gpu, glam::Mat4::IDENTITY.to_cols_array_2d());
+        let mut simulation = ParticleSim::new(
+            &SimulationContext {
+                device: &gpu,
+                queue: &queue,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                sample_count: 1,
+                camera_layout: camera.layout(),
+                workgroup_size: 64,
+            },
+            vec![Particle::new([0.0, 0.0], [1.0, 0.0], RADIUS)],
+            16,
+        );
+        let environment = Environment {
+            gravity: 0.0,
+            restitution: RestitutionModel::Constant(1.0),
+            floor: -1.0,
+            left: -1.0,
+            right: 1.0,
+            ceiling: 1.0,
+            force_field: None,
+            boundary: BoundaryMode::Bounce,
+            gravitation: None,
+            damping: 0.0,
+        };
+        assert!(!simulation.system.flipped());
+        simulation.step(1.0 / 60.0, &environment);
+        assert!(simulation.system.flipped());
+        simulation.step(1.0 / 60.0, &environment);
+        assert!(!simulation.system.flipped());
+    }
+}
@@ -0,0 +1,145 @@
+//! Arrangements of the particles that the particle simulation starts with
+
+#![expect(
+    clippy::float_arithmetic,
+    reason = "Starting positions are laid out in continuous world coordinates"
+)]
+
+use core::{f32::consts::TAU, iter};
+
+use rand::Rng;
+
+use super::particles::{Particle, SizeDistribution};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// How the particles of the particle simulation are arranged when it starts. Every pattern fills
+/// the world, whatever its aspect ratio
+#[non_exhaustive]
+pub enum SpawnPattern {
+    /// Particles at uniformly random positions, moving with uniformly random velocities
+    #[default]
+    RandomUniform,
+    /// Particles at rest in evenly spaced rows and columns
+    Grid,
+    /// Particles at rest, evenly spaced around a circle
+    Circle,
+    /// Two streams of particles at random positions on either side, heading towards each other
+    TwoStreams,
+}
+
+/// The fraction of the view that structured patterns span, leaving a margin around the edges
+const FILL: f32 = 0.8;
+
+/// The speed at which each of the two streams moves, in meters per second
+const STREAM_SPEED: f32 = 0.5;
+
+impl SpawnPattern {
+    /// Every pattern, in the order they are offered
+    pub(super) const ALL: [Self; 4] = [
+        Self::RandomUniform,
+        Self::Grid,
+        Self::Circle,
+        Self::TwoStreams,
+    ];
+
+    /// Returns `count` particles arranged in this pattern within a world centered on the origin
+    /// with the given half width and height, with radii drawn from the given distribution
+    #[expect(
+        clippy::as_conversions,
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss,
+        reason = "Particle counts are small enough to be represented exactly"
+    )]
+    pub(super) fn spawn<R>(
+        self,
+        count: usize,
+        half_extents: [f32; 2],
+        rng: &mut R,
+        sizes: SizeDistribution,
+    ) -> Vec<Particle>
+    where
+        R: Rng,
+    {
+        let [half_width, half_height] = half_extents;
+        match self {
+            Self::RandomUniform => iter::repeat_with(|| Particle::random(rng, half_extents, sizes))
+                .take(count)
+                .collect(),
+            Self::Grid => {
+                let columns = (count as f32).sqrt().ceil().max(1.0) as usize;
+                let rows = count.div_ceil(columns);
+                // Each particle sits at the center of its cell
+                let spacing = [
+                    2.0 * half_width * FILL / columns as f32,
+                    2.0 * half_height * FILL / rows.max(1) as f32,
+                ];
+                (0..rows)
+                    .flat_map(|row| (0..columns).map(move |column| (row, column)))
+                    .take(count)
+                    .map(|(row, column)| {
+                        let position = [
+                            -half_width * FILL + (column as f32 + 0.5) * spacing[0],
+                            half_height * FILL - (row as f32 + 0.5) * spacing[1],
+                        ];
+                        Particle::new(position, [0.0, 0.0], sizes.sample(rng))
+                    })
+                    .collect()
+            }
+            Self::Circle => {
+                let radius = FILL * half_width.min(half_height);
+                (0..count)
+                    .map(|index| {
+                        let angle = TAU * index as f32 / count as f32;
+                        let position = [radius * angle.cos(), radius * angle.sin()];
+                        Particle::new(position, [0.0, 0.0], sizes.sample(rng))
+                    })
+                    .collect()
+            }
+            Self::TwoStreams => (0..count)
+                .map(|index| {
+                    // Alternate particles join the stream on the left, heading right, and the
+                    // stream on the right, heading left
+                    let side = if index.is_multiple_of(2) { -1.0 } else { 1.0 };
+                    let position = [
+                        side * rng.gen_range(half_width * FILL / 2.0..=half_width * FILL),
+                        half_height * rng.gen_range(-FILL / 2.0..=FILL / 2.0),
+                    ];
+                    Particle::new(position, [-side * STREAM_SPEED, 0.0], sizes.sample(rng))
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rand::{rngs::StdRng, SeedableRng as _};
+
+    use super::SpawnPattern;
+    use crate::app::particles::SizeDistribution;
+
+    #[test]
+    fn grid_of_a_hundred_is_ten_by_ten() {
+        let particles = SpawnPattern::Grid.spawn(
+            100,
+            [2.0, 1.0],
+            &mut StdRng::seed_from_u64(0),
+            SizeDistribution::default(),
+        );
+        let positions: HashSet<_> = particles
+            .iter()
+            .map(|particle| particle.position.map(f32::to_bits))
+            .collect();
+        let columns: HashSet<_> = positions.iter().map(|&[x, _]| x).collect();
+        let rows: HashSet<_> = positions.iter().map(|&[_, y]| y).collect();
+        assert_eq!(positions.len(), 100);
+        assert_eq!((columns.len(), rows.len()), (10, 10));
+        assert!(particles.iter().all(|particle| {
+            let [x, y] = particle.position;
+            x.abs() < 2.0 && y.abs() < 1.0
+        }));
+    }
+}
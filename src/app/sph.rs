@@ -0,0 +1,317 @@
+//! A fluid simulated with smoothed particle hydrodynamics, in which each particle carries a share
+//! of the fluid, and the fluid's density, pressure, and viscosity at each particle are estimated
+//! from its neighbors. The fluid is stepped entirely on the CPU, so the number of particles is
+//! capped to keep it interactive
+
+#![expect(
+    clippy::float_arithmetic,
+    reason = "Density, pressure, and viscosity are estimated with floating-point smoothing kernels"
+)]
+
+use core::f32::consts::PI;
+
+use super::{
+    coloring::Palette,
+    grid::SpatialGrid,
+    particles::{Environment, Particle, RADIUS},
+    simulation::{ParticleSim, ParticleStyle, Simulation, SimulationContext},
+};
+
+/// The most particles that the fluid is made of
+const MAX_FLUID_PARTICLES: usize = 1200;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// The tunable properties of the fluid
+pub(super) struct FluidParameters {
+    /// How far each particle's share of the fluid is smeared out, in meters. Only particles this
+    /// close influence each other
+    pub smoothing_radius: f32,
+    /// The density that the fluid settles at, in mass per unit area
+    pub rest_density: f32,
+    /// How strongly the fluid resists being compressed past, or stretched below, its rest
+    /// density
+    pub stiffness: f32,
+    /// How strongly neighboring particles are pulled towards moving together
+    pub viscosity: f32,
+}
+
+impl Default for FluidParameters {
+    /// Particles of unit mass, which settle about a diameter apart, each influencing the
+    /// neighbors within a couple of diameters
+    fn default() -> Self {
+        Self {
+            smoothing_radius: 4.0 * RADIUS,
+            rest_density: 1.0 / (2.0 * RADIUS * 2.0 * RADIUS),
+            stiffness: 1.0,
+            viscosity: 1.0,
+        }
+    }
+}
+
+/// The two-dimensional smoothing kernels of Müller et al., for a given smoothing radius, which
+/// all vanish at and beyond the smoothing radius
+struct Kernels {
+    /// The smoothing radius
+    radius: f32,
+    /// The scale of the density kernel
+    density: f32,
+    /// The scale of the gradient of the pressure kernel
+    pressure: f32,
+    /// The scale of the Laplacian of the viscosity kernel
+    viscosity: f32,
+}
+
+impl Kernels {
+    /// Creates the kernels for the given smoothing radius
+    fn new(radius: f32) -> Self {
+        Self {
+            radius,
+            density: 4.0 / (PI * radius.powi(8)),
+            pressure: -30.0 / (PI * radius.powi(5)),
+            viscosity: 40.0 / (PI * radius.powi(5)),
+        }
+    }
+
+    /// Returns the weight of a neighbor at the given squared distance towards the density
+    fn density(&self, distance_squared: f32) -> f32 {
+        let remaining = self.radius * self.radius - distance_squared;
+        if remaining > 0.0 {
+            self.density * remaining * remaining * remaining
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the change in the pressure kernel with distance, at the given distance
+    fn pressure_gradient(&self, distance: f32) -> f32 {
+        let remaining = (self.radius - distance).max(0.0);
+        self.pressure * remaining * remaining
+    }
+
+    /// Returns the Laplacian of the viscosity kernel at the given distance
+    fn viscosity_laplacian(&self, distance: f32) -> f32 {
+        self.viscosity * (self.radius - distance).max(0.0)
+    }
+}
+
+/// Returns the density of the fluid at each of the given particles, summed over the neighbors
+/// found through the grid, which must hold the particles' current positions
+fn fluid_densities(particles: &[Particle], grid: &SpatialGrid, smoothing_radius: f32) -> Vec<f32> {
+    let kernels = Kernels::new(smoothing_radius);
+    particles
+        .iter()
+        .map(|particle| {
+            grid.neighbors(particle.position, smoothing_radius)
+                .filter_map(|index| particles.get(index))
+                .map(|neighbor| {
+                    let offset = [
+                        particle.position[0] - neighbor.position[0],
+                        particle.position[1] - neighbor.position[1],
+                    ];
+                    neighbor.mass * kernels.density(offset[0] * offset[0] + offset[1] * offset[1])
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// A fluid made of particles, drawn as the particle simulation draws its particles
+pub(super) struct SphSim {
+    /// Holds and draws the fluid's particles. Only its rendering is used, as the fluid's physics
+    /// replace its own
+    particles: ParticleSim,
+    /// Buckets the particles by position to find their neighbors
+    grid: SpatialGrid,
+    /// The properties of the fluid
+    parameters: FluidParameters,
+}
+
+impl SphSim {
+    /// Creates a block of up to `MAX_FLUID_PARTICLES` of `count` particles of fluid at rest,
+    /// packed at the rest density into the bottom left of a world centered on the origin with the
+    /// given half width and height
+    #[expect(
+        clippy::as_conversions,
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_sign_loss,
+        reason = "Particle counts are small enough to be represented exactly"
+    )]
+    pub fn new(
+        context: &SimulationContext<'_>,
+        count: usize,
+        half_extents: [f32; 2],
+        parameters: FluidParameters,
+    ) -> Self {
+        let capped = count.min(MAX_FLUID_PARTICLES);
+        // Unit masses spaced so that each occupies its share of the fluid at rest
+        let spacing = parameters.rest_density.recip().sqrt();
+        let columns = (capped as f32 * 4.0 / 3.0).sqrt().ceil().max(1.0) as usize;
+        let rows = capped.div_ceil(columns);
+        let particles = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (row, column)))
+            .take(capped)
+            .map(|(row, column)| {
+                let position = [
+                    (column as f32 + 0.5).mul_add(spacing, -half_extents[0]),
+                    (row as f32 + 0.5).mul_add(spacing, -half_extents[1]),
+                ];
+                Particle::new(position, [0.0, 0.0], RADIUS)
+            })
+            .collect();
+        Self {
+            particles: ParticleSim::new(context, particles, capped),
+            grid: SpatialGrid::new(parameters.smoothing_radius),
+            parameters,
+        }
+    }
+}
+
+impl Simulation for SphSim {
+    fn step(&mut self, dt: f32, environment: &Environment) {
+        let parameters = self.parameters;
+        let kernels = Kernels::new(parameters.smoothing_radius);
+        let particles = self.particles.system_mut().core_mut().particles_mut();
+        self.grid
+            .rebuild(particles.iter().map(|particle| particle.position));
+        let densities = fluid_densities(particles, &self.grid, parameters.smoothing_radius);
+        let pressures: Vec<_> = densities
+            .iter()
+            .map(|density| parameters.stiffness * (density - parameters.rest_density))
+            .collect();
+        let accelerations: Vec<_> = particles
+            .iter()
+            .zip(densities.iter().zip(&pressures))
+            .enumerate()
+            .map(|(index, (particle, (&density, &pressure)))| {
+                let mut force = [0.0, 0.0];
+                for neighbor in self
+                    .grid
+                    .neighbors(particle.position, parameters.smoothing_radius)
+                    .filter(|&neighbor| neighbor != index)
+                {
+                    let (Some(other), Some(&other_density), Some(&other_pressure)) = (
+                        particles.get(neighbor),
+                        densities.get(neighbor),
+                        pressures.get(neighbor),
+                    ) else {
+                        continue;
+                    };
+                    let offset = [
+                        particle.position[0] - other.position[0],
+                        particle.position[1] - other.position[1],
+                    ];
+                    let distance = (offset[0] * offset[0] + offset[1] * offset[1]).sqrt();
+                    // Pressure pushes apart along the line between the particles, shared
+                    // symmetrically so that momentum is conserved
+                    let push = if distance > 0.0 {
+                        -other.mass * (pressure + other_pressure) / (2.0 * other_density)
+                            * kernels.pressure_gradient(distance)
+                            / distance
+                    } else {
+                        0.0
+                    };
+                    // Viscosity pulls the particles towards moving together
+                    let drag = parameters.viscosity * other.mass / other_density
+                        * kernels.viscosity_laplacian(distance);
+                    for (((total, component), velocity), other_velocity) in force
+                        .iter_mut()
+                        .zip(offset)
+                        .zip(particle.velocity)
+                        .zip(other.velocity)
+                    {
+                        *total += push * component + drag * (other_velocity - velocity);
+                    }
+                }
+                let external = environment.acceleration(particle);
+                [
+                    force[0] / density + external[0],
+                    force[1] / density + external[1],
+                ]
+            })
+            .collect();
+        let damping = environment.damping_factor(dt);
+        for (particle, acceleration) in particles.iter_mut().zip(accelerations) {
+            particle.velocity = particle.velocity.map(|component| component * damping);
+            particle.integrate(acceleration, dt);
+            particle.apply_bounds(environment);
+        }
+        self.particles.system_mut().sanitize();
+    }
+
+    fn upload(&mut self, queue: &wgpu::Queue) {
+        self.particles.upload(queue);
+    }
+
+    fn draw<'pass>(&'pass self, render_pass: &mut wgpu::RenderPass<'pass>) {
+        self.particles.draw(render_pass);
+    }
+
+    fn set_velocity_color_enabled(&mut self, enabled: bool) -> bool {
+        self.particles.set_velocity_color_enabled(enabled)
+    }
+
+    fn set_point_size(&mut self, point_size: Option<[f32; 2]>) -> bool {
+        self.particles.set_point_size(point_size)
+    }
+
+    fn set_palette(&mut self, palette: Palette) -> bool {
+        self.particles.set_palette(palette)
+    }
+
+    fn set_particle_style(&mut self, style: ParticleStyle) -> bool {
+        self.particles.set_particle_style(style)
+    }
+
+    fn rebuild_pipelines(&mut self, context: &SimulationContext<'_>) {
+        self.particles.rebuild_pipelines(context);
+    }
+
+    fn reload_shaders(&mut self, context: &SimulationContext<'_>) {
+        self.particles.reload_shaders(context);
+    }
+
+    fn particles(&self) -> &[Particle] {
+        self.particles.particles()
+    }
+
+    fn non_finite_count(&self) -> usize {
+        self.particles.non_finite_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fluid_densities, FluidParameters};
+    use crate::app::{
+        grid::SpatialGrid,
+        particles::{Particle, RADIUS},
+    };
+
+    /// The number of particles along each side of the test's block of fluid
+    const SIDE: u16 = 21;
+
+    /// The index of the particle in the middle of the block, ten rows and columns in from its
+    /// corner, which is surrounded by fluid on every side
+    const MIDDLE: usize = 220;
+
+    #[test]
+    fn uniformly_packed_fluid_is_near_its_rest_density() {
+        let parameters = FluidParameters::default();
+        let spacing = parameters.rest_density.recip().sqrt();
+        let particles: Vec<_> = (0..SIDE)
+            .flat_map(|row| (0..SIDE).map(move |column| (row, column)))
+            .map(|(row, column)| {
+                let position = [f32::from(column) * spacing, f32::from(row) * spacing];
+                Particle::new(position, [0.0, 0.0], RADIUS)
+            })
+            .collect();
+        let mut grid = SpatialGrid::new(parameters.smoothing_radius);
+        grid.rebuild(particles.iter().map(|particle| particle.position));
+        let densities = fluid_densities(&particles, &grid, parameters.smoothing_radius);
+        let middle = densities
+            .get(MIDDLE)
+            .expect("the block should have a middle");
+        assert!((middle / parameters.rest_density - 1.0).abs() < 0.05);
+    }
+}
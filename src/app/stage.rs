@@ -0,0 +1,213 @@
+//! The stages that each frame is drawn in, such as the scene, the HUD, and the control panel,
+//! which are recorded in order into a single command encoder and submitted together
+
+use core::iter;
+
+use wgpu::{LoadOp, StoreOp};
+
+use super::{
+    bounds::BoundsOverlay, camera::CameraBinding, collider::ColliderOverlay, gpu_timer::GpuTimer,
+    gradient::Gradient, gui::Gui, hud::Hud, simulation::Simulation, trails::Trails,
+};
+
+/// A step in drawing a frame, recording its passes or commands into the frame's encoder
+pub(super) trait RenderStage {
+    /// Records the stage into the encoder, drawing over the frame's output view
+    fn encode(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView);
+}
+
+/// Records every stage into one encoder, in the given order, so that later stages draw over
+/// earlier ones, and submits the result once
+pub(super) fn submit(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    view: &wgpu::TextureView,
+    stages: &[&dyn RenderStage],
+) {
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Render Encoder"),
+    });
+    for stage in stages {
+        stage.encode(&mut encoder, view);
+    }
+    queue.submit(iter::once(encoder.finish()));
+}
+
+/// The stage drawing the background, the simulation, and the overlays over it, in a single render
+/// pass. With trails, this draws into the trails' texture rather than the output
+pub(super) struct Scene<'frame> {
+    /// The multisampled color buffer to render into and resolve from, if anti-aliasing is on
+    pub msaa_view: Option<&'frame wgpu::TextureView>,
+    /// The depth buffer
+    pub depth_view: &'frame wgpu::TextureView,
+    /// The color to clear to, when the background is not drawn otherwise
+    pub background: wgpu::Color,
+    /// The trails to draw into and fade, if trails are on
+    pub trails: Option<&'frame Trails>,
+    /// The gradient to draw as the background, if it is on and there are no trails
+    pub gradient: Option<&'frame Gradient>,
+    /// The camera to draw through
+    pub camera_binding: &'frame CameraBinding,
+    /// The simulation to draw
+    pub simulation: &'frame dyn Simulation,
+    /// The collider overlays to draw over the simulation, in order
+    pub overlays: [&'frame ColliderOverlay; 2],
+    /// The outline of the world's bounds, if shown
+    pub bounds: Option<&'frame BoundsOverlay>,
+    /// The timer to measure the pass with, if timestamp queries are supported
+    pub gpu_timer: Option<&'frame GpuTimer>,
+}
+
+impl RenderStage for Scene<'_> {
+    fn encode(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        let scene_view = self.trails.map_or(view, Trails::view);
+        let (target, resolve_target) = color_targets(self.msaa_view, scene_view);
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target,
+                ops: wgpu::Operations {
+                    load: if self.trails.is_some() {
+                        LoadOp::Load
+                    } else {
+                        LoadOp::Clear(self.background)
+                    },
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: self.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: LoadOp::Clear(1.0),
+                    store: StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: self.gpu_timer.map(GpuTimer::timestamp_writes),
+        });
+        match (self.trails, self.gradient) {
+            (Some(trails), _) => trails.draw_fade(&mut render_pass),
+            (None, Some(gradient)) => gradient.draw(&mut render_pass),
+            // The pass already cleared the output to the flat background
+            (None, None) => {}
+        }
+        render_pass.set_bind_group(0, self.camera_binding.bind_group(), &[]);
+        self.simulation.draw(&mut render_pass);
+        for overlay in self.overlays {
+            overlay.draw(&mut render_pass);
+        }
+        if let Some(bounds) = self.bounds {
+            bounds.draw(&mut render_pass);
+        }
+    }
+}
+
+impl RenderStage for Trails {
+    /// Copies the accumulated trails onto the output
+    fn encode(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        self.blit(encoder, view);
+    }
+}
+
+impl RenderStage for Hud {
+    fn encode(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        self.draw(encoder, view);
+    }
+}
+
+impl RenderStage for Gui {
+    fn encode(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        self.draw(encoder, view);
+    }
+}
+
+impl RenderStage for GpuTimer {
+    /// Resolves the frame's timestamps, which draws nothing, so it should come last
+    fn encode(&self, encoder: &mut wgpu::CommandEncoder, _view: &wgpu::TextureView) {
+        self.resolve(encoder);
+    }
+}
+
+/// Returns the view to render color into and the view to resolve it into, if any.
+/// When multisampling, rendering goes into the multisampled view and is resolved into the output;
+/// otherwise, rendering goes directly into the output
+fn color_targets<'view>(
+    msaa_view: Option<&'view wgpu::TextureView>,
+    output_view: &'view wgpu::TextureView,
+) -> (&'view wgpu::TextureView, Option<&'view wgpu::TextureView>) {
+    msaa_view.map_or((output_view, None), |view| (view, Some(output_view)))
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{cell::RefCell, ptr};
+
+    use super::{color_targets, submit, RenderStage};
+    use crate::app::device;
+
+    /// A stage that only notes its name in a shared log when encoded
+    struct RecordingStage<'log> {
+        /// The name noted in the log
+        name: &'static str,
+        /// The names of the stages encoded so far, in order
+        log: &'log RefCell<Vec<&'static str>>,
+    }
+
+    impl RenderStage for RecordingStage<'_> {
+        fn encode(&self, _: &mut wgpu::CommandEncoder, _: &wgpu::TextureView) {
+            self.log.borrow_mut().push(self.name);
+        }
+    }
+
+    /// Returns a view of a small texture of the given device
+    fn view(device: &wgpu::Device) -> wgpu::TextureView {
+        device
+            .create_texture(&wgpu::TextureDescriptor {
+                label: None,
+                size: wgpu::Extent3d {
+                    width: 4,
+                    height: 4,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8Unorm,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            })
+            .create_view(&wgpu::TextureViewDescriptor::default())
+    }
+
+    #[test]
+    fn frames_resolve_only_when_multisampling() {
+        let Some((device, _)) = device::test_device() else {
+            return;
+        };
+        let output = view(&device);
+        let msaa = view(&device);
+
+        let (direct, unresolved) = color_targets(None, &output);
+        assert!(ptr::eq(direct, &raw const output));
+        assert!(unresolved.is_none());
+
+        let (multisampled, resolved) = color_targets(Some(&msaa), &output);
+        assert!(ptr::eq(multisampled, &raw const msaa));
+        assert!(resolved.is_some_and(|view| ptr::eq(view, &raw const output)));
+    }
+
+    #[test]
+    fn stages_are_encoded_in_the_order_given() {
+        let Some((device, queue)) = device::test_device() else {
+            return;
+        };
+        let output = view(&device);
+        let log = RefCell::new(Vec::new());
+        let stage = |name| RecordingStage { name, log: &log };
+        let (background, scene, hud) = (stage("background"), stage("scene"), stage("hud"));
+        submit(&device, &queue, &output, &[&background, &scene, &hud]);
+        assert_eq!(log.into_inner(), ["background", "scene", "hud"]);
+    }
+}
@@ -0,0 +1,16 @@
+//! Snapshots of the simulation, for saving and restoring it
+
+use serde::{Deserialize, Serialize};
+
+use super::particles::Particle;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+/// The state of the simulation at a point in time, independent of any GPU resources
+pub(super) struct SimulationState {
+    /// Every particle in the simulation
+    pub particles: Vec<Particle>,
+    /// Vertical acceleration applied to every particle. Negative values pull downwards
+    pub gravity: f32,
+    /// Whether the simulation is paused
+    pub paused: bool,
+}
@@ -0,0 +1,77 @@
+//! Visual themes, each setting the background and the colors of particles together
+
+use super::{color, coloring::Palette};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// A coordinated set of colors for the background, the gradient background, and particles
+pub(super) enum Theme {
+    /// White particles over teal, as the simulation starts
+    #[default]
+    Classic,
+    /// Pale particles over deep blue, ramping from navy through cyan to white with speed
+    Ocean,
+    /// Warm particles over a dark red, ramping from deep red through orange to yellow with speed
+    Fire,
+    /// Light gray particles over near black, ramping from dark gray to white with speed
+    Mono,
+}
+
+impl Theme {
+    /// Returns the theme after this one, cycling back to the first
+    pub const fn next(self) -> Self {
+        match self {
+            Self::Classic => Self::Ocean,
+            Self::Ocean => Self::Fire,
+            Self::Fire => Self::Mono,
+            Self::Mono => Self::Classic,
+        }
+    }
+
+    /// Returns the flat background color, in linear space
+    pub fn background(self) -> wgpu::Color {
+        match self {
+            Self::Classic => {
+                let [red, green, blue] = color::TEAL;
+                color::srgb8(red, green, blue)
+            }
+            Self::Ocean => color::srgb8(8, 40, 80),
+            Self::Fire => color::srgb8(40, 8, 4),
+            Self::Mono => color::srgb8(16, 16, 16),
+        }
+    }
+
+    /// Returns the colors at the top and bottom of the gradient background, in linear space
+    pub fn gradient(self) -> (wgpu::Color, wgpu::Color) {
+        match self {
+            Self::Classic => (self.background(), wgpu::Color::BLACK),
+            Self::Ocean => (color::srgb8(30, 110, 170), color::srgb8(2, 10, 30)),
+            Self::Fire => (color::srgb8(120, 30, 10), color::srgb8(10, 0, 0)),
+            Self::Mono => (color::srgb8(80, 80, 80), color::srgb8(0, 0, 0)),
+        }
+    }
+
+    /// Returns the colors that particles are drawn with
+    pub fn palette(self) -> Palette {
+        match self {
+            Self::Classic => Palette::default(),
+            Self::Ocean => Palette {
+                flat: color::srgb8(200, 235, 255),
+                slow: color::srgb8(20, 40, 140),
+                middle: color::srgb8(0, 200, 220),
+                fast: wgpu::Color::WHITE,
+            },
+            Self::Fire => Palette {
+                flat: color::srgb8(255, 200, 120),
+                slow: color::srgb8(140, 10, 0),
+                middle: color::srgb8(255, 120, 0),
+                fast: color::srgb8(255, 240, 80),
+            },
+            Self::Mono => Palette {
+                flat: color::srgb8(220, 220, 220),
+                slow: color::srgb8(60, 60, 60),
+                middle: color::srgb8(150, 150, 150),
+                fast: wgpu::Color::WHITE,
+            },
+        }
+    }
+}
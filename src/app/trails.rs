@@ -0,0 +1,268 @@
+//! Motion trails, made by fading the previous frame towards the background instead of clearing it
+
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt as _;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+/// The color drawn over the previous frame, matching `Fade` in the trails shader
+struct FadeUniform {
+    /// The background color, with the opacity it is drawn over the previous frame with
+    color: [f32; 4],
+}
+
+/// Clamps the opacity the background is drawn over the previous frame with into `[0, 1]`.
+/// NaN, being no opacity at all, clears the frame fully
+pub(super) fn clamp_fade(alpha: f32) -> f32 {
+    if alpha.is_nan() {
+        1.0
+    } else {
+        alpha.clamp(0.0, 1.0)
+    }
+}
+
+/// Returns the texture frames are drawn into and kept in between frames, of the given format and
+/// matching the size of the surface, with a view into it and a bind group exposing it to the blit
+/// pipeline
+fn create_persistent_texture(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+    format: wgpu::TextureFormat,
+    blit_pipeline: &wgpu::RenderPipeline,
+    sampler: &wgpu::Sampler,
+) -> (wgpu::TextureView, wgpu::BindGroup) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Trail Texture"),
+        size: wgpu::Extent3d {
+            width: config.width.max(1),
+            height: config.height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("Trail Blit Bind Group"),
+        layout: &blit_pipeline.get_bind_group_layout(0),
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    });
+    (view, bind_group)
+}
+
+/// A texture that frames accumulate in, along with the pipelines that fade it and copy it onto
+/// the surface
+pub(super) struct Trails {
+    /// The format that frames are drawn in
+    format: wgpu::TextureFormat,
+    /// A view into the texture that frames are drawn into, kept in between frames
+    view: wgpu::TextureView,
+    /// Draws the background over the previous frame, within the main render pass
+    fade_pipeline: wgpu::RenderPipeline,
+    /// Uniform buffer holding the `FadeUniform`
+    fade_buffer: wgpu::Buffer,
+    /// Binds `fade_buffer` to the fade pipeline
+    fade_bind_group: wgpu::BindGroup,
+    /// Copies the persistent texture onto the surface
+    blit_pipeline: wgpu::RenderPipeline,
+    /// Samples the persistent texture while copying it
+    sampler: wgpu::Sampler,
+    /// Binds the persistent texture and `sampler` to the blit pipeline
+    blit_bind_group: wgpu::BindGroup,
+}
+
+impl Trails {
+    /// Creates trails for a surface with the given configuration, drawn in the given format.
+    /// The fade is drawn within a pass with the given number of samples and depth format
+    pub fn new(
+        device: &wgpu::Device,
+        config: &wgpu::SurfaceConfiguration,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        depth_format: wgpu::TextureFormat,
+    ) -> Self {
+        let shader = device.create_shader_module(wgpu::include_wgsl!("trails.wgsl"));
+        let fade_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Trail Fade Pipeline"),
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_fullscreen",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_fade",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            // The fade lies behind everything drawn after it
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: depth_format,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..wgpu::MultisampleState::default()
+            },
+            multiview: None,
+        });
+        let fade_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Trail Fade Buffer"),
+            contents: bytemuck::bytes_of(&FadeUniform { color: [0.0; 4] }),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let fade_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Trail Fade Bind Group"),
+            layout: &fade_pipeline.get_bind_group_layout(0),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: fade_buffer.as_entire_binding(),
+            }],
+        });
+        let blit_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Trail Blit Pipeline"),
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_fullscreen",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_blit",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Trail Sampler"),
+            ..wgpu::SamplerDescriptor::default()
+        });
+        let (view, blit_bind_group) =
+            create_persistent_texture(device, config, format, &blit_pipeline, &sampler);
+        Self {
+            format,
+            view,
+            fade_pipeline,
+            fade_buffer,
+            fade_bind_group,
+            blit_pipeline,
+            sampler,
+            blit_bind_group,
+        }
+    }
+
+    /// Recreates the persistent texture to match the resized surface, losing any trails
+    pub fn resize(&mut self, device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) {
+        (self.view, self.blit_bind_group) = create_persistent_texture(
+            device,
+            config,
+            self.format,
+            &self.blit_pipeline,
+            &self.sampler,
+        );
+    }
+
+    /// Returns the view of the texture that frames should be drawn into, or resolved into
+    pub const fn view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+
+    /// Sets the background color faded towards, and the opacity it is drawn over the previous
+    /// frame with
+    #[expect(
+        clippy::as_conversions,
+        clippy::cast_possible_truncation,
+        reason = "Color channels lie in [0, 1], well within `f32` precision"
+    )]
+    pub fn set_fade(&self, queue: &wgpu::Queue, background: wgpu::Color, alpha: f32) {
+        let color = [
+            background.r as f32,
+            background.g as f32,
+            background.b as f32,
+            clamp_fade(alpha),
+        ];
+        queue.write_buffer(
+            &self.fade_buffer,
+            0,
+            bytemuck::bytes_of(&FadeUniform { color }),
+        );
+    }
+
+    /// Fades the previous frame towards the background. Must be drawn first in the render pass
+    pub fn draw_fade<'pass>(&'pass self, render_pass: &mut wgpu::RenderPass<'pass>) {
+        render_pass.set_pipeline(&self.fade_pipeline);
+        render_pass.set_bind_group(0, &self.fade_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    /// Copies the persistent texture onto the given view, in its own pass
+    pub fn blit(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Trail Blit Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_pipeline(&self.blit_pipeline);
+        render_pass.set_bind_group(0, &self.blit_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::clamp_fade;
+
+    #[test]
+    #[expect(
+        clippy::float_cmp,
+        reason = "Fades are clamped exactly onto the bounds"
+    )]
+    fn fade_is_clamped_to_the_unit_interval() {
+        assert_eq!(clamp_fade(0.25), 0.25);
+        assert_eq!(clamp_fade(-1.0), 0.0);
+        assert_eq!(clamp_fade(2.0), 1.0);
+        assert_eq!(clamp_fade(f32::NAN), 1.0);
+    }
+}
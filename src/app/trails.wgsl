@@ -0,0 +1,38 @@
+// Shaders for fading the previous frame into motion trails, and copying the result to the screen
+
+struct Fade {
+    // The background color, with the opacity it is drawn over the previous frame with
+    color: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> fade: Fade;
+
+@group(0) @binding(0) var trail_texture: texture_2d<f32>;
+@group(0) @binding(1) var trail_sampler: sampler;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+// Covers the screen with a single triangle, from three vertices without any buffers
+@vertex
+fn vs_fullscreen(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+// Takes the vertex output, even though the fade is uniform, as WebGL requires every output of
+// the vertex stage to be consumed
+@fragment
+fn fs_fade(in: VertexOutput) -> @location(0) vec4<f32> {
+    return fade.color;
+}
+
+@fragment
+fn fs_blit(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(trail_texture, trail_sampler, in.uv);
+}
@@ -1,42 +1,58 @@
 //! A physical simulation with corresponding graphical niceities
 
-#![feature(lint_reasons)]
-
 mod app;
+mod logging;
 
-use crate::app::AppWrapper;
-use log::Level;
+pub use crate::app::{
+    run, AppWrapper, BuilderError, Collider, Emitter, PhysicalSimulation,
+    PhysicalSimulationBuilder, PresentPreference, RedrawPolicy, RestitutionModel, RunError,
+    SimulationConfig, SpawnPattern, WindowIcon,
+};
+#[cfg(target_arch = "wasm32")]
 use std::panic;
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
-use winit::event_loop::EventLoop;
 
+/// The error type returned from the entry point
+#[cfg(target_arch = "wasm32")]
+pub type MainError = JsValue;
+/// The error type returned from the entry point
+#[cfg(not(target_arch = "wasm32"))]
+pub type MainError = String;
+
+// `inline` is ignored on functions exported to JavaScript. The lint is allowed rather than
+// expected there, as the export, not the function, is what it is checked against
+#[cfg_attr(
+    target_arch = "wasm32",
+    allow(
+        clippy::missing_inline_in_public_items,
+        reason = "`inline` is ignored on functions exported to JavaScript"
+    )
+)]
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
-#[inline]
-/// The global entry point. Initializes logging and begins the event loop.
+#[cfg_attr(not(target_arch = "wasm32"), inline)]
+/// The global entry point. Initializes logging and runs the simulation with the default
+/// configuration.
 ///
 /// # Errors
-/// Returns an error if configuring logging or configuring/starting the event loop fails
-pub fn main() -> Result<(), JsValue> {
-    if cfg!(target_arch = "wasm32") {
+/// Returns an error if configuring logging or running the simulation fails.
+/// Logging defaults to the `info` level, which can be changed with the `RUST_LOG` environment
+/// variable natively, or the `log` query parameter on the web
+#[cfg_attr(
+    not(target_arch = "wasm32"),
+    expect(
+        clippy::useless_conversion,
+        reason = "Errors only need converting on the web, where the error type is not `String`"
+    )
+)]
+pub fn main() -> Result<(), MainError> {
+    #[cfg(target_arch = "wasm32")]
+    {
         panic::set_hook(Box::new(console_error_panic_hook::hook));
-        if console_log::init_with_level(Level::Trace).is_err() {
-            return Err("Failed to attach to console".into());
-        }
-    } else {
-        env_logger::init();
     }
+    logging::init(logging::DEFAULT_LEVEL)
+        .map_err::<MainError, _>(|err| format!("Failed to initialize logging: {err}").into())?;
 
-    let event_loop = EventLoop::new()
-        .map_err::<JsValue, _>(|err| format!("Failed to create event loop: {err:?}").into())?;
-
-    if cfg!(target_arch = "wasm32") {
-        use winit::platform::web::EventLoopExtWebSys;
-        event_loop.spawn_app(AppWrapper::new());
-    } else {
-        event_loop
-            .run_app(&mut AppWrapper::new())
-            .map_err::<JsValue, _>(|err| format!("Failed to create event loop: {err:?}").into())?;
-    }
-    Ok(())
+    run(SimulationConfig::default())
+        .map_err::<MainError, _>(|err| format!("Failed to run the simulation: {err}").into())
 }
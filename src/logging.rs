@@ -0,0 +1,73 @@
+//! Setting up logging, at a level chosen when the simulation is launched
+
+use log::LevelFilter;
+
+/// The level logged at when none is requested
+pub(crate) const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+/// Parses a level name such as `debug` or `off`, ignoring case and surrounding whitespace.
+/// Returns `None` if the name is not a level
+#[cfg_attr(
+    all(not(target_arch = "wasm32"), not(test)),
+    expect(
+        dead_code,
+        reason = "Natively, `env_logger` parses the requested level itself"
+    )
+)]
+pub(crate) fn parse_level(name: &str) -> Option<LevelFilter> {
+    name.trim().parse().ok()
+}
+
+/// Returns the level requested by the `log` query parameter of the page's URL, as in
+/// `?log=debug`, or `None` if there is no valid request
+#[cfg(target_arch = "wasm32")]
+fn requested_level() -> Option<LevelFilter> {
+    let search = web_sys::window()?.location().search().ok()?;
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .find_map(|parameter| parameter.strip_prefix("log="))
+        .and_then(parse_level)
+}
+
+/// Starts logging to the browser console, at the level requested in the page's URL, or
+/// `default` if there is none
+///
+/// # Errors
+/// Returns an error if a logger is already attached
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn init(default: LevelFilter) -> Result<(), log::SetLoggerError> {
+    match requested_level().unwrap_or(default).to_level() {
+        Some(level) => console_log::init_with_level(level),
+        // Logging is turned off
+        None => Ok(()),
+    }
+}
+
+/// Starts logging to standard error, filtered by the `RUST_LOG` environment variable if it is set,
+/// or at `default` otherwise
+///
+/// # Errors
+/// Returns an error if a logger is already attached
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn init(default: LevelFilter) -> Result<(), log::SetLoggerError> {
+    env_logger::Builder::new()
+        .filter_level(default)
+        .parse_default_env()
+        .try_init()
+}
+
+#[cfg(test)]
+mod tests {
+    use log::LevelFilter;
+
+    use super::parse_level;
+
+    #[test]
+    fn levels_are_parsed_by_name() {
+        assert_eq!(parse_level("debug"), Some(LevelFilter::Debug));
+        assert_eq!(parse_level(" WARN "), Some(LevelFilter::Warn));
+        assert_eq!(parse_level("off"), Some(LevelFilter::Off));
+        assert_eq!(parse_level("loud"), None);
+    }
+}
@@ -0,0 +1,11 @@
+//! Desktop entry point, running the simulation in a native window
+//!
+//! On the web, the library's `main` is started by `wasm-bindgen` instead, so this has no entry
+//! point of its own
+
+#![cfg_attr(target_arch = "wasm32", no_main)]
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> Result<(), physical_simulation::MainError> {
+    physical_simulation::main()
+}